@@ -0,0 +1,99 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+/// Archive previews stop after this many entries.
+pub const ARCHIVE_PREVIEW_LIMIT: usize = 1000;
+/// Most bytes decompressed while listing a tar.gz, since skipping over the file contents
+/// means decompressing them.
+const TAR_GZ_READ_LIMIT: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// Detects an archive by file name, falling back to the sniffed content type.
+pub fn detect(path: &Path, buf: &[u8]) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        return Some(ArchiveKind::TarGz);
+    }
+    if name.ends_with(".tar") {
+        return Some(ArchiveKind::Tar);
+    }
+    if name.ends_with(".zip") || name.ends_with(".jar") {
+        return Some(ArchiveKind::Zip);
+    }
+    match infer::get(buf)?.mime_type() {
+        "application/zip" => Some(ArchiveKind::Zip),
+        "application/x-tar" => Some(ArchiveKind::Tar),
+        _ => None,
+    }
+}
+
+/// Lists up to `limit` entries without extracting anything. Zip archives are read from
+/// the central directory; tar archives are streamed header by header, a tar.gz only up to
+/// `TAR_GZ_READ_LIMIT` decompressed bytes.
+pub fn list(path: &Path, kind: ArchiveKind, limit: usize) -> io::Result<Vec<ArchiveEntry>> {
+    let file = BufReader::new(File::open(path)?);
+    let mut entries = match kind {
+        ArchiveKind::Zip => list_zip(file, limit)?,
+        ArchiveKind::Tar => list_tar(file, limit)?,
+        ArchiveKind::TarGz => list_tar(
+            flate2::bufread::GzDecoder::new(file).take(TAR_GZ_READ_LIMIT),
+            limit,
+        )?,
+    };
+    // Component-wise so a directory's children follow it directly.
+    entries.sort_by(|a, b| a.path.split('/').cmp(b.path.split('/')));
+    Ok(entries)
+}
+
+fn list_zip(file: BufReader<File>, limit: usize) -> io::Result<Vec<ArchiveEntry>> {
+    let mut archive = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+    let mut entries = Vec::new();
+    for index in 0..archive.len().min(limit) {
+        let entry = archive.by_index_raw(index).map_err(io::Error::other)?;
+        entries.push(ArchiveEntry {
+            path: entry.name().trim_end_matches('/').to_string(),
+            size: entry.size(),
+            is_dir: entry.is_dir(),
+        });
+    }
+    Ok(entries)
+}
+
+fn list_tar(reader: impl Read, limit: usize) -> io::Result<Vec<ArchiveEntry>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    for entry in archive.entries()?.take(limit) {
+        // A stream cut short by the read limit ends in an error; what was listed is kept.
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) if !entries.is_empty() => break,
+            Err(err) => return Err(err),
+        };
+        let header = entry.header();
+        let path = entry.path()?.to_string_lossy().to_string();
+        entries.push(ArchiveEntry {
+            path: path
+                .trim_start_matches("./")
+                .trim_end_matches('/')
+                .to_string(),
+            size: header.size().unwrap_or(0),
+            is_dir: header.entry_type().is_dir(),
+        });
+    }
+    entries.retain(|entry| !entry.path.is_empty());
+    Ok(entries)
+}
@@ -0,0 +1,128 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::Hint;
+
+/// Tags shown in audio previews, in display order.
+const SHOWN_TAGS: [(StandardTagKey, &str); 7] = [
+    (StandardTagKey::TrackTitle, "Title"),
+    (StandardTagKey::Artist, "Artist"),
+    (StandardTagKey::Album, "Album"),
+    (StandardTagKey::AlbumArtist, "Album artist"),
+    (StandardTagKey::TrackNumber, "Track"),
+    (StandardTagKey::Date, "Date"),
+    (StandardTagKey::Genre, "Genre"),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct AudioInfo {
+    /// Label and value of each tag found, in `SHOWN_TAGS` order.
+    pub tags: Vec<(&'static str, String)>,
+    pub duration: Option<Duration>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<usize>,
+}
+
+impl AudioInfo {
+    /// One `Label: value` line per known field; untagged files only get the stream details.
+    pub fn to_text(&self) -> String {
+        let mut lines: Vec<(&str, String)> = self.tags.clone();
+        if lines.is_empty() {
+            lines.push(("Tags", "none".to_string()));
+        }
+        if let Some(duration) = self.duration {
+            lines.push(("Duration", format_duration(duration)));
+        }
+        if let Some(rate) = self.sample_rate {
+            lines.push(("Sample rate", format!("{rate} Hz")));
+        }
+        if let Some(channels) = self.channels {
+            lines.push(("Channels", channels.to_string()));
+        }
+        let width = lines
+            .iter()
+            .map(|(label, _)| label.len() + 1)
+            .max()
+            .unwrap_or(0);
+        lines
+            .iter()
+            .map(|(label, value)| format!("{:<width$} {value}\n", format!("{label}:")))
+            .collect()
+    }
+}
+
+/// Reads the tags and stream parameters without decoding any audio. Tags may sit before
+/// the container (ID3v2 in front of an MP3) or inside it; the container's win.
+pub fn read(path: &Path) -> io::Result<AudioInfo> {
+    let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(io::Error::other)?;
+
+    let mut info = AudioInfo::default();
+    if let Some(revision) = probed.format.metadata().current() {
+        add_tags(&mut info, revision);
+    }
+    if let Some(revision) = probed
+        .metadata
+        .get()
+        .as_ref()
+        .and_then(|metadata| metadata.current())
+    {
+        add_tags(&mut info, revision);
+    }
+    info.tags
+        .sort_by_key(|(label, _)| SHOWN_TAGS.iter().position(|(_, shown)| shown == label));
+
+    if let Some(track) = probed.format.default_track() {
+        let params = &track.codec_params;
+        info.sample_rate = params.sample_rate;
+        info.channels = params.channels.map(|channels| channels.count());
+        info.duration = params
+            .time_base
+            .zip(params.n_frames)
+            .map(|(time_base, frames)| {
+                let time = time_base.calc_time(frames);
+                Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac)
+            });
+    }
+    Ok(info)
+}
+
+/// Adds the shown tags of `revision` that are not in `info` yet.
+fn add_tags(info: &mut AudioInfo, revision: &MetadataRevision) {
+    for tag in revision.tags() {
+        let Some((_, label)) = SHOWN_TAGS.iter().find(|(key, _)| tag.std_key == Some(*key)) else {
+            continue;
+        };
+        let value = tag.value.to_string();
+        let value = value.trim();
+        if value.is_empty() || info.tags.iter().any(|(shown, _)| shown == label) {
+            continue;
+        }
+        info.tags.push((label, value.to_string()));
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
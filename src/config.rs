@@ -8,22 +8,59 @@ use std::path::{Path, PathBuf};
 #[serde(default)]
 pub struct Config {
     pub check_mismatch: bool,
+    /// Terminal emulator started by `open_terminal`, e.g. `alacritty -e`; `$SHELL` is
+    /// appended. Empty falls back to the inline shell.
+    pub terminal_cmd: String,
+    /// Seconds a directory listing is reused on revisits while it is re-checked; 0 turns
+    /// the cache off.
+    pub listing_cache_secs: u64,
+    /// What opening a symlink to a directory does.
+    pub symlink_dirs: SymlinkDirs,
+    /// Re-list the current directory when files are added, removed or renamed in it.
+    pub watch_dir: bool,
+    /// Directory opened at startup when no path is given; empty uses the working directory.
+    pub start_dir: String,
     pub theme: Theme,
     pub icons: Icons,
     pub metadata_bar: MetadataBar,
     pub open_with: OpenWithConfig,
+    pub sort: SortConfig,
+    pub delete: DeleteConfig,
+    pub confirm: ConfirmConfig,
+    pub markers: MarkersConfig,
+    pub search: SearchConfig,
+    pub security: SecurityConfig,
+    pub layout: LayoutConfig,
+    pub preview: PreviewConfig,
     pub keys: KeyBindings,
+    /// File this config was loaded from (or written to), watched for live reloads.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             check_mismatch: false,
+            terminal_cmd: String::new(),
+            listing_cache_secs: 30,
+            symlink_dirs: SymlinkDirs::default(),
+            watch_dir: false,
+            start_dir: String::new(),
             theme: Theme::default(),
             icons: Icons::default(),
             metadata_bar: MetadataBar::default(),
             open_with: OpenWithConfig::default(),
+            sort: SortConfig::default(),
+            delete: DeleteConfig::default(),
+            confirm: ConfirmConfig::default(),
+            markers: MarkersConfig::default(),
+            search: SearchConfig::default(),
+            security: SecurityConfig::default(),
+            layout: LayoutConfig::default(),
+            preview: PreviewConfig::default(),
             keys: KeyBindings::default(),
+            source: None,
         }
     }
 }
@@ -31,26 +68,61 @@ impl Default for Config {
 impl Config {
     pub fn load() -> Result<Self, ConfigError> {
         let fallback = Self::default();
-        if let Ok(path) = env::var("TFM_CONFIG") {
-            let path = PathBuf::from(path);
-            if path.exists() {
-                return load_from_path(&path);
-            }
-            let _ = write_default_config(&path, &fallback);
+        let Some(path) = Self::resolve_path() else {
             return Ok(fallback);
+        };
+        if !path.exists() {
+            let _ = write_default_config(&path);
+            return Ok(Self {
+                source: Some(path),
+                ..fallback
+            });
         }
+        let mut config = load_from_path(&path)?;
+        config.source = Some(path);
+        Ok(config)
+    }
 
-        let paths = default_paths();
-        for path in &paths {
-            if path.exists() {
-                return load_from_path(path);
+    /// Reads the file this config came from again, without looking for another one.
+    pub fn reload(&self) -> Result<Self, ConfigError> {
+        let Some(path) = &self.source else {
+            return Ok(Self::default());
+        };
+        let mut config = load_from_path(path)?;
+        config.source = Some(path.clone());
+        Ok(config)
+    }
+
+    /// Writes the default config for `--init-config`: to `TFM_CONFIG` if set, else the
+    /// first default path. Refuses to replace an existing config unless `force` is set.
+    pub fn init(force: bool) -> Result<PathBuf, ConfigError> {
+        if !force {
+            if let Some(existing) = Self::resolve_path().filter(|path| path.exists()) {
+                return Err(ConfigError::Exists(existing));
             }
         }
-        if let Some(path) = paths.first() {
-            let _ = write_default_config(path, &fallback);
-        }
+        let path = match env::var("TFM_CONFIG") {
+            Ok(path) => PathBuf::from(path),
+            Err(_) => default_paths()
+                .into_iter()
+                .next()
+                .ok_or(ConfigError::NoLocation)?,
+        };
+        write_default_config(&path)?;
+        Ok(path)
+    }
 
-        Ok(fallback)
+    /// `TFM_CONFIG`, else the first existing default path, else where the default is written.
+    pub fn resolve_path() -> Option<PathBuf> {
+        if let Ok(path) = env::var("TFM_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        let paths = default_paths();
+        paths
+            .iter()
+            .find(|path| path.exists())
+            .or(paths.first())
+            .cloned()
     }
 }
 
@@ -63,8 +135,12 @@ pub struct Theme {
     pub selection_fg: String,
     pub accent: String,
     pub folder: String,
+    pub marked: String,
     pub warning: String,
     pub error: String,
+    /// Drop all colors; the selection is shown in reverse video. Also on when `NO_COLOR`
+    /// is set.
+    pub monochrome: bool,
 }
 
 impl Default for Theme {
@@ -76,15 +152,26 @@ impl Default for Theme {
             selection_fg: "black".to_string(),
             accent: "cyan".to_string(),
             folder: "lightblue".to_string(),
+            marked: "magenta".to_string(),
             warning: "yellow".to_string(),
             error: "red".to_string(),
+            monochrome: false,
         }
     }
 }
 
+impl Theme {
+    /// `monochrome`, or a non-empty `NO_COLOR` (see <https://no-color.org>).
+    pub fn is_monochrome(&self) -> bool {
+        self.monochrome || env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(default)]
+#[serde(from = "IconsFile", into = "IconsFile")]
 pub struct Icons {
+    /// Glyph set the icons not set individually come from.
+    pub preset: IconPreset,
     pub folder: String,
     pub file: String,
     pub text: String,
@@ -98,16 +185,101 @@ pub struct Icons {
 
 impl Default for Icons {
     fn default() -> Self {
+        Self::preset(IconPreset::default())
+    }
+}
+
+impl Icons {
+    pub fn preset(preset: IconPreset) -> Self {
+        let [folder, file, text, image, video, audio, archive, symlink, unknown] = match preset {
+            IconPreset::Nerd => ["󰉋", "󰈔", "󰈙", "󰈟", "󰕧", "󰎆", "󰀼", "󰌷", "󰈚"],
+            IconPreset::Ascii => ["/", " ", " ", " ", " ", " ", " ", "@", " "],
+        };
         Self {
-            folder: "󰉋".to_string(),
-            file: "󰈔".to_string(),
-            text: "󰈙".to_string(),
-            image: "󰈟".to_string(),
-            video: "󰕧".to_string(),
-            audio: "󰎆".to_string(),
-            archive: "󰀼".to_string(),
-            symlink: "󰌷".to_string(),
-            unknown: "󰈚".to_string(),
+            preset,
+            folder: folder.to_string(),
+            file: file.to_string(),
+            text: text.to_string(),
+            image: image.to_string(),
+            video: video.to_string(),
+            audio: audio.to_string(),
+            archive: archive.to_string(),
+            symlink: symlink.to_string(),
+            unknown: unknown.to_string(),
+        }
+    }
+}
+
+/// Built-in icon sets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconPreset {
+    /// Nerd Font glyphs.
+    #[default]
+    Nerd,
+    /// Plain characters for terminals without a Nerd Font.
+    Ascii,
+}
+
+/// `[icons]` as written in the config: icons left out come from the preset.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct IconsFile {
+    preset: IconPreset,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    folder: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    video: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audio: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archive: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symlink: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unknown: Option<String>,
+}
+
+impl From<IconsFile> for Icons {
+    fn from(file: IconsFile) -> Self {
+        let base = Icons::preset(file.preset);
+        Self {
+            preset: file.preset,
+            folder: file.folder.unwrap_or(base.folder),
+            file: file.file.unwrap_or(base.file),
+            text: file.text.unwrap_or(base.text),
+            image: file.image.unwrap_or(base.image),
+            video: file.video.unwrap_or(base.video),
+            audio: file.audio.unwrap_or(base.audio),
+            archive: file.archive.unwrap_or(base.archive),
+            symlink: file.symlink.unwrap_or(base.symlink),
+            unknown: file.unknown.unwrap_or(base.unknown),
+        }
+    }
+}
+
+/// Writes only the icons that differ from the preset.
+impl From<Icons> for IconsFile {
+    fn from(icons: Icons) -> Self {
+        let base = Icons::preset(icons.preset);
+        let changed = |icon: String, preset: String| (icon != preset).then_some(icon);
+        Self {
+            preset: icons.preset,
+            folder: changed(icons.folder, base.folder),
+            file: changed(icons.file, base.file),
+            text: changed(icons.text, base.text),
+            image: changed(icons.image, base.image),
+            video: changed(icons.video, base.video),
+            audio: changed(icons.audio, base.audio),
+            archive: changed(icons.archive, base.archive),
+            symlink: changed(icons.symlink, base.symlink),
+            unknown: changed(icons.unknown, base.unknown),
         }
     }
 }
@@ -119,6 +291,8 @@ pub struct MetadataBar {
     pub show_permissions: bool,
     pub show_dates: bool,
     pub show_owner: bool,
+    pub show_size: bool,
+    pub date_format: String,
     pub icons: MetadataIcons,
 }
 
@@ -129,6 +303,8 @@ impl Default for MetadataBar {
             show_permissions: true,
             show_dates: true,
             show_owner: true,
+            show_size: true,
+            date_format: "rfc3339".to_string(),
             icons: MetadataIcons::default(),
         }
     }
@@ -139,6 +315,7 @@ impl Default for MetadataBar {
 pub struct MetadataIcons {
     pub permissions: String,
     pub owner: String,
+    pub size: String,
     pub created: String,
     pub modified: String,
     pub accessed: String,
@@ -149,6 +326,7 @@ impl Default for MetadataIcons {
         Self {
             permissions: "󰌾".to_string(),
             owner: "󰉍".to_string(),
+            size: "󰋊".to_string(),
             created: "󰃰".to_string(),
             modified: "󰃯".to_string(),
             accessed: "󰃱".to_string(),
@@ -160,12 +338,198 @@ impl Default for MetadataIcons {
 #[serde(default)]
 pub struct OpenWithConfig {
     pub quick: HashMap<String, String>,
+    /// Save the `PATH` program scan so later launches can skip it while `PATH` is unchanged.
+    pub cache_programs: bool,
 }
 
 impl Default for OpenWithConfig {
     fn default() -> Self {
         Self {
             quick: HashMap::new(),
+            cache_programs: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SortConfig {
+    pub natural: bool,
+    /// List directories before files; when off, both are sorted together.
+    pub dirs_first: bool,
+}
+
+impl Default for SortConfig {
+    fn default() -> Self {
+        Self {
+            natural: false,
+            dirs_first: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DeleteConfig {
+    pub use_trash: bool,
+}
+
+impl Default for DeleteConfig {
+    fn default() -> Self {
+        Self { use_trash: true }
+    }
+}
+
+/// Which destructive actions ask before they run.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfirmConfig {
+    pub delete: bool,
+    /// Ask how to handle pasted files that already exist; otherwise they are overwritten.
+    pub overwrite: bool,
+    /// Ask before a cut-paste moves files to another filesystem, which copies them and
+    /// then deletes the originals.
+    pub move_across_filesystems: bool,
+}
+
+impl Default for ConfirmConfig {
+    fn default() -> Self {
+        Self {
+            delete: true,
+            overwrite: true,
+            move_across_filesystems: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MarkersConfig {
+    /// Open file markers with the default application after jumping to them.
+    pub open_files: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Number of past filters kept for Up/Down in the search prompt.
+    pub history_size: usize,
+    /// Keep the search history in a file next to the markers.
+    pub save_history: bool,
+    /// Levels of subdirectories the flatten view lists below the current directory.
+    pub flatten_depth: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            history_size: 50,
+            save_history: false,
+            flatten_depth: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SecurityConfig {
+    /// What to do when `check_mismatch` finds content that does not fit the extension.
+    pub on_mismatch: MismatchAction,
+    /// Extra groups of extensions to treat as the same content, e.g. `[["raw", "dng"]]`.
+    pub equivalent_extensions: Vec<Vec<String>>,
+    /// Ask before opening executables and scripts with a `#!` line.
+    pub confirm_executables: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MismatchAction {
+    /// Mark the preview title.
+    #[default]
+    Warn,
+    /// Ask before opening a mismatched file.
+    Confirm,
+    /// Also show the detected type of every file in the metadata bar.
+    Show,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkDirs {
+    /// Enter the link itself, so going up returns to the directory holding the link.
+    #[default]
+    Follow,
+    /// Jump to the resolved target in its real parent directory.
+    Reveal,
+}
+
+/// Relative widths of the parent, current and preview panels.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    pub parent: u16,
+    pub current: u16,
+    pub preview: u16,
+    pub show_parent: bool,
+}
+
+impl LayoutConfig {
+    pub fn is_valid(&self) -> bool {
+        (self.parent > 0 || !self.show_parent) && self.current > 0 && self.preview > 0
+    }
+
+    /// Panel ratios with a hidden parent set to zero, or the defaults when invalid.
+    pub fn ratios(&self) -> [u16; 3] {
+        let layout = if self.is_valid() {
+            self.clone()
+        } else {
+            Self {
+                show_parent: self.show_parent,
+                ..Self::default()
+            }
+        };
+        let parent = if layout.show_parent { layout.parent } else { 0 };
+        [parent, layout.current, layout.preview]
+    }
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            parent: 25,
+            current: 35,
+            preview: 40,
+            show_parent: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PreviewConfig {
+    pub line_numbers: bool,
+    pub wrap: bool,
+    pub max_bytes: usize,
+    pub syntax_theme: String,
+    /// Extensions of files previewed from the end when larger than `max_bytes`.
+    pub tail_extensions: Vec<String>,
+    /// Show Markdown files formatted instead of as highlighted source.
+    pub render_markdown: bool,
+    /// Larger images are scaled down to about this many pixels once decoded, so the
+    /// preview keeps only the small copy; 0 keeps the full size.
+    pub max_image_pixels: u64,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self {
+            line_numbers: false,
+            wrap: false,
+            max_bytes: 65536,
+            syntax_theme: "base16-ocean.dark".to_string(),
+            tail_extensions: vec!["log".to_string()],
+            render_markdown: true,
+            max_image_pixels: 4_000_000,
         }
     }
 }
@@ -179,8 +543,12 @@ pub struct KeyBindings {
     pub view: ViewKeys,
     pub copy: CopyKeys,
     pub delete: DeleteKeys,
+    pub sort: SortKeys,
     pub marker_list: MarkerListKeys,
     pub open_with: OpenWithKeys,
+    pub search_results: SearchResultsKeys,
+    pub preview: PreviewKeys,
+    pub palette: PaletteKeys,
 }
 
 impl Default for KeyBindings {
@@ -192,8 +560,12 @@ impl Default for KeyBindings {
             view: ViewKeys::default(),
             copy: CopyKeys::default(),
             delete: DeleteKeys::default(),
+            sort: SortKeys::default(),
             marker_list: MarkerListKeys::default(),
             open_with: OpenWithKeys::default(),
+            search_results: SearchResultsKeys::default(),
+            preview: PreviewKeys::default(),
+            palette: PaletteKeys::default(),
         }
     }
 }
@@ -207,6 +579,7 @@ pub struct NormalKeys {
     pub parent: Vec<String>,
     pub open: Vec<String>,
     pub search: Vec<String>,
+    pub fuzzy_search: Vec<String>,
     pub add: Vec<String>,
     pub rename: Vec<String>,
     pub delete: Vec<String>,
@@ -221,6 +594,32 @@ pub struct NormalKeys {
     pub open_shell: Vec<String>,
     pub open_with_picker: Vec<String>,
     pub open_with_quick: Vec<String>,
+    pub sort: Vec<String>,
+    pub content_search: Vec<String>,
+    pub quick_marker_set: Vec<String>,
+    pub quick_marker_jump: Vec<String>,
+    pub cancel: Vec<String>,
+    pub toggle_mark: Vec<String>,
+    pub undo: Vec<String>,
+    pub bulk_rename: Vec<String>,
+    pub edit: Vec<String>,
+    pub focus_preview: Vec<String>,
+    pub top: Vec<String>,
+    pub bottom: Vec<String>,
+    pub page_up: Vec<String>,
+    pub page_down: Vec<String>,
+    pub half_page_up: Vec<String>,
+    pub half_page_down: Vec<String>,
+    pub chmod: Vec<String>,
+    pub open_terminal: Vec<String>,
+    pub paste_system: Vec<String>,
+    pub clear_filter: Vec<String>,
+    pub mismatch_scan: Vec<String>,
+    pub reveal_link: Vec<String>,
+    pub file_manager: Vec<String>,
+    pub command_palette: Vec<String>,
+    pub dir_size: Vec<String>,
+    pub go_to_path: Vec<String>,
 }
 
 impl Default for NormalKeys {
@@ -232,6 +631,7 @@ impl Default for NormalKeys {
             parent: vec!["left".to_string(), "h".to_string()],
             open: vec!["right".to_string(), "l".to_string(), "enter".to_string()],
             search: vec!["/".to_string()],
+            fuzzy_search: vec!["alt+/".to_string()],
             add: vec!["a".to_string()],
             rename: vec!["r".to_string()],
             delete: vec!["d".to_string()],
@@ -246,6 +646,32 @@ impl Default for NormalKeys {
             open_shell: vec!["t".to_string()],
             open_with_picker: vec!["ctrl+o".to_string(), "O".to_string()],
             open_with_quick: vec!["o".to_string()],
+            sort: vec!["S".to_string()],
+            content_search: vec!["F".to_string()],
+            quick_marker_set: vec!["alt+m".to_string()],
+            quick_marker_jump: vec!["'".to_string()],
+            cancel: vec!["esc".to_string()],
+            toggle_mark: vec!["space".to_string()],
+            undo: vec!["u".to_string()],
+            bulk_rename: vec!["R".to_string()],
+            edit: vec!["e".to_string()],
+            focus_preview: vec!["tab".to_string()],
+            top: vec!["home".to_string()],
+            bottom: vec!["end".to_string(), "G".to_string()],
+            page_up: vec!["pageup".to_string()],
+            page_down: vec!["pagedown".to_string()],
+            half_page_up: vec!["ctrl+u".to_string()],
+            half_page_down: vec!["ctrl+d".to_string()],
+            chmod: vec!["alt+x".to_string()],
+            open_terminal: vec!["T".to_string()],
+            paste_system: vec!["P".to_string()],
+            clear_filter: vec!["alt+c".to_string()],
+            mismatch_scan: vec!["!".to_string()],
+            reveal_link: vec!["L".to_string()],
+            file_manager: vec!["E".to_string()],
+            command_palette: vec![":".to_string()],
+            dir_size: vec!["D".to_string()],
+            go_to_path: vec!["ctrl+l".to_string()],
         }
     }
 }
@@ -270,8 +696,11 @@ pub struct SettingsKeys {
     pub toggle_permissions: Vec<String>,
     pub toggle_dates: Vec<String>,
     pub toggle_owner: Vec<String>,
+    pub toggle_size: Vec<String>,
     pub toggle_metadata: Vec<String>,
     pub toggle_hidden: Vec<String>,
+    pub toggle_sticky_filter: Vec<String>,
+    pub toggle_relative_dates: Vec<String>,
 }
 
 impl Default for SettingsKeys {
@@ -280,8 +709,11 @@ impl Default for SettingsKeys {
             toggle_permissions: vec!["r".to_string()],
             toggle_dates: vec!["d".to_string()],
             toggle_owner: vec!["o".to_string()],
+            toggle_size: vec!["s".to_string()],
             toggle_metadata: vec!["m".to_string()],
             toggle_hidden: vec!["h".to_string(), "H".to_string()],
+            toggle_sticky_filter: vec!["f".to_string()],
+            toggle_relative_dates: vec!["t".to_string()],
         }
     }
 }
@@ -291,6 +723,10 @@ impl Default for SettingsKeys {
 pub struct ViewKeys {
     pub toggle_list_permissions: Vec<String>,
     pub toggle_list_owner: Vec<String>,
+    pub toggle_list_size: Vec<String>,
+    pub toggle_preview_wrap: Vec<String>,
+    pub toggle_flatten: Vec<String>,
+    pub toggle_dual_pane: Vec<String>,
 }
 
 impl Default for ViewKeys {
@@ -298,6 +734,10 @@ impl Default for ViewKeys {
         Self {
             toggle_list_permissions: vec!["p".to_string()],
             toggle_list_owner: vec!["o".to_string()],
+            toggle_list_size: vec!["s".to_string()],
+            toggle_preview_wrap: vec!["w".to_string()],
+            toggle_flatten: vec!["f".to_string()],
+            toggle_dual_pane: vec!["d".to_string()],
         }
     }
 }
@@ -306,12 +746,22 @@ impl Default for ViewKeys {
 #[serde(default)]
 pub struct CopyKeys {
     pub copy_path: Vec<String>,
+    pub copy_name: Vec<String>,
+    pub copy_relative_path: Vec<String>,
+    pub copy_parent_path: Vec<String>,
+    pub copy_contents: Vec<String>,
+    pub duplicate: Vec<String>,
 }
 
 impl Default for CopyKeys {
     fn default() -> Self {
         Self {
             copy_path: vec!["p".to_string()],
+            copy_name: vec!["n".to_string()],
+            copy_relative_path: vec!["r".to_string()],
+            copy_parent_path: vec!["P".to_string()],
+            copy_contents: vec!["c".to_string()],
+            duplicate: vec!["d".to_string()],
         }
     }
 }
@@ -330,6 +780,26 @@ impl Default for DeleteKeys {
     }
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SortKeys {
+    pub by_name: Vec<String>,
+    pub by_size: Vec<String>,
+    pub by_modified: Vec<String>,
+    pub reverse: Vec<String>,
+}
+
+impl Default for SortKeys {
+    fn default() -> Self {
+        Self {
+            by_name: vec!["n".to_string()],
+            by_size: vec!["z".to_string()],
+            by_modified: vec!["m".to_string()],
+            reverse: vec!["r".to_string()],
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct MarkerListKeys {
@@ -342,6 +812,8 @@ pub struct MarkerListKeys {
     pub delete: Vec<String>,
     pub add: Vec<String>,
     pub search: Vec<String>,
+    pub export: Vec<String>,
+    pub import: Vec<String>,
 }
 
 impl Default for MarkerListKeys {
@@ -356,6 +828,8 @@ impl Default for MarkerListKeys {
             delete: vec!["d".to_string()],
             add: vec!["a".to_string()],
             search: vec!["/".to_string()],
+            export: vec!["x".to_string()],
+            import: vec!["i".to_string()],
         }
     }
 }
@@ -382,12 +856,85 @@ impl Default for OpenWithKeys {
     }
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SearchResultsKeys {
+    pub close: Vec<String>,
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+    pub open: Vec<String>,
+}
+
+impl Default for SearchResultsKeys {
+    fn default() -> Self {
+        Self {
+            close: vec!["esc".to_string()],
+            up: vec!["up".to_string(), "k".to_string()],
+            down: vec!["down".to_string(), "j".to_string()],
+            open: vec!["enter".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PreviewKeys {
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+    pub page_up: Vec<String>,
+    pub page_down: Vec<String>,
+    pub close: Vec<String>,
+}
+
+impl Default for PreviewKeys {
+    fn default() -> Self {
+        Self {
+            up: vec!["up".to_string(), "k".to_string()],
+            down: vec!["down".to_string(), "j".to_string()],
+            page_up: vec!["pageup".to_string()],
+            page_down: vec!["pagedown".to_string()],
+            close: vec![
+                "esc".to_string(),
+                "tab".to_string(),
+                "left".to_string(),
+                "h".to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PaletteKeys {
+    pub close: Vec<String>,
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+    pub run: Vec<String>,
+    pub backspace: Vec<String>,
+}
+
+impl Default for PaletteKeys {
+    fn default() -> Self {
+        Self {
+            close: vec!["esc".to_string()],
+            up: vec!["up".to_string()],
+            down: vec!["down".to_string()],
+            run: vec!["enter".to_string()],
+            backspace: vec!["backspace".to_string()],
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
     #[error("config file not found: {0}")]
     Missing(PathBuf),
     #[error("failed to read config file: {0}")]
     Io(#[from] std::io::Error),
+    #[error("config file already exists: {0} (use --force to overwrite)")]
+    Exists(PathBuf),
+    #[error("no config directory found; set TFM_CONFIG")]
+    NoLocation,
     #[error("unsupported config format: {0}")]
     UnsupportedFormat(PathBuf),
     #[error("toml parse error: {0}")]
@@ -411,15 +958,28 @@ fn load_from_path(path: &Path) -> Result<Config, ConfigError> {
     }
 }
 
-fn write_default_config(path: &Path, config: &Config) -> std::io::Result<()> {
+/// The default config written to TOML paths: every option at its default, each with a
+/// comment on what it does.
+const DEFAULT_CONFIG_TOML: &str = include_str!("../example_config.toml");
+
+const DEFAULT_YAML_HEADER: &str = "\
+# tfm configuration, generated from the built-in defaults.
+# Every option is listed with its default value; delete the ones you do not change.
+# example_config.toml in the tfm sources describes each one.
+
+";
+
+fn write_default_config(path: &Path) -> std::io::Result<()> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
     let content = match path.extension().and_then(|ext| ext.to_str()) {
-        Some("yaml") | Some("yml") => serde_yaml::to_string(config)
-            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))?,
-        _ => toml::to_string_pretty(config)
-            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))?,
+        Some("yaml") | Some("yml") => {
+            let body = serde_yaml::to_string(&Config::default())
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))?;
+            format!("{DEFAULT_YAML_HEADER}{body}")
+        }
+        _ => DEFAULT_CONFIG_TOML.to_string(),
     };
     fs::write(path, content)
 }
@@ -1,8 +1,17 @@
 use crate::config::Config;
 use crate::preview::{self, Preview};
+use regex::Regex;
 use std::cmp::Ordering;
+use std::env;
+use std::iter::Peekable;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::str::Chars;
+use std::sync::atomic::{self, AtomicBool, AtomicU64, AtomicUsize};
+use std::task::{Context, Poll};
+use std::time::SystemTime;
 use tokio::fs;
+use tokio::io::{AsyncRead, ReadBuf};
 use tokio_stream::wrappers::ReadDirStream;
 
 #[cfg(unix)]
@@ -13,25 +22,185 @@ pub struct FileEntry {
     pub name: String,
     pub path: PathBuf,
     pub is_dir: bool,
+    pub is_symlink: bool,
+    pub link_target: Option<PathBuf>,
+    /// Set for symlinks whose target does not exist.
+    pub broken_link: bool,
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+    /// Only gathered while the list size column is shown.
+    pub child_count: Option<ChildCount>,
     pub permissions: String,
     pub owner: String,
+    /// `None` for regular files without an extension until their content is sniffed,
+    /// which only happens for a `type:` filter.
+    pub category: Option<FileCategory>,
+}
+
+/// Number of entries in a directory, for the list size column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildCount {
+    Counted(usize),
+    /// The directory could not be read.
+    Unreadable,
+}
+
+/// Coarse file type used by `type:` filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileCategory {
+    Dir,
+    Image,
+    Video,
+    Audio,
+    Archive,
+    Document,
+    Text,
+    Other,
+}
+
+impl FileCategory {
+    /// Parses a `type:` filter value; a few common aliases are accepted.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let category = match name.to_ascii_lowercase().as_str() {
+            "dir" | "directory" | "folder" => Self::Dir,
+            "image" | "img" => Self::Image,
+            "video" => Self::Video,
+            "audio" | "music" => Self::Audio,
+            "archive" => Self::Archive,
+            "document" | "doc" => Self::Document,
+            "text" | "code" => Self::Text,
+            "other" => Self::Other,
+            _ => return None,
+        };
+        Some(category)
+    }
+
+    fn from_extension(extension: &str) -> Option<Self> {
+        let category = match extension.to_ascii_lowercase().as_str() {
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "svg" | "ico" | "tif" | "tiff"
+            | "avif" | "heic" => Self::Image,
+            "mp4" | "mkv" | "webm" | "avi" | "mov" | "wmv" | "flv" | "m4v" => Self::Video,
+            "mp3" | "flac" | "wav" | "ogg" | "oga" | "opus" | "m4a" | "aac" | "wma" => Self::Audio,
+            "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "deb" | "rpm" => {
+                Self::Archive
+            }
+            "pdf" | "doc" | "docx" | "odt" | "rtf" | "xls" | "xlsx" | "ods" | "ppt" | "pptx"
+            | "odp" | "epub" => Self::Document,
+            "txt" | "md" | "rst" | "log" | "csv" | "json" | "toml" | "yaml" | "yml" | "xml"
+            | "html" | "css" | "ini" | "cfg" | "conf" | "sh" | "rs" | "py" | "js" | "ts" | "go"
+            | "c" | "h" | "cpp" | "hpp" | "java" | "rb" | "lua" => Self::Text,
+            _ => return None,
+        };
+        Some(category)
+    }
+
+    fn from_matcher(matcher: infer::MatcherType) -> Self {
+        match matcher {
+            infer::MatcherType::Image => Self::Image,
+            infer::MatcherType::Video => Self::Video,
+            infer::MatcherType::Audio => Self::Audio,
+            infer::MatcherType::Archive => Self::Archive,
+            infer::MatcherType::Doc | infer::MatcherType::Book => Self::Document,
+            infer::MatcherType::Text => Self::Text,
+            _ => Self::Other,
+        }
+    }
+
+    /// Classifies by extension without touching the file. Regular files without one
+    /// give `None` and need `sniff`; other special files are `Other`.
+    fn classify(path: &Path, metadata: &std::fs::Metadata) -> Option<Self> {
+        if metadata.is_dir() {
+            return Some(Self::Dir);
+        }
+        match path.extension() {
+            Some(extension) => {
+                Some(Self::from_extension(&extension.to_string_lossy()).unwrap_or(Self::Other))
+            }
+            None if metadata.is_file() => None,
+            None => Some(Self::Other),
+        }
+    }
+
+    /// Classifies a file without an extension by its content, like the preview does.
+    /// Only regular files are opened: FIFOs would block and device nodes can react.
+    pub async fn sniff(path: &Path) -> Self {
+        if !fs::metadata(path)
+            .await
+            .is_ok_and(|metadata| metadata.is_file())
+        {
+            return Self::Other;
+        }
+        sniff_matcher(path)
+            .await
+            .map_or(Self::Other, Self::from_matcher)
+    }
+}
+
+/// Enough of the file for `infer`'s magic numbers (tar needs 262 bytes).
+const SNIFF_BYTES: u64 = 512;
+
+async fn sniff_matcher(path: &Path) -> Option<infer::MatcherType> {
+    use tokio::io::AsyncReadExt;
+
+    let file = fs::File::open(path).await.ok()?;
+    let mut buf = Vec::with_capacity(SNIFF_BYTES as usize);
+    file.take(SNIFF_BYTES).read_to_end(&mut buf).await.ok()?;
+    infer::get(&buf).map(|kind| kind.matcher_type())
 }
 
 impl FileEntry {
     pub async fn from_dir_entry(entry: fs::DirEntry) -> Result<Self, std::io::Error> {
         let file_type = entry.file_type().await?;
-        let metadata = entry.metadata().await?;
+        let mut metadata = entry.metadata().await?;
         let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        let is_symlink = file_type.is_symlink();
+        let mut link_target = None;
+        let mut broken_link = false;
+        if is_symlink {
+            link_target = fs::read_link(&path).await.ok();
+            // Describe links by what they point at so linked directories can be entered.
+            match fs::metadata(&path).await {
+                Ok(target) => metadata = target,
+                Err(_) => broken_link = true,
+            }
+        }
+        let is_dir = metadata.is_dir();
+        let category = if broken_link {
+            Some(FileCategory::Other)
+        } else {
+            FileCategory::classify(&path, &metadata)
+        };
         Ok(FileEntry {
             name,
-            path: entry.path(),
-            is_dir: file_type.is_dir(),
+            path,
+            is_dir,
+            is_symlink,
+            link_target,
+            broken_link,
+            size: (!is_dir).then_some(metadata.len()),
+            modified: metadata.modified().ok(),
+            child_count: None,
             permissions: permissions_string(&metadata),
             owner: owner_string(&metadata),
+            category,
         })
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ContentMatch {
+    pub path: PathBuf,
+    pub relative: String,
+    pub line: usize,
+    pub text: String,
+}
+
+const CONTENT_SEARCH_MAX_FILE_SIZE: u64 = 8 * 1024 * 1024;
+const CONTENT_SEARCH_MAX_FILES: usize = 100_000;
+const CONTENT_SEARCH_BATCH_SIZE: usize = 64;
+const CONTENT_SEARCH_LINE_WIDTH: usize = 200;
+
 #[derive(Debug, thiserror::Error)]
 pub enum CoreError {
     #[error("failed to read directory: {0}")]
@@ -40,35 +209,327 @@ pub enum CoreError {
     Preview(#[from] preview::PreviewError),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    Name,
+    Size,
+    Modified,
+}
+
+impl SortMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Name => "name",
+            SortMode::Size => "size",
+            SortMode::Modified => "modified",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SortOptions {
+    pub mode: SortMode,
+    pub reverse: bool,
+    pub natural: bool,
+    pub dirs_first: bool,
+    /// Put dotfiles after all other entries.
+    pub hidden_last: bool,
+}
+
 pub async fn read_dir_stream(path: &Path) -> Result<ReadDirStream, CoreError> {
     Ok(ReadDirStream::new(fs::read_dir(path).await?))
 }
 
-pub fn sort_entries(entries: &mut [FileEntry]) {
-    entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-        (true, false) => Ordering::Less,
-        (false, true) => Ordering::Greater,
-        _ => a
-            .name
-            .to_ascii_lowercase()
-            .cmp(&b.name.to_ascii_lowercase()),
+/// Counts the entries of the directory at `path`; this blocks while it is read.
+pub fn count_children(path: &Path) -> ChildCount {
+    match std::fs::read_dir(path) {
+        Ok(entries) => ChildCount::Counted(entries.count()),
+        Err(_) => ChildCount::Unreadable,
+    }
+}
+
+pub fn sort_entries(entries: &mut [FileEntry], options: SortOptions) {
+    // Entries without a size, i.e. directories, can't be ordered by size; the size sort
+    // puts them after the others, by name in either direction.
+    let unmeasured = |entry: &FileEntry| options.mode == SortMode::Size && entry.size.is_none();
+    let group = |entry: &FileEntry| {
+        (
+            options.hidden_last && entry.name.starts_with('.'),
+            options.dirs_first && !entry.is_dir,
+            unmeasured(entry),
+        )
+    };
+    entries.sort_by(|a, b| {
+        group(a).cmp(&group(b)).then_with(|| {
+            if unmeasured(a) {
+                compare_names(a, b, options)
+            } else {
+                options.directed(compare_entries(a, b, options))
+            }
+        })
     });
 }
 
-pub async fn load_preview(path: &Path, config: &Config) -> Result<Preview, CoreError> {
-    Ok(preview::load(path, config).await?)
+impl SortOptions {
+    fn directed(self, ordering: Ordering) -> Ordering {
+        if self.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+fn compare_entries(a: &FileEntry, b: &FileEntry, options: SortOptions) -> Ordering {
+    let by_name = || compare_names(a, b, options);
+    match options.mode {
+        SortMode::Name => by_name(),
+        SortMode::Size => b.size.cmp(&a.size).then_with(by_name),
+        SortMode::Modified => b.modified.cmp(&a.modified).then_with(by_name),
+    }
+}
+
+fn compare_names(a: &FileEntry, b: &FileEntry, options: SortOptions) -> Ordering {
+    if options.natural {
+        natural_cmp(&a.name, &b.name)
+    } else {
+        a.name
+            .to_ascii_lowercase()
+            .cmp(&b.name.to_ascii_lowercase())
+    }
+}
+
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut left = a.chars().peekable();
+    let mut right = b.chars().peekable();
+    loop {
+        match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let ordering = compare_digit_runs(&mut left, &mut right);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(l), Some(r)) => {
+                let ordering = l.to_ascii_lowercase().cmp(&r.to_ascii_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                left.next();
+                right.next();
+            }
+        }
+    }
+}
+
+fn compare_digit_runs(left: &mut Peekable<Chars>, right: &mut Peekable<Chars>) -> Ordering {
+    while left.peek() == Some(&'0') {
+        left.next();
+    }
+    while right.peek() == Some(&'0') {
+        right.next();
+    }
+    // Longer runs are larger numbers; equal-length runs compare by their first differing digit.
+    let mut ordering = Ordering::Equal;
+    loop {
+        let l = left.peek().copied().filter(|ch| ch.is_ascii_digit());
+        let r = right.peek().copied().filter(|ch| ch.is_ascii_digit());
+        match (l, r) {
+            (None, None) => return ordering,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) => {
+                if ordering == Ordering::Equal {
+                    ordering = l.cmp(&r);
+                }
+                left.next();
+                right.next();
+            }
+        }
+    }
+}
+
+pub async fn load_preview(
+    path: &Path,
+    config: &Config,
+    sort: SortOptions,
+    show_hidden: bool,
+) -> Result<Preview, CoreError> {
+    Ok(preview::load(path, config, sort, show_hidden).await?)
+}
+
+/// Creates an empty file; an existing file is an error rather than being truncated.
+/// Returns the parent directories that had to be created, outermost first.
+pub async fn create_file(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let parents = create_parents(path).await?;
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .await?;
+    Ok(parents)
+}
+
+/// Expands `$VAR`/`${VAR}` and a leading `~` or `~user` in a user-entered path. Unset
+/// variables and unknown users are left as typed.
+pub fn expand_path(input: &str) -> PathBuf {
+    expand_tilde(&expand_vars(input))
+}
+
+fn expand_vars(input: &str) -> String {
+    let mut expanded = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(index) = rest.find('$') {
+        expanded.push_str(&rest[..index]);
+        let after = &rest[index + 1..];
+        let (name, consumed) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after
+                    .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        match env::var(name).ok().filter(|_| !name.is_empty()) {
+            Some(value) => {
+                expanded.push_str(&value);
+                rest = &after[consumed..];
+            }
+            None => {
+                expanded.push('$');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
 }
 
-pub async fn create_file(path: &Path) -> std::io::Result<()> {
-    fs::File::create(path).await.map(|_| ())
+/// Completions for the last component of a partly typed path: the byte offset where
+/// that component starts and the sorted names in its directory that begin with it,
+/// directories ending in `/`. Relative input is resolved against `base`, `~` and `$VAR`
+/// are expanded, and hidden entries need a leading `.` typed.
+pub fn path_completions(input: &str, base: &Path) -> (usize, Vec<String>) {
+    let start = input.rfind('/').map_or(0, |index| index + 1);
+    let (head, partial) = input.split_at(start);
+    let Ok(entries) = std::fs::read_dir(base.join(expand_path(head))) else {
+        return (start, Vec::new());
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(partial) || (name.starts_with('.') && !partial.starts_with('.')) {
+                return None;
+            }
+            Some(if entry.path().is_dir() {
+                format!("{name}/")
+            } else {
+                name
+            })
+        })
+        .collect();
+    names.sort();
+    (start, names)
 }
 
-pub async fn create_dir(path: &Path) -> std::io::Result<()> {
-    fs::create_dir(path).await
+/// The longest prefix shared by all `names`.
+pub fn common_prefix(names: &[String]) -> &str {
+    let Some((first, rest)) = names.split_first() else {
+        return "";
+    };
+    rest.iter().fold(first.as_str(), |common, name| {
+        let len = common
+            .chars()
+            .zip(name.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .sum();
+        &common[..len]
+    })
+}
+
+fn expand_tilde(input: &str) -> PathBuf {
+    let Some(rest) = input.strip_prefix('~') else {
+        return PathBuf::from(input);
+    };
+    let (user, tail) = match rest.find(std::path::is_separator) {
+        Some(index) => (&rest[..index], &rest[index + 1..]),
+        None => (rest, ""),
+    };
+    let home = if user.is_empty() {
+        dirs::home_dir()
+    } else {
+        user_home(user)
+    };
+    match home {
+        Some(home) if tail.is_empty() => home,
+        Some(home) => home.join(tail),
+        None => PathBuf::from(input),
+    }
+}
+
+#[cfg(unix)]
+fn user_home(name: &str) -> Option<PathBuf> {
+    use uzers::os::unix::UserExt;
+    uzers::get_user_by_name(name).map(|user| user.home_dir().to_path_buf())
+}
+
+#[cfg(not(unix))]
+fn user_home(_: &str) -> Option<PathBuf> {
+    None
+}
+
+/// Like `create_file`, for a directory.
+pub async fn create_dir(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let parents = create_parents(path).await?;
+    fs::create_dir(path).await?;
+    Ok(parents)
+}
+
+/// Creates the missing ancestors of `path` and returns them, outermost first.
+async fn create_parents(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let Some(parent) = path.parent() else {
+        return Ok(Vec::new());
+    };
+    let mut missing: Vec<PathBuf> = parent
+        .ancestors()
+        .take_while(|ancestor| !ancestor.as_os_str().is_empty() && !ancestor.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    missing.reverse();
+    fs::create_dir_all(parent).await?;
+    Ok(missing)
+}
+
+/// Undoes a create: removes `path` only while it is still as created, an empty
+/// directory or an empty file. Anything else is left in place and reported.
+pub async fn remove_created(path: &Path) -> std::io::Result<()> {
+    let metadata = fs::symlink_metadata(path).await?;
+    if metadata.is_dir() {
+        // `remove_dir` refuses directories that are not empty.
+        if fs::remove_dir(path).await.is_ok() {
+            return Ok(());
+        }
+    } else if metadata.is_file() && metadata.len() == 0 {
+        return fs::remove_file(path).await;
+    }
+    Err(std::io::Error::other(format!(
+        "{} changed since creation, not undone",
+        path.display()
+    )))
 }
 
 pub async fn remove_path(path: &Path) -> std::io::Result<()> {
-    let metadata = fs::metadata(path).await?;
+    // Not followed, so broken links can be removed and linked directories stay intact.
+    let metadata = fs::symlink_metadata(path).await?;
     if metadata.is_dir() {
         fs::remove_dir_all(path).await
     } else {
@@ -76,16 +537,331 @@ pub async fn remove_path(path: &Path) -> std::io::Result<()> {
     }
 }
 
+/// Moves `path` to the platform trash. Returns an `Unsupported` error where no trash
+/// implementation exists so callers can refuse instead of deleting permanently.
+#[cfg(any(
+    windows,
+    target_os = "macos",
+    all(unix, not(any(target_os = "ios", target_os = "android")))
+))]
+pub async fn trash_path(path: &Path) -> std::io::Result<()> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || trash::delete(&path))
+        .await
+        .map_err(std::io::Error::other)?
+        .map_err(|err| std::io::Error::other(err.to_string()))
+}
+
+#[cfg(not(any(
+    windows,
+    target_os = "macos",
+    all(unix, not(any(target_os = "ios", target_os = "android")))
+)))]
+pub async fn trash_path(_: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "moving to trash is not supported on this platform",
+    ))
+}
+
+/// Restores the most recently trashed item that was deleted from `path`.
+#[cfg(any(
+    windows,
+    all(
+        unix,
+        not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+    )
+))]
+pub async fn restore_from_trash(path: &Path) -> std::io::Result<()> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let item = trash::os_limited::list()
+            .map_err(|err| std::io::Error::other(err.to_string()))?
+            .into_iter()
+            .filter(|item| item.original_path() == path)
+            .max_by_key(|item| item.time_deleted)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "item is no longer in the trash",
+                )
+            })?;
+        trash::os_limited::restore_all([item]).map_err(|err| std::io::Error::other(err.to_string()))
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+#[cfg(not(any(
+    windows,
+    all(
+        unix,
+        not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+    )
+)))]
+pub async fn restore_from_trash(_: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "restoring from trash is not supported on this platform",
+    ))
+}
+
 pub async fn rename_path(src: &Path, dest: &Path) -> std::io::Result<()> {
     fs::rename(src, dest).await
 }
 
-pub async fn copy_recursively(src: &Path, dest: &Path) -> std::io::Result<()> {
+/// Changes the permissions of `path` like `chmod`: `mode` is either octal (`755`) or
+/// comma-separated symbolic clauses (`+x`, `u+x,go-w`, `a=r`).
+#[cfg(unix)]
+pub async fn set_mode(path: &Path, mode: &str) -> std::io::Result<()> {
+    let metadata = fs::metadata(path).await?;
+    let current = metadata.permissions().mode() & 0o7777;
+    let Some(new_mode) = apply_mode(mode, current, metadata.is_dir()) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid mode `{mode}`"),
+        ));
+    };
+    fs::set_permissions(path, std::fs::Permissions::from_mode(new_mode)).await
+}
+
+#[cfg(not(unix))]
+pub async fn set_mode(_: &Path, _: &str) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "chmod is only supported on Unix",
+    ))
+}
+
+/// Applies a chmod mode string to `current`. A clause without `ugoa` affects everyone;
+/// `X` only adds execute bits to directories and files that already have one.
+#[cfg(unix)]
+fn apply_mode(spec: &str, current: u32, is_dir: bool) -> Option<u32> {
+    const OPS: [char; 3] = ['+', '-', '='];
+    if !spec.is_empty() && spec.chars().all(|ch| ch.is_digit(8)) {
+        return u32::from_str_radix(spec, 8)
+            .ok()
+            .filter(|mode| *mode <= 0o7777);
+    }
+    let mut mode = current;
+    for clause in spec.split(',') {
+        let ops_start = clause.find(OPS)?;
+        let mut who = 0;
+        for ch in clause[..ops_start].chars() {
+            who |= match ch {
+                'u' => 0o4700,
+                'g' => 0o2070,
+                'o' => 0o1007,
+                'a' => 0o7777,
+                _ => return None,
+            };
+        }
+        if who == 0 {
+            who = 0o7777;
+        }
+        let mut rest = &clause[ops_start..];
+        while let Some(op) = rest.chars().next() {
+            rest = &rest[1..];
+            let end = rest.find(OPS).unwrap_or(rest.len());
+            let mut bits = 0;
+            for ch in rest[..end].chars() {
+                bits |= match ch {
+                    'r' => 0o444,
+                    'w' => 0o222,
+                    'x' => 0o111,
+                    'X' if is_dir || mode & 0o111 != 0 => 0o111,
+                    'X' => 0,
+                    's' => 0o6000,
+                    't' => 0o1000,
+                    _ => return None,
+                };
+            }
+            let bits = bits & who;
+            mode = match op {
+                '+' => mode | bits,
+                '-' => mode & !bits,
+                _ => (mode & !who) | bits,
+            };
+            rest = &rest[end..];
+        }
+    }
+    Some(mode)
+}
+
+/// Builds the new name for one entry of a bulk rename. `search=>replace` replaces every
+/// occurrence of `search`; any other template becomes the whole name. `{n}` expands to the
+/// 1-based position padded to `width` digits, `{name}` and `{ext}` to the original parts;
+/// `.{ext}` disappears for names without an extension. A whole-name template with
+/// neither `{ext}` nor an extension of its own keeps the original extension.
+pub fn bulk_rename_name(name: &str, template: &str, index: usize, width: usize) -> String {
+    let number = format!("{:0width$}", index + 1);
+    let (stem, extension) = split_extension(name);
+    let expand = |text: &str| {
+        let text = if extension.is_empty() {
+            text.replace(".{ext}", "")
+        } else {
+            text.to_string()
+        };
+        text.replace("{n}", &number)
+            .replace("{name}", stem)
+            .replace("{ext}", extension)
+    };
+    match template.split_once("=>") {
+        Some((search, replace)) if !search.is_empty() => name.replace(search, &expand(replace)),
+        _ => {
+            let new_name = expand(template);
+            if template.contains("{ext}")
+                || extension.is_empty()
+                || !split_extension(&new_name).1.is_empty()
+            {
+                new_name
+            } else {
+                format!("{new_name}.{extension}")
+            }
+        }
+    }
+}
+
+/// `name` split into stem and extension; dotfiles like `.bashrc` have no extension.
+fn split_extension(name: &str) -> (&str, &str) {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, extension),
+        _ => (name, ""),
+    }
+}
+
+/// How to treat a paste destination that already exists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    Overwrite,
+    Skip,
+    Rename,
+}
+
+/// Moves `src` to `dest`. Overwriting a directory with a directory merges the trees.
+/// Returns the final destination when moving it back would restore the previous state.
+/// Files copied across filesystems are counted in `progress`, which can also cancel the
+/// copy.
+pub async fn move_path(
+    src: &Path,
+    dest: &Path,
+    policy: ConflictPolicy,
+    progress: &Progress,
+) -> std::io::Result<Option<PathBuf>> {
+    if src == dest {
+        return Ok(None);
+    }
+    if !fs::try_exists(dest).await? {
+        rename_or_copy(src, dest, progress).await?;
+        return Ok(Some(dest.to_path_buf()));
+    }
+    match policy {
+        ConflictPolicy::Skip => Ok(None),
+        ConflictPolicy::Rename => {
+            let dest = unique_path(dest).await?;
+            rename_or_copy(src, &dest, progress).await?;
+            Ok(Some(dest))
+        }
+        ConflictPolicy::Overwrite => {
+            let src_dir = fs::metadata(src).await?.is_dir();
+            let dest_dir = fs::metadata(dest).await?.is_dir();
+            if src_dir && dest_dir {
+                copy_recursively(src, dest, policy, progress).await?;
+                remove_path(src).await?;
+            } else {
+                remove_path(dest).await?;
+                rename_or_copy(src, dest, progress).await?;
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Renames `src` to `dest`, or copies it and removes the original when `dest` is on
+/// another filesystem. A rename counts as one file in `progress`.
+async fn rename_or_copy(src: &Path, dest: &Path, progress: &Progress) -> std::io::Result<()> {
+    match fs::rename(src, dest).await {
+        Ok(()) => {
+            progress.files.fetch_add(1, atomic::Ordering::Relaxed);
+            Ok(())
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+            copy_recursively(src, dest, ConflictPolicy::Overwrite, progress).await?;
+            remove_path(src).await
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Whether moving `path` into `dir` stays on one filesystem. Assumed when either cannot
+/// be checked.
+#[cfg(unix)]
+pub fn same_filesystem(path: &Path, dir: &Path) -> bool {
+    match (std::fs::symlink_metadata(path), std::fs::metadata(dir)) {
+        (Ok(path), Ok(dir)) => path.dev() == dir.dev(),
+        _ => true,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn same_filesystem(_path: &Path, _dir: &Path) -> bool {
+    true
+}
+
+/// Copies `src` to `dest`, applying `policy` to every file that already exists so
+/// directory copies merge into an existing tree. If `progress` gets cancelled, the
+/// files and directories created so far are removed again.
+pub async fn copy_recursively(
+    src: &Path,
+    dest: &Path,
+    policy: ConflictPolicy,
+    progress: &Progress,
+) -> std::io::Result<()> {
+    let mut created = Vec::new();
+    let result = copy_tree(src, dest, policy, progress, &mut created).await;
+    if progress.is_cancelled() {
+        for path in created.iter().rev() {
+            let _ = remove_path(path).await;
+        }
+    }
+    result
+}
+
+async fn copy_tree(
+    src: &Path,
+    dest: &Path,
+    policy: ConflictPolicy,
+    progress: &Progress,
+    created: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
     let mut stack = vec![(src.to_path_buf(), dest.to_path_buf())];
-    while let Some((src_path, dest_path)) = stack.pop() {
+    while let Some((src_path, mut dest_path)) = stack.pop() {
+        progress.check()?;
+        if src_path == dest_path {
+            continue;
+        }
         let metadata = fs::metadata(&src_path).await?;
+        let mut exists = fs::try_exists(&dest_path).await?;
+        if exists && policy == ConflictPolicy::Rename {
+            dest_path = unique_path(&dest_path).await?;
+            exists = false;
+        }
+        // A file and a directory of the same name cannot be merged: the destination is
+        // skipped, or removed to make room as a move does.
+        if exists && metadata.is_dir() != fs::metadata(&dest_path).await?.is_dir() {
+            if policy == ConflictPolicy::Skip {
+                continue;
+            }
+            remove_path(&dest_path).await?;
+            exists = false;
+        }
         if metadata.is_dir() {
-            fs::create_dir_all(&dest_path).await?;
+            if !exists {
+                fs::create_dir_all(&dest_path).await?;
+                // Everything below a new directory is new as well, so only the
+                // directory itself needs to be remembered for cleanup.
+                created.push(dest_path.clone());
+            }
             let mut entries = fs::read_dir(&src_path).await?;
             while let Some(entry) = entries.next_entry().await? {
                 let entry_path = entry.path();
@@ -93,15 +869,436 @@ pub async fn copy_recursively(src: &Path, dest: &Path) -> std::io::Result<()> {
                 stack.push((entry_path, entry_dest));
             }
         } else {
+            if exists && policy == ConflictPolicy::Skip {
+                continue;
+            }
             if let Some(parent) = dest_path.parent() {
                 fs::create_dir_all(parent).await?;
             }
-            fs::copy(&src_path, &dest_path).await?;
+            if !exists && !created.iter().any(|dir| dest_path.starts_with(dir)) {
+                created.push(dest_path.clone());
+            }
+            copy_file(&src_path, &dest_path, metadata.permissions(), progress).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Running totals of a file operation, read by the UI while the operation is in
+/// flight, plus a flag the operation checks to stop early.
+#[derive(Debug, Default)]
+pub struct Progress {
+    pub bytes: AtomicU64,
+    pub files: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Fails with [`std::io::ErrorKind::Interrupted`] once the operation was cancelled.
+    pub fn check(&self) -> std::io::Result<()> {
+        if self.is_cancelled() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "cancelled",
+            ));
         }
+        Ok(())
     }
+}
+
+/// Streams the file instead of using `fs::copy` so bytes can be counted as they go.
+async fn copy_file(
+    src: &Path,
+    dest: &Path,
+    permissions: std::fs::Permissions,
+    progress: &Progress,
+) -> std::io::Result<()> {
+    let mut reader = CountingReader {
+        inner: fs::File::open(src).await?,
+        progress,
+    };
+    let mut writer = fs::File::create(dest).await?;
+    tokio::io::copy(&mut reader, &mut writer).await?;
+    fs::set_permissions(dest, permissions).await?;
+    progress.files.fetch_add(1, atomic::Ordering::Relaxed);
     Ok(())
 }
 
+struct CountingReader<'a, R> {
+    inner: R,
+    progress: &'a Progress,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CountingReader<'_, R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        if let Err(err) = this.progress.check() {
+            return Poll::Ready(Err(err));
+        }
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            let read = (buf.filled().len() - before) as u64;
+            this.progress
+                .bytes
+                .fetch_add(read, atomic::Ordering::Relaxed);
+        }
+        poll
+    }
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`, or `None` if
+/// the filesystem can't report it.
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stats` is only read after statvfs filled it.
+    let stats = unsafe {
+        if libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stats.assume_init()
+    };
+    // The field types differ between platforms.
+    #[allow(clippy::unnecessary_cast)]
+    Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_: &Path) -> Option<u64> {
+    None
+}
+
+/// Total size in bytes of the files below `root`, hidden ones included. Symlinks are
+/// counted as links rather than followed and unreadable directories are skipped. Returns
+/// `None` once `cancel` is set.
+pub fn dir_size(root: &Path, cancel: &AtomicBool) -> Option<u64> {
+    let mut stack = vec![root.to_path_buf()];
+    let mut total = 0u64;
+    while let Some(dir) = stack.pop() {
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            if cancel.load(atomic::Ordering::Relaxed) {
+                return None;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
+    Some(total)
+}
+
+/// Splits a command line into words on whitespace. Single quotes keep their contents
+/// as is; inside double quotes and outside quotes a backslash escapes the next character.
+pub fn split_args(input: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = input.chars();
+    while let Some(ch) = chars.next() {
+        match (quote, ch) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('\''), _) => word.push(ch),
+            (_, '\\') => {
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                }
+                in_word = true;
+            }
+            (Some(_), _) => word.push(ch),
+            (None, '\'' | '"') => {
+                quote = Some(ch);
+                in_word = true;
+            }
+            (None, _) if ch.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, _) => {
+                word.push(ch);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Paths listed one per line in clipboard text. Lines may be plain paths (with `~` and
+/// variables expanded) or `file://` URIs as copied by file managers.
+pub fn clipboard_paths(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.strip_prefix("file://") {
+            Some(uri) => {
+                let path = uri.strip_prefix("localhost").unwrap_or(uri);
+                path_from_bytes(percent_decode(path))
+            }
+            None => expand_path(line),
+        })
+        .collect()
+}
+
+/// `path` as a `file://` URI, percent-encoding everything but unreserved characters and
+/// separators. On Unix the raw bytes of the path are encoded, so names that are not valid
+/// UTF-8 round-trip.
+pub fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path_bytes(path) {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn percent_decode(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            if let Some(byte) = input
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                output.push(byte);
+                index += 3;
+                continue;
+            }
+        }
+        output.push(bytes[index]);
+        index += 1;
+    }
+    output
+}
+
+/// `path` relative to `base`, stepping up with `..` where they diverge. Paths without a
+/// common root (e.g. on different drives) are returned unchanged.
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path_parts: Vec<_> = path.components().collect();
+    let base_parts: Vec<_> = base.components().collect();
+    let common = path_parts
+        .iter()
+        .zip(&base_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return path.to_path_buf();
+    }
+    let mut relative: PathBuf = base_parts[common..].iter().map(|_| "..").collect();
+    relative.extend(&path_parts[common..]);
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
+/// The first free name of the form `stem copy.ext`, `stem copy 2.ext`, … for a duplicate
+/// of `name`. Directories and names without an extension get the suffix at the end.
+pub fn duplicate_name(name: &str, is_dir: bool, taken: impl Fn(&str) -> bool) -> String {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !is_dir && !stem.is_empty() => (stem, format!(".{extension}")),
+        _ => (name, String::new()),
+    };
+    let mut counter = 1u32;
+    loop {
+        let candidate = if counter == 1 {
+            format!("{stem} copy{extension}")
+        } else {
+            format!("{stem} copy {counter}{extension}")
+        };
+        if !taken(&candidate) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Returns the first free `name (copy).ext`, `name (copy 2).ext`, ... next to `path`.
+async fn unique_path(path: &Path) -> std::io::Result<PathBuf> {
+    let is_dir = fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir());
+    let (stem, extension) = match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(extension)) if !is_dir => (
+            stem.to_string_lossy().to_string(),
+            format!(".{}", extension.to_string_lossy()),
+        ),
+        _ => (
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            String::new(),
+        ),
+    };
+    let mut counter = 1u32;
+    loop {
+        let name = if counter == 1 {
+            format!("{stem} (copy){extension}")
+        } else {
+            format!("{stem} (copy {counter}){extension}")
+        };
+        let candidate = path.with_file_name(name);
+        if !fs::try_exists(&candidate).await? {
+            return Ok(candidate);
+        }
+        counter += 1;
+    }
+}
+
+/// Walks `root` and hands matching lines to `emit` in batches. The walk stops once
+/// `limit` matches were found, `cancel` is set, or `emit` returns `false`.
+pub fn search_contents(
+    root: &Path,
+    regex: &Regex,
+    show_hidden: bool,
+    limit: usize,
+    cancel: &AtomicBool,
+    mut emit: impl FnMut(Vec<ContentMatch>) -> bool,
+) {
+    let mut stack = vec![root.to_path_buf()];
+    let mut batch = Vec::new();
+    let mut found = 0usize;
+    let mut scanned = 0usize;
+    while let Some(dir) = stack.pop() {
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            if cancel.load(atomic::Ordering::Relaxed) {
+                return;
+            }
+            if !show_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            if file_type.is_dir() {
+                stack.push(path);
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            scanned += 1;
+            if scanned > CONTENT_SEARCH_MAX_FILES {
+                emit(batch);
+                return;
+            }
+            for (line, text) in matching_lines(&path, regex) {
+                let relative = path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .to_string();
+                batch.push(ContentMatch {
+                    path: path.clone(),
+                    relative,
+                    line,
+                    text,
+                });
+                found += 1;
+                if found >= limit {
+                    emit(batch);
+                    return;
+                }
+            }
+            if batch.len() >= CONTENT_SEARCH_BATCH_SIZE && !emit(std::mem::take(&mut batch)) {
+                return;
+            }
+        }
+    }
+    if !batch.is_empty() {
+        emit(batch);
+    }
+}
+
+fn matching_lines(path: &Path, regex: &Regex) -> Vec<(usize, String)> {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return Vec::new();
+    };
+    if metadata.len() > CONTENT_SEARCH_MAX_FILE_SIZE {
+        return Vec::new();
+    }
+    let Ok(bytes) = std::fs::read(path) else {
+        return Vec::new();
+    };
+    let sniff = &bytes[..bytes.len().min(8192)];
+    if sniff.contains(&0) {
+        return Vec::new();
+    }
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line))
+        .map(|(index, line)| {
+            let text = line
+                .trim()
+                .chars()
+                .take(CONTENT_SEARCH_LINE_WIDTH)
+                .collect();
+            (index + 1, text)
+        })
+        .collect()
+}
+
 #[cfg(unix)]
 fn permissions_string(metadata: &std::fs::Metadata) -> String {
     let mode = metadata.permissions().mode();
@@ -143,11 +1340,40 @@ fn permissions_string(metadata: &std::fs::Metadata) -> String {
 }
 
 #[cfg(unix)]
-fn owner_string(metadata: &std::fs::Metadata) -> String {
-    format!("{}:{}", metadata.uid(), metadata.gid())
+pub(crate) fn owner_string(metadata: &std::fs::Metadata) -> String {
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock, PoisonError};
+
+    type NameCache = OnceLock<Mutex<HashMap<u32, String>>>;
+
+    // Lookups can go through NSS (LDAP and the like), so each id is resolved once.
+    fn cached_name(
+        cache: &NameCache,
+        id: u32,
+        lookup: impl FnOnce(u32) -> Option<String>,
+    ) -> String {
+        let mut names = cache
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        names
+            .entry(id)
+            .or_insert_with(|| lookup(id).unwrap_or_else(|| id.to_string()))
+            .clone()
+    }
+
+    static USERS: NameCache = OnceLock::new();
+    static GROUPS: NameCache = OnceLock::new();
+    let user = cached_name(&USERS, metadata.uid(), |uid| {
+        uzers::get_user_by_uid(uid).map(|user| user.name().to_string_lossy().into_owned())
+    });
+    let group = cached_name(&GROUPS, metadata.gid(), |gid| {
+        uzers::get_group_by_gid(gid).map(|group| group.name().to_string_lossy().into_owned())
+    });
+    format!("{user}:{group}")
 }
 
 #[cfg(not(unix))]
-fn owner_string(_: &std::fs::Metadata) -> String {
+pub(crate) fn owner_string(_: &std::fs::Metadata) -> String {
     "-".to_string()
 }
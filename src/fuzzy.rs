@@ -0,0 +1,75 @@
+use std::ops::Range;
+
+const MATCH_SCORE: i64 = 1;
+const BOUNDARY_BONUS: i64 = 8;
+const CONSECUTIVE_BONUS: i64 = 5;
+const MAX_GAP_PENALTY: usize = 5;
+const MAX_LEADING_PENALTY: usize = 10;
+
+/// Scores `candidate` against an already lowercased `query` as an in-order subsequence.
+/// Returns `None` when not every query character can be matched. Higher is better:
+/// matches at word boundaries (separators, camelCase humps, digit runs) and runs of
+/// consecutive matches are rewarded, gaps and late starts are penalized.
+pub fn score(query: &[char], candidate: &str) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let mut total = 0i64;
+    let mut query_index = 0usize;
+    let mut previous: Option<char> = None;
+    let mut last_match: Option<usize> = None;
+    for (index, ch) in candidate.chars().enumerate() {
+        if query_index == query.len() {
+            break;
+        }
+        if ch.to_ascii_lowercase() == query[query_index] {
+            total += MATCH_SCORE;
+            if is_boundary(previous, ch) {
+                total += BOUNDARY_BONUS;
+            }
+            match last_match {
+                Some(last) if last + 1 == index => total += CONSECUTIVE_BONUS,
+                Some(last) => total -= (index - last - 1).min(MAX_GAP_PENALTY) as i64,
+                None => total -= index.min(MAX_LEADING_PENALTY) as i64,
+            }
+            last_match = Some(index);
+            query_index += 1;
+        }
+        previous = Some(ch);
+    }
+    (query_index == query.len()).then_some(total)
+}
+
+/// Byte ranges of `candidate` matched by `query`, picked the same way as in [`score`]
+/// with adjacent characters merged. Empty when `query` does not match.
+pub fn matched_ranges(query: &[char], candidate: &str) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut query_index = 0usize;
+    for (start, ch) in candidate.char_indices() {
+        if query_index == query.len() {
+            break;
+        }
+        if ch.to_ascii_lowercase() != query[query_index] {
+            continue;
+        }
+        let end = start + ch.len_utf8();
+        match ranges.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => ranges.push(start..end),
+        }
+        query_index += 1;
+    }
+    if query_index < query.len() {
+        ranges.clear();
+    }
+    ranges
+}
+
+fn is_boundary(previous: Option<char>, current: char) -> bool {
+    let Some(previous) = previous else {
+        return true;
+    };
+    matches!(previous, '_' | '-' | '.' | ' ' | '/')
+        || (previous.is_lowercase() && current.is_uppercase())
+        || (!previous.is_ascii_digit() && current.is_ascii_digit())
+}
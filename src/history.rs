@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io;
+use std::path::PathBuf;
+use tokio::fs;
+
+/// Past list filters, newest last, recalled with Up/Down in the search prompt.
+#[derive(Debug)]
+pub struct SearchHistory {
+    path: PathBuf,
+    entries: VecDeque<String>,
+    limit: usize,
+}
+
+impl SearchHistory {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            path: default_history_path(),
+            entries: VecDeque::new(),
+            limit,
+        }
+    }
+
+    /// Reads the saved history, one query per line.
+    pub async fn load(limit: usize) -> Self {
+        let mut history = Self::new(limit);
+        if let Ok(content) = fs::read_to_string(&history.path).await {
+            for line in content.lines() {
+                history.push(line);
+            }
+        }
+        history
+    }
+
+    /// Records `query` as the newest entry, dropping an older copy of it and the oldest
+    /// entries beyond the limit.
+    pub fn push(&mut self, query: &str) {
+        let query = query.trim();
+        if query.is_empty() || self.limit == 0 {
+            return;
+        }
+        self.entries.retain(|entry| entry != query);
+        self.entries.push_back(query.to_string());
+        while self.entries.len() > self.limit {
+            self.entries.pop_front();
+        }
+    }
+
+    /// The entry `index` steps back from the newest one (0 is the newest).
+    pub fn get(&self, index: usize) -> Option<&str> {
+        let position = self.entries.len().checked_sub(index + 1)?;
+        self.entries.get(position).map(String::as_str)
+    }
+
+    pub fn save_task(&self) -> impl Future<Output = io::Result<()>> + Send + 'static {
+        let path = self.path.clone();
+        let mut content = Vec::from_iter(self.entries.iter().map(String::as_str)).join("\n");
+        content.push('\n');
+        async move {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            fs::write(&path, content).await
+        }
+    }
+}
+
+fn default_history_path() -> PathBuf {
+    if let Some(dir) = dirs::config_dir() {
+        return dir.join("tfm").join("search_history");
+    }
+    if let Some(home) = dirs::home_dir() {
+        return home.join(".tfm.search_history");
+    }
+    PathBuf::from("search_history")
+}
@@ -0,0 +1,91 @@
+use crate::core::FileEntry;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Most directories kept at once.
+const CACHE_DIRS: usize = 16;
+
+#[derive(Debug)]
+pub struct CachedListing {
+    pub entries: Vec<FileEntry>,
+    stored: Instant,
+    show_hidden: bool,
+    counted_children: bool,
+}
+
+/// Recently listed directories, shown at once on revisits until the re-scan replaces them.
+#[derive(Debug)]
+pub struct ListingCache {
+    listings: HashMap<PathBuf, CachedListing>,
+    ttl: Duration,
+}
+
+impl ListingCache {
+    /// A zero `ttl` disables the cache.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            listings: HashMap::new(),
+            ttl,
+        }
+    }
+
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+        self.listings
+            .retain(|_, listing| listing.stored.elapsed() < ttl);
+    }
+
+    /// The listing of `dir` if it is recent and was made with the same hidden-file setting
+    /// and, when `count_children` is asked for, with child counts.
+    pub fn get(
+        &self,
+        dir: &Path,
+        show_hidden: bool,
+        count_children: bool,
+    ) -> Option<&CachedListing> {
+        self.listings.get(dir).filter(|listing| {
+            listing.stored.elapsed() < self.ttl
+                && listing.show_hidden == show_hidden
+                && (listing.counted_children || !count_children)
+        })
+    }
+
+    pub fn insert(
+        &mut self,
+        dir: PathBuf,
+        entries: Vec<FileEntry>,
+        show_hidden: bool,
+        counted_children: bool,
+    ) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let ttl = self.ttl;
+        self.listings
+            .retain(|_, listing| listing.stored.elapsed() < ttl);
+        if !self.listings.contains_key(&dir) && self.listings.len() >= CACHE_DIRS {
+            let oldest = self
+                .listings
+                .iter()
+                .min_by_key(|(_, listing)| listing.stored)
+                .map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                self.listings.remove(&oldest);
+            }
+        }
+        self.listings.insert(
+            dir,
+            CachedListing {
+                entries,
+                stored: Instant::now(),
+                show_hidden,
+                counted_children,
+            },
+        );
+    }
+
+    pub fn clear(&mut self) {
+        self.listings.clear();
+    }
+}
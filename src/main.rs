@@ -1,20 +1,42 @@
+mod archive;
+mod audio;
 mod config;
 mod core;
+mod fuzzy;
+mod history;
+mod listing_cache;
+mod markdown;
 mod markers;
+mod open_with;
 mod preview;
+mod preview_cache;
+mod programs;
 mod security;
 mod ui;
+mod view_state;
 
-use crate::config::Config;
-use crate::core::FileEntry;
+use crate::config::{Config, ConfirmConfig, KeyBindings, MismatchAction, SymlinkDirs};
+use crate::core::{ConflictPolicy, ContentMatch, FileCategory, FileEntry, SortMode, SortOptions};
+use crate::history::SearchHistory;
+use crate::listing_cache::ListingCache;
 use crate::markers::MarkerStore;
+use crate::open_with::LastPrograms;
 use crate::preview::Preview;
+use crate::preview_cache::{CacheKey, PreviewCache};
+use crate::programs::{is_executable, ProgramEntry, ProgramScan};
+use crate::security::MismatchStatus;
+use crate::ui::{FilterHighlight, StatusKind, StatusMessage};
+use crate::view_state::ViewState;
 use arboard::Clipboard;
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+    MouseButton, MouseEvent, MouseEventKind,
+};
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
 use crossterm::{cursor, event, execute};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::Rect;
 use ratatui::Terminal;
@@ -22,70 +44,336 @@ use ratatui_image::picker::Picker;
 use ratatui_image::protocol::StatefulProtocol;
 use ratatui_image::Resize;
 use regex::RegexBuilder;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::error::Error;
 use std::future::Future;
 use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Sender};
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc as tokio_mpsc;
 use tokio_stream::StreamExt;
 
 const DIR_BATCH_SIZE: usize = 512;
+const UNDO_LIMIT: usize = 32;
+const CONTENT_SEARCH_LIMIT: usize = 1000;
+/// Most files a flattened listing shows; the walk stops there.
+const FLAT_LISTING_LIMIT: usize = 50_000;
+const MISMATCH_SCAN_BATCH_SIZE: usize = 32;
+/// Two clicks on the same row within this interval open it.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+/// Preview requests closer together than this are coalesced until the selection settles.
+const PREVIEW_DEBOUNCE: Duration = Duration::from_millis(80);
+/// How long changes to the watched directory are collected before it is re-listed.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(250);
+/// Largest file whose contents are copied to the clipboard.
+const COPY_CONTENTS_LIMIT: u64 = 1024 * 1024;
+/// Entries on each side of the selection remembered to select instead if it disappears.
+const SELECTION_NEIGHBORS: usize = 64;
+/// Frames shown while a directory size is being computed, one per progress tick.
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
 
 #[derive(Clone, Copy)]
 enum DirTarget {
     Parent,
     Current,
+    /// The inactive pane of the dual-pane layout.
+    Other,
+}
+
+/// The inactive side of the dual-pane layout, shown in place of the preview.
+struct Pane {
+    dir: PathBuf,
+    entries: Vec<FileEntry>,
+    /// Entry selected when the pane was last active, or the first one pasted into it.
+    selected: Option<PathBuf>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum InputAction {
     Search,
+    FuzzySearch,
+    ContentSearch,
     MarkerSearch,
     AddFile,
     AddDir,
     Rename,
+    Chmod,
     MarkerSet,
     MarkerJump,
-    MarkerRename { name: String },
-    MarkerEditPath { name: String },
+    MarkerRename {
+        name: String,
+    },
+    MarkerEditPath {
+        name: String,
+    },
     MarkerCreateName,
-    MarkerCreatePath { name: String },
-    ConfirmDelete,
+    MarkerCreatePath {
+        name: String,
+    },
+    MarkerExport,
+    /// Exporting markers onto a file that already exists.
+    ConfirmMarkerExport(PathBuf),
+    MarkerImport,
+    GoToPath,
+    /// A rename that drops the file's extension, which is usually a slip.
+    ConfirmRename {
+        src: PathBuf,
+        dest: PathBuf,
+        extension: String,
+    },
+    /// A destructive action waiting for the user, see [`Destructive`].
+    Confirm(Destructive),
+    /// Opening a file whose content does not match its extension, or an executable.
+    ConfirmOpen {
+        path: PathBuf,
+        detail: String,
+    },
+    /// Quitting while file operations are still running.
+    ConfirmQuit,
+    BulkRename,
+    ConfirmBulkRename(Vec<(PathBuf, PathBuf)>),
+}
+
+/// An action that destroys or replaces files, confirmed first when `[confirm]` asks for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Destructive {
+    Delete(Vec<PathBuf>),
+    /// A paste onto existing files; confirming picks how to resolve them.
+    Overwrite(PasteRequest),
+    /// A cut-paste onto another filesystem, which copies and then deletes the originals.
+    CrossDeviceMove(PasteRequest, ConflictPolicy),
+}
+
+impl Destructive {
+    fn needs_confirmation(&self, confirm: &ConfirmConfig) -> bool {
+        match self {
+            Self::Delete(_) => confirm.delete,
+            Self::Overwrite(_) => confirm.overwrite,
+            Self::CrossDeviceMove(..) => confirm.move_across_filesystems,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct PasteRequest {
+    op: ClipboardOp,
+    pairs: Vec<(PathBuf, PathBuf)>,
+    conflicts: usize,
 }
 
 #[derive(Debug)]
 struct InputState {
     action: InputAction,
     buffer: String,
+    /// Byte offset of the caret in `buffer`, always on a character boundary.
+    cursor: usize,
+    /// Position in the search history while browsing it, and the text typed before.
+    history: Option<(usize, String)>,
+    /// Path matches cycled through by repeated Tab presses.
+    completion: Option<Completion>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Completion {
+    /// Byte offset in the buffer of the completed path component.
+    start: usize,
+    candidates: Vec<String>,
+    /// The candidate currently in the buffer.
+    index: usize,
 }
 
 impl InputState {
     fn new(action: InputAction, buffer: String) -> Self {
-        Self { action, buffer }
+        Self {
+            action,
+            cursor: buffer.len(),
+            buffer,
+            history: None,
+            completion: None,
+        }
+    }
+
+    /// Whether the prompt edits text, as opposed to waiting for a single answer key.
+    fn takes_text(&self) -> bool {
+        !matches!(
+            self.action,
+            InputAction::Confirm(_)
+                | InputAction::ConfirmRename { .. }
+                | InputAction::ConfirmOpen { .. }
+                | InputAction::ConfirmQuit
+                | InputAction::ConfirmBulkRename(_)
+                | InputAction::ConfirmMarkerExport(_)
+        )
+    }
+
+    fn insert(&mut self, ch: char) {
+        self.buffer.insert(self.cursor, ch);
+        self.cursor += ch.len_utf8();
+    }
+
+    /// Deletes back to the start of the word before the caret; `/` ends a word too, so
+    /// path components go one at a time.
+    fn erase_word(&mut self) {
+        let separator = |ch: char| ch.is_whitespace() || ch == '/';
+        let before = &self.buffer[..self.cursor];
+        let trimmed = before.trim_end_matches(separator);
+        let start = trimmed.trim_end_matches(|ch| !separator(ch)).len();
+        self.buffer.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+        self.cursor = 0;
+    }
+
+    /// Prompts whose text is a path, completed with Tab.
+    fn completes_paths(&self) -> bool {
+        matches!(
+            self.action,
+            InputAction::GoToPath
+                | InputAction::MarkerEditPath { .. }
+                | InputAction::MarkerCreatePath { .. }
+                | InputAction::MarkerExport
+                | InputAction::MarkerImport
+        )
+    }
+
+    /// Completes the path component before the caret against the filesystem: a single
+    /// match is filled in, several as far as they agree. When that adds nothing, this and
+    /// further presses cycle through the matches.
+    fn complete(&mut self, base: &Path) {
+        if let Some(completion) = self.completion.as_mut() {
+            let end = completion.start + completion.candidates[completion.index].len();
+            completion.index = (completion.index + 1) % completion.candidates.len();
+            let next = &completion.candidates[completion.index];
+            self.buffer.replace_range(completion.start..end, next);
+            self.cursor = completion.start + next.len();
+            return;
+        }
+        let (start, candidates) = core::path_completions(&self.buffer[..self.cursor], base);
+        let fill = match candidates.as_slice() {
+            [] => return,
+            [only] => only.clone(),
+            _ => {
+                let common = core::common_prefix(&candidates);
+                if common.len() > self.cursor - start {
+                    common.to_string()
+                } else {
+                    let first = candidates[0].clone();
+                    self.completion = Some(Completion {
+                        start,
+                        candidates,
+                        index: 0,
+                    });
+                    first
+                }
+            }
+        };
+        self.buffer.replace_range(start..self.cursor, &fill);
+        self.cursor = start + fill.len();
+    }
+
+    /// Backspace removes the character before the caret, Delete the one after it.
+    fn erase(&mut self, code: KeyCode) {
+        if code == KeyCode::Backspace {
+            if let Some(ch) = self.buffer[..self.cursor].chars().next_back() {
+                self.cursor -= ch.len_utf8();
+                self.buffer.remove(self.cursor);
+            }
+        } else if self.cursor < self.buffer.len() {
+            self.buffer.remove(self.cursor);
+        }
+    }
+
+    /// Moves the caret for Left, Right, Home and End; other keys are left alone.
+    fn move_cursor(&mut self, code: KeyCode) -> bool {
+        self.cursor = match code {
+            KeyCode::Left => match self.buffer[..self.cursor].chars().next_back() {
+                Some(ch) => self.cursor - ch.len_utf8(),
+                None => 0,
+            },
+            KeyCode::Right => match self.buffer[self.cursor..].chars().next() {
+                Some(ch) => self.cursor + ch.len_utf8(),
+                None => self.buffer.len(),
+            },
+            KeyCode::Home => 0,
+            KeyCode::End => self.buffer.len(),
+            _ => return false,
+        };
+        true
+    }
+
+    /// Steps through `history` (`older` for Up) and puts the recalled query in the
+    /// buffer. Stepping past the newest entry brings back what was typed before.
+    fn recall(&mut self, history: &SearchHistory, older: bool) -> bool {
+        let next = match (&self.history, older) {
+            (None, true) => Some(0),
+            (None, false) => return false,
+            (Some((position, _)), true) => Some(position + 1),
+            (Some((0, _)), false) => None,
+            (Some((position, _)), false) => Some(position - 1),
+        };
+        match next {
+            Some(position) => {
+                let Some(query) = history.get(position) else {
+                    return false;
+                };
+                let draft = match self.history.take() {
+                    Some((_, draft)) => draft,
+                    None => self.buffer.clone(),
+                };
+                self.buffer = query.to_string();
+                self.cursor = self.buffer.len();
+                self.history = Some((position, draft));
+            }
+            None => {
+                if let Some((_, draft)) = self.history.take() {
+                    self.buffer = draft;
+                    self.cursor = self.buffer.len();
+                }
+            }
+        }
+        true
     }
 
     fn title(&self) -> &'static str {
+        let inverted = split_inverted(&self.buffer).0;
         match self.action.clone() {
+            InputAction::Search if inverted => "Search (regex, inverted)",
             InputAction::Search => "Search (regex)",
+            InputAction::FuzzySearch if inverted => "Search (fuzzy, inverted)",
+            InputAction::FuzzySearch => "Search (fuzzy)",
+            InputAction::ContentSearch => "Search Contents (regex)",
             InputAction::MarkerSearch => "Search Markers (n:/p:)",
             InputAction::AddFile => "Add File",
             InputAction::AddDir => "Add Dir",
             InputAction::Rename => "Rename",
+            InputAction::Chmod => "Chmod (755, +x, go-w)",
             InputAction::MarkerSet => "Set Marker",
             InputAction::MarkerJump => "Jump Marker",
             InputAction::MarkerRename { .. } => "Rename Marker",
             InputAction::MarkerEditPath { .. } => "Edit Marker Path",
             InputAction::MarkerCreateName => "New Marker Name",
             InputAction::MarkerCreatePath { .. } => "New Marker Path",
-            InputAction::ConfirmDelete => "Delete",
+            InputAction::MarkerExport => "Export Markers To",
+            InputAction::ConfirmMarkerExport(_) => "File Exists",
+            InputAction::MarkerImport => "Import Markers From",
+            InputAction::GoToPath => "Go To Path",
+            InputAction::ConfirmRename { .. } => "Remove Extension",
+            InputAction::Confirm(Destructive::Delete(_)) => "Delete",
+            InputAction::Confirm(Destructive::Overwrite(_)) => "Destination Exists",
+            InputAction::Confirm(Destructive::CrossDeviceMove(..)) => "Move Across Filesystems",
+            InputAction::ConfirmOpen { .. } => "Confirm Open",
+            InputAction::ConfirmQuit => "Quit",
+            InputAction::BulkRename => "Bulk Rename (find=>replace or template with {n})",
+            InputAction::ConfirmBulkRename(_) => "Confirm Rename",
         }
     }
 }
@@ -96,6 +384,8 @@ enum Mode {
     Input(InputState),
     MarkerList,
     ProgramList,
+    SearchResults,
+    Palette,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -106,6 +396,9 @@ enum PendingPrefix {
     View,
     Delete,
     OpenWith,
+    Sort,
+    QuickMarkerSet,
+    QuickMarkerJump,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -117,19 +410,65 @@ enum ClipboardOp {
 #[derive(Clone, Debug)]
 struct ClipboardEntry {
     op: ClipboardOp,
-    path: PathBuf,
+    paths: Vec<PathBuf>,
 }
 
-#[derive(Debug, Clone)]
-struct MarkerListEntry {
-    name: String,
-    path: PathBuf,
+/// Inverse information for a completed file operation.
+#[derive(Clone, Debug)]
+enum UndoEntry {
+    Create {
+        path: PathBuf,
+        parents: Vec<PathBuf>,
+    },
+    Copy(PathBuf),
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Trash(PathBuf),
+    Delete(PathBuf),
+    Batch(Vec<UndoEntry>),
+}
+
+impl UndoEntry {
+    fn batch(mut entries: Vec<UndoEntry>) -> Option<Self> {
+        match entries.len() {
+            0 => None,
+            1 => entries.pop(),
+            _ => Some(Self::Batch(entries)),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Create { .. } => "create",
+            Self::Copy(_) => "duplicate",
+            Self::Rename { .. } => "rename",
+            Self::Move { .. } => "move",
+            Self::Trash(_) | Self::Delete(_) => "delete",
+            Self::Batch(entries) => entries.first().map_or("batch", Self::label),
+        }
+    }
+
+    fn restored_path(&self) -> Option<PathBuf> {
+        match self {
+            Self::Create { .. } | Self::Copy(_) | Self::Delete(_) => None,
+            Self::Rename { from, .. } | Self::Move { from, .. } => Some(from.clone()),
+            Self::Trash(path) => Some(path.clone()),
+            Self::Batch(entries) => entries.iter().find_map(Self::restored_path),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-struct ProgramEntry {
+struct MarkerListEntry {
     name: String,
     path: PathBuf,
+    is_file: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -147,15 +486,53 @@ struct MarkerListState {
     filter: String,
 }
 
+/// What the results popup lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchKind {
+    Contents,
+    /// Files whose content does not match their extension; `query` is the directory.
+    Mismatches,
+}
+
+#[derive(Debug)]
+struct ContentSearchState {
+    id: u64,
+    kind: SearchKind,
+    query: String,
+    matches: Vec<ContentMatch>,
+    selected: usize,
+    done: bool,
+    cancel: Arc<AtomicBool>,
+}
+
 #[derive(Debug)]
 struct ProgramListState {
     entries: Vec<ProgramEntry>,
     filtered_indices: Vec<usize>,
     selected: usize,
     filter: String,
+    /// Program last used for the target's extension, selected when the list opens.
+    remembered: Option<String>,
 }
 
-#[derive(Clone)]
+#[derive(Debug)]
+struct PaletteState {
+    commands: Vec<PaletteCommand>,
+    filtered_indices: Vec<usize>,
+    selected: usize,
+    filter: String,
+}
+
+#[derive(Debug)]
+struct PaletteCommand {
+    name: &'static str,
+    /// The first configured key of each step, shown next to the name.
+    label: String,
+    /// Keys replayed in normal mode to run the command; `None` when a step is unbound.
+    keys: Option<Vec<KeyBinding>>,
+}
+
+#[derive(Clone, Debug)]
 struct KeyBinding {
     code: KeyCode,
     modifiers: KeyModifiers,
@@ -169,8 +546,12 @@ struct KeyMap {
     view: ViewKeyMap,
     copy: CopyKeyMap,
     delete: DeleteKeyMap,
+    sort: SortKeyMap,
     marker_list: MarkerListKeyMap,
     open_with: OpenWithKeyMap,
+    search_results: SearchResultsKeyMap,
+    preview: PreviewKeyMap,
+    palette: PaletteKeyMap,
 }
 
 #[derive(Clone)]
@@ -181,6 +562,7 @@ struct NormalKeyMap {
     parent: Vec<KeyBinding>,
     open: Vec<KeyBinding>,
     search: Vec<KeyBinding>,
+    fuzzy_search: Vec<KeyBinding>,
     add: Vec<KeyBinding>,
     rename: Vec<KeyBinding>,
     delete: Vec<KeyBinding>,
@@ -195,6 +577,32 @@ struct NormalKeyMap {
     open_shell: Vec<KeyBinding>,
     open_with_picker: Vec<KeyBinding>,
     open_with_quick: Vec<KeyBinding>,
+    sort: Vec<KeyBinding>,
+    content_search: Vec<KeyBinding>,
+    quick_marker_set: Vec<KeyBinding>,
+    quick_marker_jump: Vec<KeyBinding>,
+    cancel: Vec<KeyBinding>,
+    toggle_mark: Vec<KeyBinding>,
+    undo: Vec<KeyBinding>,
+    bulk_rename: Vec<KeyBinding>,
+    edit: Vec<KeyBinding>,
+    focus_preview: Vec<KeyBinding>,
+    top: Vec<KeyBinding>,
+    bottom: Vec<KeyBinding>,
+    page_up: Vec<KeyBinding>,
+    page_down: Vec<KeyBinding>,
+    half_page_up: Vec<KeyBinding>,
+    half_page_down: Vec<KeyBinding>,
+    chmod: Vec<KeyBinding>,
+    open_terminal: Vec<KeyBinding>,
+    paste_system: Vec<KeyBinding>,
+    clear_filter: Vec<KeyBinding>,
+    mismatch_scan: Vec<KeyBinding>,
+    reveal_link: Vec<KeyBinding>,
+    file_manager: Vec<KeyBinding>,
+    command_palette: Vec<KeyBinding>,
+    dir_size: Vec<KeyBinding>,
+    go_to_path: Vec<KeyBinding>,
 }
 
 #[derive(Clone)]
@@ -207,19 +615,31 @@ struct SettingsKeyMap {
     toggle_permissions: Vec<KeyBinding>,
     toggle_dates: Vec<KeyBinding>,
     toggle_owner: Vec<KeyBinding>,
+    toggle_size: Vec<KeyBinding>,
     toggle_metadata: Vec<KeyBinding>,
     toggle_hidden: Vec<KeyBinding>,
+    toggle_sticky_filter: Vec<KeyBinding>,
+    toggle_relative_dates: Vec<KeyBinding>,
 }
 
 #[derive(Clone)]
 struct ViewKeyMap {
     toggle_list_permissions: Vec<KeyBinding>,
     toggle_list_owner: Vec<KeyBinding>,
+    toggle_list_size: Vec<KeyBinding>,
+    toggle_preview_wrap: Vec<KeyBinding>,
+    toggle_flatten: Vec<KeyBinding>,
+    toggle_dual_pane: Vec<KeyBinding>,
 }
 
 #[derive(Clone)]
 struct CopyKeyMap {
     copy_path: Vec<KeyBinding>,
+    copy_name: Vec<KeyBinding>,
+    copy_relative_path: Vec<KeyBinding>,
+    copy_parent_path: Vec<KeyBinding>,
+    copy_contents: Vec<KeyBinding>,
+    duplicate: Vec<KeyBinding>,
 }
 
 #[derive(Clone)]
@@ -227,6 +647,14 @@ struct DeleteKeyMap {
     confirm: Vec<KeyBinding>,
 }
 
+#[derive(Clone)]
+struct SortKeyMap {
+    by_name: Vec<KeyBinding>,
+    by_size: Vec<KeyBinding>,
+    by_modified: Vec<KeyBinding>,
+    reverse: Vec<KeyBinding>,
+}
+
 #[derive(Clone)]
 struct MarkerListKeyMap {
     close: Vec<KeyBinding>,
@@ -238,6 +666,8 @@ struct MarkerListKeyMap {
     delete: Vec<KeyBinding>,
     add: Vec<KeyBinding>,
     search: Vec<KeyBinding>,
+    export: Vec<KeyBinding>,
+    import: Vec<KeyBinding>,
 }
 
 #[derive(Clone)]
@@ -249,6 +679,32 @@ struct OpenWithKeyMap {
     backspace: Vec<KeyBinding>,
 }
 
+#[derive(Clone)]
+struct PaletteKeyMap {
+    close: Vec<KeyBinding>,
+    up: Vec<KeyBinding>,
+    down: Vec<KeyBinding>,
+    run: Vec<KeyBinding>,
+    backspace: Vec<KeyBinding>,
+}
+
+#[derive(Clone)]
+struct PreviewKeyMap {
+    up: Vec<KeyBinding>,
+    down: Vec<KeyBinding>,
+    page_up: Vec<KeyBinding>,
+    page_down: Vec<KeyBinding>,
+    close: Vec<KeyBinding>,
+}
+
+#[derive(Clone)]
+struct SearchResultsKeyMap {
+    close: Vec<KeyBinding>,
+    up: Vec<KeyBinding>,
+    down: Vec<KeyBinding>,
+    open: Vec<KeyBinding>,
+}
+
 impl KeyBinding {
     fn matches(&self, key: KeyEvent) -> bool {
         if key.code != self.code {
@@ -267,77 +723,217 @@ impl KeyBinding {
 }
 
 impl KeyMap {
-    fn from_config(config: &Config) -> Self {
+    /// Builds the keymap, returning every binding that could not be parsed.
+    fn from_config(config: &Config) -> (Self, Vec<String>) {
         let keys = &config.keys;
-        Self {
+        let mut parser = KeyListParser::default();
+        let keymap = Self {
             normal: NormalKeyMap {
-                quit: parse_key_list(&keys.normal.quit),
-                up: parse_key_list(&keys.normal.up),
-                down: parse_key_list(&keys.normal.down),
-                parent: parse_key_list(&keys.normal.parent),
-                open: parse_key_list(&keys.normal.open),
-                search: parse_key_list(&keys.normal.search),
-                add: parse_key_list(&keys.normal.add),
-                rename: parse_key_list(&keys.normal.rename),
-                delete: parse_key_list(&keys.normal.delete),
-                marker_set: parse_key_list(&keys.normal.marker_set),
-                marker_list: parse_key_list(&keys.normal.marker_list),
-                marker_jump: parse_key_list(&keys.normal.marker_jump),
-                settings: parse_key_list(&keys.normal.settings),
-                view: parse_key_list(&keys.normal.view),
-                copy: parse_key_list(&keys.normal.copy),
-                cut: parse_key_list(&keys.normal.cut),
-                paste: parse_key_list(&keys.normal.paste),
-                open_shell: parse_key_list(&keys.normal.open_shell),
-                open_with_picker: parse_key_list(&keys.normal.open_with_picker),
-                open_with_quick: parse_key_list(&keys.normal.open_with_quick),
+                quit: parser.list("normal.quit", &keys.normal.quit),
+                up: parser.list("normal.up", &keys.normal.up),
+                down: parser.list("normal.down", &keys.normal.down),
+                parent: parser.list("normal.parent", &keys.normal.parent),
+                open: parser.list("normal.open", &keys.normal.open),
+                search: parser.list("normal.search", &keys.normal.search),
+                fuzzy_search: parser.list("normal.fuzzy_search", &keys.normal.fuzzy_search),
+                add: parser.list("normal.add", &keys.normal.add),
+                rename: parser.list("normal.rename", &keys.normal.rename),
+                delete: parser.list("normal.delete", &keys.normal.delete),
+                marker_set: parser.list("normal.marker_set", &keys.normal.marker_set),
+                marker_list: parser.list("normal.marker_list", &keys.normal.marker_list),
+                marker_jump: parser.list("normal.marker_jump", &keys.normal.marker_jump),
+                settings: parser.list("normal.settings", &keys.normal.settings),
+                view: parser.list("normal.view", &keys.normal.view),
+                copy: parser.list("normal.copy", &keys.normal.copy),
+                cut: parser.list("normal.cut", &keys.normal.cut),
+                paste: parser.list("normal.paste", &keys.normal.paste),
+                open_shell: parser.list("normal.open_shell", &keys.normal.open_shell),
+                open_with_picker: parser
+                    .list("normal.open_with_picker", &keys.normal.open_with_picker),
+                open_with_quick: parser
+                    .list("normal.open_with_quick", &keys.normal.open_with_quick),
+                sort: parser.list("normal.sort", &keys.normal.sort),
+                content_search: parser.list("normal.content_search", &keys.normal.content_search),
+                quick_marker_set: parser
+                    .list("normal.quick_marker_set", &keys.normal.quick_marker_set),
+                quick_marker_jump: parser
+                    .list("normal.quick_marker_jump", &keys.normal.quick_marker_jump),
+                cancel: parser.list("normal.cancel", &keys.normal.cancel),
+                toggle_mark: parser.list("normal.toggle_mark", &keys.normal.toggle_mark),
+                undo: parser.list("normal.undo", &keys.normal.undo),
+                bulk_rename: parser.list("normal.bulk_rename", &keys.normal.bulk_rename),
+                edit: parser.list("normal.edit", &keys.normal.edit),
+                focus_preview: parser.list("normal.focus_preview", &keys.normal.focus_preview),
+                top: parser.list("normal.top", &keys.normal.top),
+                bottom: parser.list("normal.bottom", &keys.normal.bottom),
+                page_up: parser.list("normal.page_up", &keys.normal.page_up),
+                page_down: parser.list("normal.page_down", &keys.normal.page_down),
+                half_page_up: parser.list("normal.half_page_up", &keys.normal.half_page_up),
+                half_page_down: parser.list("normal.half_page_down", &keys.normal.half_page_down),
+                chmod: parser.list("normal.chmod", &keys.normal.chmod),
+                open_terminal: parser.list("normal.open_terminal", &keys.normal.open_terminal),
+                paste_system: parser.list("normal.paste_system", &keys.normal.paste_system),
+                clear_filter: parser.list("normal.clear_filter", &keys.normal.clear_filter),
+                mismatch_scan: parser.list("normal.mismatch_scan", &keys.normal.mismatch_scan),
+                reveal_link: parser.list("normal.reveal_link", &keys.normal.reveal_link),
+                file_manager: parser.list("normal.file_manager", &keys.normal.file_manager),
+                command_palette: parser
+                    .list("normal.command_palette", &keys.normal.command_palette),
+                dir_size: parser.list("normal.dir_size", &keys.normal.dir_size),
+                go_to_path: parser.list("normal.go_to_path", &keys.normal.go_to_path),
             },
             add: AddKeyMap {
-                dir: parse_key_list(&keys.add.dir),
+                dir: parser.list("add.dir", &keys.add.dir),
             },
             settings: SettingsKeyMap {
-                toggle_permissions: parse_key_list(&keys.settings.toggle_permissions),
-                toggle_dates: parse_key_list(&keys.settings.toggle_dates),
-                toggle_owner: parse_key_list(&keys.settings.toggle_owner),
-                toggle_metadata: parse_key_list(&keys.settings.toggle_metadata),
-                toggle_hidden: parse_key_list(&keys.settings.toggle_hidden),
+                toggle_permissions: parser.list(
+                    "settings.toggle_permissions",
+                    &keys.settings.toggle_permissions,
+                ),
+                toggle_dates: parser.list("settings.toggle_dates", &keys.settings.toggle_dates),
+                toggle_owner: parser.list("settings.toggle_owner", &keys.settings.toggle_owner),
+                toggle_size: parser.list("settings.toggle_size", &keys.settings.toggle_size),
+                toggle_metadata: parser
+                    .list("settings.toggle_metadata", &keys.settings.toggle_metadata),
+                toggle_hidden: parser.list("settings.toggle_hidden", &keys.settings.toggle_hidden),
+                toggle_sticky_filter: parser.list(
+                    "settings.toggle_sticky_filter",
+                    &keys.settings.toggle_sticky_filter,
+                ),
+                toggle_relative_dates: parser.list(
+                    "settings.toggle_relative_dates",
+                    &keys.settings.toggle_relative_dates,
+                ),
             },
             view: ViewKeyMap {
-                toggle_list_permissions: parse_key_list(&keys.view.toggle_list_permissions),
-                toggle_list_owner: parse_key_list(&keys.view.toggle_list_owner),
+                toggle_list_permissions: parser.list(
+                    "view.toggle_list_permissions",
+                    &keys.view.toggle_list_permissions,
+                ),
+                toggle_list_owner: parser
+                    .list("view.toggle_list_owner", &keys.view.toggle_list_owner),
+                toggle_list_size: parser.list("view.toggle_list_size", &keys.view.toggle_list_size),
+                toggle_preview_wrap: parser
+                    .list("view.toggle_preview_wrap", &keys.view.toggle_preview_wrap),
+                toggle_flatten: parser.list("view.toggle_flatten", &keys.view.toggle_flatten),
+                toggle_dual_pane: parser.list("view.toggle_dual_pane", &keys.view.toggle_dual_pane),
             },
             copy: CopyKeyMap {
-                copy_path: parse_key_list(&keys.copy.copy_path),
+                copy_path: parser.list("copy.copy_path", &keys.copy.copy_path),
+                copy_name: parser.list("copy.copy_name", &keys.copy.copy_name),
+                copy_relative_path: parser
+                    .list("copy.copy_relative_path", &keys.copy.copy_relative_path),
+                copy_parent_path: parser.list("copy.copy_parent_path", &keys.copy.copy_parent_path),
+                copy_contents: parser.list("copy.copy_contents", &keys.copy.copy_contents),
+                duplicate: parser.list("copy.duplicate", &keys.copy.duplicate),
             },
             delete: DeleteKeyMap {
-                confirm: parse_key_list(&keys.delete.confirm),
+                confirm: parser.list("delete.confirm", &keys.delete.confirm),
+            },
+            sort: SortKeyMap {
+                by_name: parser.list("sort.by_name", &keys.sort.by_name),
+                by_size: parser.list("sort.by_size", &keys.sort.by_size),
+                by_modified: parser.list("sort.by_modified", &keys.sort.by_modified),
+                reverse: parser.list("sort.reverse", &keys.sort.reverse),
             },
             marker_list: MarkerListKeyMap {
-                close: parse_key_list(&keys.marker_list.close),
-                up: parse_key_list(&keys.marker_list.up),
-                down: parse_key_list(&keys.marker_list.down),
-                open: parse_key_list(&keys.marker_list.open),
-                rename: parse_key_list(&keys.marker_list.rename),
-                edit_path: parse_key_list(&keys.marker_list.edit_path),
-                delete: parse_key_list(&keys.marker_list.delete),
-                add: parse_key_list(&keys.marker_list.add),
-                search: parse_key_list(&keys.marker_list.search),
+                close: parser.list("marker_list.close", &keys.marker_list.close),
+                up: parser.list("marker_list.up", &keys.marker_list.up),
+                down: parser.list("marker_list.down", &keys.marker_list.down),
+                open: parser.list("marker_list.open", &keys.marker_list.open),
+                rename: parser.list("marker_list.rename", &keys.marker_list.rename),
+                edit_path: parser.list("marker_list.edit_path", &keys.marker_list.edit_path),
+                delete: parser.list("marker_list.delete", &keys.marker_list.delete),
+                add: parser.list("marker_list.add", &keys.marker_list.add),
+                search: parser.list("marker_list.search", &keys.marker_list.search),
+                export: parser.list("marker_list.export", &keys.marker_list.export),
+                import: parser.list("marker_list.import", &keys.marker_list.import),
             },
             open_with: OpenWithKeyMap {
-                close: parse_key_list(&keys.open_with.close),
-                up: parse_key_list(&keys.open_with.up),
-                down: parse_key_list(&keys.open_with.down),
-                open: parse_key_list(&keys.open_with.open),
-                backspace: parse_key_list(&keys.open_with.backspace),
+                close: parser.list("open_with.close", &keys.open_with.close),
+                up: parser.list("open_with.up", &keys.open_with.up),
+                down: parser.list("open_with.down", &keys.open_with.down),
+                open: parser.list("open_with.open", &keys.open_with.open),
+                backspace: parser.list("open_with.backspace", &keys.open_with.backspace),
             },
-        }
+            search_results: SearchResultsKeyMap {
+                close: parser.list("search_results.close", &keys.search_results.close),
+                up: parser.list("search_results.up", &keys.search_results.up),
+                down: parser.list("search_results.down", &keys.search_results.down),
+                open: parser.list("search_results.open", &keys.search_results.open),
+            },
+            preview: PreviewKeyMap {
+                up: parser.list("preview.up", &keys.preview.up),
+                down: parser.list("preview.down", &keys.preview.down),
+                page_up: parser.list("preview.page_up", &keys.preview.page_up),
+                page_down: parser.list("preview.page_down", &keys.preview.page_down),
+                close: parser.list("preview.close", &keys.preview.close),
+            },
+            palette: PaletteKeyMap {
+                close: parser.list("palette.close", &keys.palette.close),
+                up: parser.list("palette.up", &keys.palette.up),
+                down: parser.list("palette.down", &keys.palette.down),
+                run: parser.list("palette.run", &keys.palette.run),
+                backspace: parser.list("palette.backspace", &keys.palette.backspace),
+            },
+        };
+        (keymap, parser.errors)
+    }
+}
+
+/// Problems in an otherwise loaded config that should be pointed out to the user, all in
+/// one message.
+fn config_message(config: &Config, key_errors: &[String]) -> Option<StatusMessage> {
+    let mut problems = Vec::new();
+    if !key_errors.is_empty() {
+        problems.push(format!(
+            "Invalid key bindings ignored: {}",
+            key_errors.join(", ")
+        ));
+    }
+    let date_format = &config.metadata_bar.date_format;
+    if !preview::date_format_is_valid(date_format) {
+        problems.push(format!(
+            "Invalid date format {date_format:?}, using RFC 3339"
+        ));
+    }
+    if !config.layout.is_valid() {
+        problems.push("Layout ratios must be positive, using the defaults".to_string());
+    }
+    let syntax_theme = &config.preview.syntax_theme;
+    if !ui::syntax_theme_exists(syntax_theme) {
+        problems.push(format!(
+            "Unknown syntax theme {syntax_theme:?}, using the default"
+        ));
     }
+    if problems.is_empty() {
+        return None;
+    }
+    let message = problems.join("; ");
+    Some(if key_errors.is_empty() {
+        StatusMessage::warning(message)
+    } else {
+        StatusMessage::error(message)
+    })
+}
+
+#[derive(Default)]
+struct KeyListParser {
+    errors: Vec<String>,
 }
 
-fn parse_key_list(list: &[String]) -> Vec<KeyBinding> {
-    list.iter()
-        .filter_map(|item| parse_key_binding(item))
-        .collect()
+impl KeyListParser {
+    fn list(&mut self, action: &str, list: &[String]) -> Vec<KeyBinding> {
+        list.iter()
+            .filter_map(|item| {
+                let binding = parse_key_binding(item);
+                if binding.is_none() {
+                    self.errors.push(format!("keys.{action} = {item:?}"));
+                }
+                binding
+            })
+            .collect()
+    }
 }
 
 fn parse_key_binding(value: &str) -> Option<KeyBinding> {
@@ -393,6 +989,95 @@ fn matches_any(key: KeyEvent, bindings: &[KeyBinding]) -> bool {
     bindings.iter().any(|binding| binding.matches(key))
 }
 
+/// Rows moved by the paging keys of the file list, or `None` for other keys.
+fn list_page_rows(app: &App, key: KeyEvent) -> Option<isize> {
+    let keys = &app.keymap.normal;
+    let page = app.list_viewport.max(1) as isize;
+    let half_page = (page / 2).max(1);
+    if matches_any(key, &keys.page_up) {
+        Some(-page)
+    } else if matches_any(key, &keys.page_down) {
+        Some(page)
+    } else if matches_any(key, &keys.half_page_up) {
+        Some(-half_page)
+    } else if matches_any(key, &keys.half_page_down) {
+        Some(half_page)
+    } else {
+        None
+    }
+}
+
+/// How a copy-prefix key turns the selected entry into clipboard text, or `None` for
+/// other keys.
+fn copy_path_text(keys: &CopyKeyMap, key: KeyEvent) -> Option<fn(&FileEntry) -> String> {
+    if matches_any(key, &keys.copy_path) {
+        Some(|entry| entry.path.to_string_lossy().into_owned())
+    } else if matches_any(key, &keys.copy_name) {
+        Some(|entry| entry.name.clone())
+    } else if matches_any(key, &keys.copy_relative_path) {
+        Some(|entry| {
+            let cwd = env::current_dir().unwrap_or_default();
+            core::relative_path(&entry.path, &cwd)
+                .to_string_lossy()
+                .into_owned()
+        })
+    } else if matches_any(key, &keys.copy_parent_path) {
+        Some(|entry| {
+            let parent = entry.path.parent().unwrap_or(&entry.path);
+            parent.to_string_lossy().into_owned()
+        })
+    } else {
+        None
+    }
+}
+
+/// Strips a leading `!` that inverts a list filter. A lone `!` is no filter at all.
+fn split_inverted(filter: &str) -> (bool, &str) {
+    match filter.trim().strip_prefix('!') {
+        Some(rest) if !rest.trim().is_empty() => (true, rest),
+        Some(_) => (false, ""),
+        None => (false, filter),
+    }
+}
+
+/// Splits `type:`/`t:` tokens off a list filter. Each token yields its category, or
+/// `None` for unknown names (which match nothing); the rest is the name query.
+fn parse_type_filter(query: &str) -> (Vec<Option<FileCategory>>, String) {
+    let mut categories = Vec::new();
+    let mut words = Vec::new();
+    for word in query.split_whitespace() {
+        match word
+            .strip_prefix("type:")
+            .or_else(|| word.strip_prefix("t:"))
+        {
+            Some(name) => categories.push(FileCategory::from_name(name)),
+            None => words.push(word),
+        }
+    }
+    (categories, words.join(" "))
+}
+
+/// Splits the open-with filter into leading `dir:`/`d:` directory filters, the program
+/// query (both lowercase) and the rest, which holds the program's arguments.
+fn parse_program_filter(filter: &str) -> (Vec<String>, String, &str) {
+    let mut dirs = Vec::new();
+    let mut rest = filter.trim_start();
+    loop {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let word = rest[..end].to_ascii_lowercase();
+        let dir = word
+            .strip_prefix("dir:")
+            .or_else(|| word.strip_prefix("d:"));
+        match dir {
+            Some(dir) => {
+                dirs.push(dir.to_string());
+                rest = rest[end..].trim_start();
+            }
+            None => return (dirs, word, &rest[end..]),
+        }
+    }
+}
+
 fn parse_marker_filter(query: &str) -> (MarkerFilterMode, String) {
     let trimmed = query.trim();
     if trimmed.is_empty() {
@@ -428,6 +1113,7 @@ impl MarkerListState {
             .map(|(name, path)| MarkerListEntry {
                 name: name.clone(),
                 path: path.clone(),
+                is_file: path.is_file(),
             })
             .collect();
         entries.sort_by(|a, b| {
@@ -458,6 +1144,7 @@ impl MarkerListState {
             .map(|(name, path)| MarkerListEntry {
                 name: name.clone(),
                 path: path.clone(),
+                is_file: path.is_file(),
             })
             .collect();
         entries.sort_by(|a, b| {
@@ -519,8 +1206,181 @@ impl MarkerListState {
     }
 }
 
+impl PaletteState {
+    fn new(keys: &KeyBindings) -> Self {
+        let commands = palette_commands(keys);
+        let filtered_indices = (0..commands.len()).collect();
+        Self {
+            commands,
+            filtered_indices,
+            selected: 0,
+            filter: String::new(),
+        }
+    }
+
+    fn selected_command(&self) -> Option<&PaletteCommand> {
+        let index = *self.filtered_indices.get(self.selected)?;
+        self.commands.get(index)
+    }
+
+    /// Keeps the commands matching `value` fuzzily, best matches first.
+    fn update_filter(&mut self, value: String) {
+        self.filter = value;
+        let query: Vec<char> = self
+            .filter
+            .to_lowercase()
+            .chars()
+            .filter(|ch| !ch.is_whitespace())
+            .collect();
+        let mut scored: Vec<(i64, usize)> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, command)| {
+                fuzzy::score(&query, command.name).map(|score| (score, index))
+            })
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.filtered_indices = scored.into_iter().map(|(_, index)| index).collect();
+        self.selected = 0;
+    }
+}
+
+/// Every action reachable from normal mode, with the key sequence that runs it.
+fn palette_commands(keys: &KeyBindings) -> Vec<PaletteCommand> {
+    let command = |name: &'static str, steps: &[&Vec<String>]| {
+        let first: Option<Vec<&String>> = steps.iter().map(|step| step.first()).collect();
+        let label = first
+            .iter()
+            .flatten()
+            .map(|key| key.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let keys =
+            first.and_then(|keys| keys.into_iter().map(|key| parse_key_binding(key)).collect());
+        PaletteCommand { name, label, keys }
+    };
+    let normal = &keys.normal;
+    vec![
+        command("Open", &[&normal.open]),
+        command("Parent directory", &[&normal.parent]),
+        command("Go to first entry", &[&normal.top]),
+        command("Go to last entry", &[&normal.bottom]),
+        command("Search (regex)", &[&normal.search]),
+        command("Search (fuzzy)", &[&normal.fuzzy_search]),
+        command("Clear filter", &[&normal.clear_filter]),
+        command("Search file contents", &[&normal.content_search]),
+        command("Scan for extension mismatches", &[&normal.mismatch_scan]),
+        command("Go to symlink target", &[&normal.reveal_link]),
+        command("Show in file manager", &[&normal.file_manager]),
+        command("Directory size", &[&normal.dir_size]),
+        command("New directory", &[&normal.add, &keys.add.dir]),
+        command("Rename", &[&normal.rename]),
+        command("Bulk rename", &[&normal.bulk_rename]),
+        command("Change permissions", &[&normal.chmod]),
+        command("Delete", &[&normal.delete, &keys.delete.confirm]),
+        command("Undo", &[&normal.undo]),
+        command("Edit file", &[&normal.edit]),
+        command("Mark/unmark entry", &[&normal.toggle_mark]),
+        command("Copy", &[&normal.copy]),
+        command("Cut", &[&normal.cut]),
+        command("Paste", &[&normal.paste]),
+        command("Paste files from system clipboard", &[&normal.paste_system]),
+        command("Copy path", &[&normal.copy, &keys.copy.copy_path]),
+        command("Copy name", &[&normal.copy, &keys.copy.copy_name]),
+        command(
+            "Copy relative path",
+            &[&normal.copy, &keys.copy.copy_relative_path],
+        ),
+        command(
+            "Copy parent path",
+            &[&normal.copy, &keys.copy.copy_parent_path],
+        ),
+        command(
+            "Copy file contents",
+            &[&normal.copy, &keys.copy.copy_contents],
+        ),
+        command("Duplicate", &[&normal.copy, &keys.copy.duplicate]),
+        command("Set marker", &[&normal.marker_set]),
+        command("Marker list", &[&normal.marker_list]),
+        command("Jump to marker", &[&normal.marker_jump]),
+        command("Go to path", &[&normal.go_to_path]),
+        command("Open with…", &[&normal.open_with_picker]),
+        command("Open shell", &[&normal.open_shell]),
+        command("Open terminal", &[&normal.open_terminal]),
+        command("Focus preview", &[&normal.focus_preview]),
+        command(
+            "Toggle permissions (metadata bar)",
+            &[&normal.settings, &keys.settings.toggle_permissions],
+        ),
+        command(
+            "Toggle dates (metadata bar)",
+            &[&normal.settings, &keys.settings.toggle_dates],
+        ),
+        command(
+            "Toggle owner (metadata bar)",
+            &[&normal.settings, &keys.settings.toggle_owner],
+        ),
+        command(
+            "Toggle size (metadata bar)",
+            &[&normal.settings, &keys.settings.toggle_size],
+        ),
+        command(
+            "Toggle relative dates",
+            &[&normal.settings, &keys.settings.toggle_relative_dates],
+        ),
+        command(
+            "Toggle metadata bar",
+            &[&normal.settings, &keys.settings.toggle_metadata],
+        ),
+        command(
+            "Cycle hidden files",
+            &[&normal.settings, &keys.settings.toggle_hidden],
+        ),
+        command(
+            "Toggle sticky filter",
+            &[&normal.settings, &keys.settings.toggle_sticky_filter],
+        ),
+        command(
+            "Toggle permissions column",
+            &[&normal.view, &keys.view.toggle_list_permissions],
+        ),
+        command(
+            "Toggle owner column",
+            &[&normal.view, &keys.view.toggle_list_owner],
+        ),
+        command(
+            "Toggle size column",
+            &[&normal.view, &keys.view.toggle_list_size],
+        ),
+        command(
+            "Toggle preview word wrap",
+            &[&normal.view, &keys.view.toggle_preview_wrap],
+        ),
+        command(
+            "Toggle flatten view",
+            &[&normal.view, &keys.view.toggle_flatten],
+        ),
+        command(
+            "Toggle dual pane",
+            &[&normal.view, &keys.view.toggle_dual_pane],
+        ),
+        command("Sort by name", &[&normal.sort, &keys.sort.by_name]),
+        command("Sort by size", &[&normal.sort, &keys.sort.by_size]),
+        command(
+            "Sort by modification time",
+            &[&normal.sort, &keys.sort.by_modified],
+        ),
+        command(
+            "Reverse sort direction",
+            &[&normal.sort, &keys.sort.reverse],
+        ),
+        command("Quit", &[&normal.quit]),
+    ]
+}
+
 impl ProgramListState {
-    fn new(programs: &[ProgramEntry]) -> Self {
+    fn new(programs: &[ProgramEntry], remembered: Option<String>) -> Self {
         let mut entries = programs.to_vec();
         entries.sort_by(|a, b| {
             a.name
@@ -528,12 +1388,28 @@ impl ProgramListState {
                 .cmp(&b.name.to_ascii_lowercase())
         });
         let filtered_indices = (0..entries.len()).collect();
-        Self {
+        let mut list = Self {
             entries,
             filtered_indices,
             selected: 0,
             filter: String::new(),
-        }
+            remembered,
+        };
+        let remembered = list.remembered.clone();
+        list.apply_filter(remembered.as_deref());
+        list
+    }
+
+    /// Swaps in a finished program scan, keeping the filter and selection.
+    fn set_programs(&mut self, programs: &[ProgramEntry]) {
+        let preferred = self
+            .selected_entry()
+            .map(|entry| entry.name.clone())
+            .or_else(|| self.remembered.clone());
+        let filter = std::mem::take(&mut self.filter);
+        *self = Self::new(programs, self.remembered.take());
+        self.filter = filter;
+        self.apply_filter(preferred.as_deref());
     }
 
     fn selected_entry(&self) -> Option<&ProgramEntry> {
@@ -547,9 +1423,15 @@ impl ProgramListState {
         self.apply_filter(preferred.as_deref());
     }
 
+    /// Words after the program name in the filter, passed to the program.
+    fn args(&self) -> Vec<String> {
+        let (_, _, rest) = parse_program_filter(&self.filter);
+        core::split_args(rest)
+    }
+
     fn apply_filter(&mut self, preferred: Option<&str>) {
-        let query = self.filter.trim().to_ascii_lowercase();
-        self.filtered_indices = if query.is_empty() {
+        let (dirs, query, _) = parse_program_filter(&self.filter);
+        self.filtered_indices = if query.is_empty() && dirs.is_empty() {
             (0..self.entries.len()).collect()
         } else {
             self.entries
@@ -558,7 +1440,13 @@ impl ProgramListState {
                 .filter(|(_, entry)| {
                     let name = entry.name.to_ascii_lowercase();
                     let path = entry.path.to_string_lossy().to_ascii_lowercase();
-                    name.contains(&query) || path.contains(&query)
+                    let dir = entry
+                        .path
+                        .parent()
+                        .map(|dir| dir.to_string_lossy().to_ascii_lowercase())
+                        .unwrap_or_default();
+                    dirs.iter().all(|filter| dir.contains(filter.as_str()))
+                        && (name.contains(&query) || path.contains(&query))
                 })
                 .map(|(index, _)| index)
                 .collect()
@@ -591,17 +1479,90 @@ enum AppEvent {
         id: u64,
         target: DirTarget,
         entries: Vec<FileEntry>,
+        /// Replaces a listing restored from the cache instead of adding to it.
+        replace: bool,
         done: bool,
+        /// Whether the finished listing may be cached, sent with `done`.
+        cache: bool,
     },
     ImageReady {
         version: u64,
         protocol: Box<dyn StatefulProtocol>,
     },
+    ContentMatches {
+        id: u64,
+        matches: Vec<ContentMatch>,
+        done: bool,
+    },
     Action(ActionResult),
+    ConfigChanged,
+    FreeSpace {
+        id: u64,
+        bytes: Option<u64>,
+    },
+    /// Content categories of listed files without an extension.
+    Categories(Vec<(PathBuf, FileCategory)>),
+    /// Text read from the system clipboard for pasting paths.
+    SystemClipboard(Result<String, String>),
+    /// Text read from the system clipboard for the open input prompt.
+    InputClipboard(Result<String, String>),
+    /// Sent periodically while a file operation or directory size calculation runs so
+    /// its progress gets redrawn.
+    OperationProgress,
+    /// Sent before a task that reports back with `ActionResult::Refresh` starts, so
+    /// quitting can wait for it.
+    ActionStarted,
+    /// Programs on `PATH` for the open-with list, scanned after startup.
+    Programs(Vec<ProgramEntry>),
+    /// Outcome of a background task that only reports back in the status line.
+    Status(StatusMessage),
+    /// Recursive size of a directory whose size was asked for.
+    DirSize {
+        path: PathBuf,
+        size: u64,
+    },
+    /// Entries were added, removed or renamed in the watched directory.
+    DirChanged(PathBuf),
+    /// The wait after the first `DirChanged` is over; re-list the directory.
+    WatchRefresh(PathBuf),
+    /// A file to open was checked; `detail` says why to ask before opening it.
+    OpenChecked {
+        path: PathBuf,
+        detail: Option<String>,
+    },
+    /// Markers read from a file to import.
+    MarkersRead(std::io::Result<HashMap<String, PathBuf>>),
+}
+
+/// The recursive size of a directory, computed on request.
+struct DirSize {
+    path: PathBuf,
+    /// `None` while the walk is still running.
+    size: Option<u64>,
+    started: Instant,
+    cancel: Arc<AtomicBool>,
+}
+
+impl DirSize {
+    fn spinner(&self) -> char {
+        let frame = self.started.elapsed().as_millis() / 250;
+        SPINNER[frame as usize % SPINNER.len()]
+    }
+}
+
+/// A paste or delete running in the background, which the cancel key can stop.
+struct Operation {
+    label: &'static str,
+    task: tokio::task::AbortHandle,
+    progress: Arc<core::Progress>,
 }
 
 enum ActionResult {
-    Refresh { select: Option<PathBuf> },
+    Refresh {
+        select: Option<PathBuf>,
+        message: Option<StatusMessage>,
+        undo: Option<UndoEntry>,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -609,6 +1570,7 @@ enum SuspendAction {
     Shell(PathBuf),
     OpenWith {
         program: PathBuf,
+        args: Vec<String>,
         path: PathBuf,
         cwd: PathBuf,
     },
@@ -628,89 +1590,250 @@ struct App {
     picker: Picker,
     current_dir: PathBuf,
     parent_entries: Vec<FileEntry>,
+    listing_cache: ListingCache,
+    /// Set while the listing of the current, parent or other pane's directory has not
+    /// finished.
+    current_loading: bool,
+    parent_loading: bool,
+    other_loading: bool,
+    /// The second directory in dual-pane mode, which pastes go to.
+    other_pane: Option<Pane>,
     current_entries: Vec<FileEntry>,
     filtered_indices: Vec<usize>,
     selected: usize,
+    marked: HashSet<PathBuf>,
     filter: String,
+    filter_fuzzy: bool,
+    /// What `filter` matched in names, for the UI; `None` when inverted or empty.
+    filter_highlight: Option<FilterHighlight>,
+    /// Keeps `filter` when changing directories instead of clearing it.
+    sticky_filter: bool,
     show_hidden: bool,
+    /// List the files below the current directory instead of its entries.
+    flatten: bool,
+    /// With `show_hidden`, sort dotfiles after everything else.
+    hidden_last: bool,
+    sort_mode: SortMode,
+    sort_reverse: bool,
     mode: Mode,
     pending_prefix: Option<PendingPrefix>,
     marker_list: Option<MarkerListState>,
     program_list: Option<ProgramListState>,
+    palette: Option<PaletteState>,
     programs: Vec<ProgramEntry>,
+    content_search: Option<ContentSearchState>,
+    content_search_id: u64,
     preview: Option<Preview>,
     highlighted_preview: Option<ui::HighlightedText>,
+    preview_scroll: ui::PreviewScroll,
+    /// Visible rows of the current list, updated on every render.
+    list_viewport: u16,
+    list_geometry: ui::ListGeometry,
+    /// Time and list position of the last click, for detecting double clicks.
+    last_click: Option<(Instant, usize)>,
+    preview_focused: bool,
+    preview_wrap: bool,
     show_metadata: bool,
     show_permissions: bool,
     show_dates: bool,
+    /// Shows dates in the other form than `date_format`: relative, or RFC 3339 when the
+    /// configured format is already relative.
+    relative_dates: bool,
     show_owner: bool,
+    show_size: bool,
     show_list_permissions: bool,
     show_list_owner: bool,
+    show_list_size: bool,
     preview_request_id: u64,
     preview_pending: bool,
+    /// A preview was asked for while nothing showed it, see [`App::preview_shown`].
+    preview_skipped: bool,
+    /// The preview load in flight (or waiting out the debounce), aborted when superseded.
+    preview_task: Option<tokio::task::AbortHandle>,
+    last_preview_request: Option<Instant>,
+    preview_cache: PreviewCache,
+    /// Cache key of the file the pending preview request is for.
+    preview_key: Option<CacheKey>,
     listing_id: u64,
+    /// Free bytes on the filesystem of `current_dir`; `None` while unknown.
+    free_space: Option<u64>,
     pending_selection: Option<PathBuf>,
+    /// Entry last selected in each visited directory, restored when it is entered again.
+    dir_positions: HashMap<PathBuf, PathBuf>,
     image_state: Option<ui::ThreadProtocol>,
     image_version: u64,
     image_worker_tx: Sender<(u64, Box<dyn StatefulProtocol>, Resize, Rect)>,
     clipboard: Option<ClipboardEntry>,
     markers: MarkerStore,
+    last_programs: LastPrograms,
+    search_history: SearchHistory,
+    status: Option<StatusMessage>,
+    operation: Option<Operation>,
+    dir_size: Option<DirSize>,
+    /// A `type:` filter needs the content category of files without an extension.
+    sniff_wanted: bool,
+    /// Categories of such files are being read.
+    sniffing: bool,
+    undo_stack: Vec<UndoEntry>,
+    /// File operations started but not yet reported back through `ActionResult::Refresh`.
+    pending_actions: usize,
+    /// Quit as soon as `pending_actions` drops to zero.
+    quit_when_idle: bool,
+    dir_watcher: Option<RecommendedWatcher>,
+    watched_dir: Option<PathBuf>,
+    /// A `WatchRefresh` is on its way.
+    watch_refresh_pending: bool,
+    /// Entries near the selection before a watch refresh, nearest first, for when the
+    /// selected one is gone afterwards.
+    selection_fallback: Vec<PathBuf>,
 }
 
 impl App {
     async fn new(
         config: Config,
+        start: StartLocation,
         picker: Picker,
         image_worker_tx: Sender<(u64, Box<dyn StatefulProtocol>, Resize, Rect)>,
         tx: &tokio_mpsc::UnboundedSender<AppEvent>,
-    ) -> Result<Self, core::CoreError> {
-        let current_dir = env::current_dir()?;
+    ) -> Self {
         let markers = MarkerStore::load().await;
-        let programs = match tokio::task::spawn_blocking(scan_programs).await {
-            Ok(programs) => programs,
-            Err(_) => Vec::new(),
+        let last_programs = LastPrograms::load().await;
+        let search_history = if config.search.save_history {
+            SearchHistory::load(config.search.history_size).await
+        } else {
+            SearchHistory::new(config.search.history_size)
         };
-        let keymap = KeyMap::from_config(&config);
+        let (keymap, key_errors) = KeyMap::from_config(&config);
+        let status = config_message(&config, &key_errors)
+            .or_else(|| start.warning.map(StatusMessage::warning));
+        let listing_cache = ListingCache::new(Duration::from_secs(config.listing_cache_secs));
         let mut app = Self {
             show_metadata: config.metadata_bar.enabled,
             show_permissions: config.metadata_bar.show_permissions,
             show_dates: config.metadata_bar.show_dates,
+            relative_dates: false,
             show_owner: config.metadata_bar.show_owner,
+            show_size: config.metadata_bar.show_size,
             show_list_permissions: false,
             show_list_owner: false,
+            show_list_size: false,
+            preview_wrap: config.preview.wrap,
             config,
             keymap,
             picker,
-            current_dir,
+            current_dir: start.dir,
             parent_entries: Vec::new(),
+            listing_cache,
+            current_loading: false,
+            parent_loading: false,
+            other_loading: false,
+            other_pane: None,
             current_entries: Vec::new(),
             filtered_indices: Vec::new(),
             selected: 0,
+            marked: HashSet::new(),
             filter: String::new(),
+            filter_fuzzy: false,
+            filter_highlight: None,
+            sticky_filter: false,
             show_hidden: true,
+            flatten: false,
+            hidden_last: false,
+            sort_mode: SortMode::Name,
+            sort_reverse: false,
             mode: Mode::Normal,
             pending_prefix: None,
             marker_list: None,
             program_list: None,
-            programs,
+            palette: None,
+            programs: Vec::new(),
+            content_search: None,
+            content_search_id: 0,
             preview: None,
             highlighted_preview: None,
+            preview_scroll: ui::PreviewScroll::default(),
+            list_viewport: 0,
+            list_geometry: ui::ListGeometry::default(),
+            last_click: None,
+            preview_focused: false,
             preview_request_id: 0,
             preview_pending: false,
+            preview_skipped: false,
+            preview_task: None,
+            last_preview_request: None,
+            preview_cache: PreviewCache::default(),
+            preview_key: None,
             listing_id: 0,
-            pending_selection: None,
+            free_space: None,
+            pending_selection: start.select,
+            selection_fallback: Vec::new(),
+            dir_positions: HashMap::new(),
             image_state: None,
             image_version: 0,
             image_worker_tx,
             clipboard: None,
             markers,
+            last_programs,
+            search_history,
+            status,
+            operation: None,
+            dir_size: None,
+            sniff_wanted: false,
+            sniffing: false,
+            undo_stack: Vec::new(),
+            pending_actions: 0,
+            quit_when_idle: false,
+            dir_watcher: None,
+            watched_dir: None,
+            watch_refresh_pending: false,
         };
+        // The config's settings stand in for toggles the saved state does not have.
+        if let Some(view) = ViewState::load(app.view_state()).await {
+            app.set_view_state(view);
+        }
         app.refresh_dirs(tx);
-        Ok(app)
+        spawn_program_scan(tx.clone(), app.config.open_with.cache_programs);
+        app
+    }
+
+    fn reload_config(&mut self) {
+        match self.config.reload() {
+            Ok(config) => {
+                let (keymap, key_errors) = KeyMap::from_config(&config);
+                self.keymap = keymap;
+                self.config = config;
+                self.preview_cache.clear();
+                self.listing_cache
+                    .set_ttl(Duration::from_secs(self.config.listing_cache_secs));
+                if !self.config.watch_dir {
+                    self.dir_watcher = None;
+                    self.watched_dir = None;
+                }
+                self.highlighted_preview = self
+                    .preview
+                    .as_ref()
+                    .and_then(|preview| ui::highlight_preview(preview, &self.config));
+                self.status = config_message(&self.config, &key_errors)
+                    .or_else(|| {
+                        // Before the first program scan every slot would look unresolved.
+                        (!self.programs.is_empty())
+                            .then(|| self.quick_slot_warning())
+                            .flatten()
+                    })
+                    .or_else(|| Some(StatusMessage::info("Config reloaded")));
+                self.resort_entries();
+            }
+            Err(err) => {
+                self.status = Some(StatusMessage::error(format!(
+                    "Config reload failed, keeping previous config: {err}"
+                )));
+            }
+        }
     }
 
     fn ui_state(&mut self) -> ui::UiState<'_> {
         let input = self.input_prompt();
+        let dir_size = self.selected_dir_size();
         let image_state = self.image_state.as_mut();
         let marker_popup = self.marker_list.as_ref().map(|list| ui::MarkerPopup {
             items: list
@@ -720,6 +1843,7 @@ impl App {
                 .map(|entry| ui::MarkerListItem {
                     name: entry.name.clone(),
                     path: entry.path.to_string_lossy().to_string(),
+                    is_file: entry.is_file,
                 })
                 .collect(),
             selected: list.selected,
@@ -731,67 +1855,252 @@ impl App {
                 .filter_map(|&index| list.entries.get(index))
                 .map(|entry| ui::ProgramListItem {
                     name: entry.name.clone(),
-                    path: entry.path.to_string_lossy().to_string(),
+                    dir: entry
+                        .path
+                        .parent()
+                        .map(|dir| dir.to_string_lossy().to_string())
+                        .unwrap_or_default(),
                 })
                 .collect(),
             selected: list.selected,
             filter: list.filter.clone(),
         });
+        let palette_popup = self.palette.as_ref().map(|palette| ui::PalettePopup {
+            items: palette
+                .filtered_indices
+                .iter()
+                .filter_map(|&index| palette.commands.get(index))
+                .map(|command| ui::PaletteItem {
+                    name: command.name.to_string(),
+                    keys: command.label.clone(),
+                })
+                .collect(),
+            selected: palette.selected,
+            filter: palette.filter.clone(),
+        });
+        let rename_popup = match &self.mode {
+            Mode::Input(InputState {
+                action: InputAction::ConfirmBulkRename(plan),
+                ..
+            }) => Some(ui::ListPopup {
+                title: format!("Rename {} entries? (y/n)", plan.len()),
+                items: plan
+                    .iter()
+                    .map(|(from, to)| {
+                        format!(
+                            "{} -> {}",
+                            from.file_name().unwrap_or_default().to_string_lossy(),
+                            to.file_name().unwrap_or_default().to_string_lossy()
+                        )
+                    })
+                    .collect(),
+                selected: 0,
+            }),
+            _ => None,
+        };
+        let search_popup = self.content_search.as_ref().map(|search| {
+            let label = match search.kind {
+                SearchKind::Contents => "Contents",
+                SearchKind::Mismatches => "Mismatches",
+            };
+            ui::ListPopup {
+                title: if search.done {
+                    format!("{label}: {} ({})", search.query, search.matches.len())
+                } else {
+                    format!(
+                        "{label}: {} ({}, searching)",
+                        search.query,
+                        search.matches.len()
+                    )
+                },
+                items: search
+                    .matches
+                    .iter()
+                    .map(|item| match search.kind {
+                        SearchKind::Contents => {
+                            format!("{}:{}  {}", item.relative, item.line, item.text)
+                        }
+                        SearchKind::Mismatches => format!("{}  {}", item.relative, item.text),
+                    })
+                    .collect(),
+                selected: search.selected,
+            }
+        });
         ui::UiState {
             config: &self.config,
             parent: &self.parent_entries,
             current: &self.current_entries,
             current_indices: &self.filtered_indices,
             selected: self.selected,
+            marked: &self.marked,
+            filter: self.filter.trim(),
+            filter_highlight: self.filter_highlight.as_ref(),
+            sticky_filter: self.sticky_filter,
+            show_hidden: self.show_hidden,
+            hidden_last: self.hidden_last,
+            flatten: self.flatten,
+            current_loading: self.current_loading,
+            parent_loading: self.parent_loading,
+            other_pane: self.other_pane.as_ref().map(|pane| ui::OtherPane {
+                dir: pane.dir.to_string_lossy().to_string(),
+                entries: &pane.entries,
+                selected: pane
+                    .selected
+                    .as_ref()
+                    .and_then(|path| pane.entries.iter().position(|entry| &entry.path == path)),
+                loading: self.other_loading,
+            }),
+            clipboard: self.clipboard.as_ref().map(|clipboard| {
+                let verb = match clipboard.op {
+                    ClipboardOp::Copy => "copied",
+                    ClipboardOp::Cut => "cut",
+                };
+                format!("{} {verb}", clipboard.paths.len())
+            }),
+            sort_mode: self.sort_mode,
+            sort_reverse: self.sort_reverse,
             preview: self.preview.as_ref(),
             highlighted_preview: self.highlighted_preview.as_ref(),
+            preview_scroll: &mut self.preview_scroll,
+            list_viewport: &mut self.list_viewport,
+            list_geometry: &mut self.list_geometry,
+            preview_focused: self.preview_focused,
+            preview_wrap: self.preview_wrap,
             show_metadata: self.show_metadata,
             show_permissions: self.show_permissions,
             show_dates: self.show_dates,
+            relative_dates: self.relative_dates,
             show_owner: self.show_owner,
+            show_size: self.show_size,
             show_list_permissions: self.show_list_permissions,
             show_list_owner: self.show_list_owner,
+            show_list_size: self.show_list_size,
             metadata: self
                 .preview
                 .as_ref()
                 .and_then(|preview| preview.metadata.as_ref()),
+            dir_size,
             image_state,
+            status: self.status.as_ref(),
+            free_space: self.free_space,
+            activity: self
+                .operation
+                .as_ref()
+                .map(|operation| {
+                    let progress = &operation.progress;
+                    let files = progress.files.load(Ordering::Relaxed);
+                    if progress.is_cancelled() {
+                        format!("{}: cancelling…", operation.label)
+                    } else if operation.label == "Copying" {
+                        format!(
+                            "Copying: {files} files, {} (esc to cancel)",
+                            ui::human_size(progress.bytes.load(Ordering::Relaxed))
+                        )
+                    } else {
+                        format!("{}: {files} done (esc to cancel)", operation.label)
+                    }
+                })
+                // Without the metadata bar the spinner goes to the status line instead.
+                .or_else(|| {
+                    let dir_size = self.dir_size.as_ref()?;
+                    (dir_size.size.is_none() && !self.show_metadata)
+                        .then(|| format!("Size: {} computing (esc to cancel)", dir_size.spinner()))
+                }),
             input,
             marker_popup,
             program_popup,
+            palette_popup,
+            list_popup: rename_popup.or(search_popup),
         }
     }
 
     fn input_prompt(&self) -> Option<ui::InputPrompt> {
         match &self.mode {
+            // The dry-run list popup carries its own y/n prompt.
+            Mode::Input(InputState {
+                action: InputAction::ConfirmBulkRename(_),
+                ..
+            }) => None,
             Mode::Input(input) => {
-                let value = if matches!(input.action.clone(), InputAction::ConfirmDelete) {
-                    "y/n".to_string()
+                let value = if let InputAction::Confirm(action) = &input.action {
+                    match action {
+                        Destructive::Delete(_) if self.marked.is_empty() => "y/n".to_string(),
+                        Destructive::Delete(paths) => format!("y/n ({} marked)", paths.len()),
+                        Destructive::Overwrite(request) => format!(
+                            "{} of {} exist: (o)verwrite, (s)kip, (r)ename",
+                            request.conflicts,
+                            request.pairs.len()
+                        ),
+                        Destructive::CrossDeviceMove(request, _) => format!(
+                            "copy {} item(s), then delete the originals? y/n",
+                            request.pairs.len()
+                        ),
+                    }
+                } else if let InputAction::ConfirmRename { extension, .. } = &input.action {
+                    format!(
+                        "{} has no .{extension} extension; rename anyway? y/n (n edits the name)",
+                        input.buffer.trim()
+                    )
+                } else if let InputAction::ConfirmOpen { detail, .. } = &input.action {
+                    format!("{detail}; open anyway? y/n")
+                } else if let InputAction::ConfirmMarkerExport(path) = &input.action {
+                    format!("{} exists; overwrite it? y/n", path.display())
+                } else if matches!(input.action, InputAction::ConfirmQuit) {
+                    format!(
+                        "{} operation(s) still running: (q)uit now, (w)ait and quit, (n)o",
+                        self.pending_actions
+                    )
                 } else {
-                    format!("{}|", input.buffer)
+                    return Some(ui::InputPrompt {
+                        title: input.title().to_string(),
+                        value: input.buffer.clone(),
+                        cursor: Some(input.cursor),
+                    });
                 };
                 Some(ui::InputPrompt {
                     title: input.title().to_string(),
                     value,
+                    cursor: None,
                 })
             }
             Mode::MarkerList => None,
             Mode::ProgramList => None,
+            Mode::SearchResults => None,
+            Mode::Palette => None,
             Mode::Normal => None,
         }
     }
 
+    /// Whether the preview, or the metadata bar that is read from it, is on screen. The
+    /// other pane of the dual-pane mode takes the preview's place.
+    fn preview_shown(&self) -> bool {
+        self.other_pane.is_none() || self.show_metadata
+    }
+
     fn clear_preview(&mut self) {
+        self.preview_scroll.offset = 0;
         self.preview = None;
         self.highlighted_preview = None;
         self.image_state = None;
         self.preview_pending = false;
     }
 
-    fn select_up(&mut self) -> bool {
-        if self.selected > 0 {
-            self.selected -= 1;
-            self.clear_preview();
+    /// Scrolls by `lines`; the upper bound is clamped to the text on the next render.
+    fn scroll_preview(&mut self, lines: i32) -> bool {
+        let offset = i32::from(self.preview_scroll.offset)
+            .saturating_add(lines)
+            .clamp(0, i32::from(u16::MAX)) as u16;
+        if offset == self.preview_scroll.offset {
+            return false;
+        }
+        self.preview_scroll.offset = offset;
+        true
+    }
+
+    fn select_up(&mut self) -> bool {
+        if self.selected > 0 {
+            self.selected -= 1;
+            self.clear_preview();
             return true;
         }
         false
@@ -806,54 +2115,233 @@ impl App {
         false
     }
 
+    /// Selects the entry at `index`, returning whether the selection moved.
+    fn select_index(&mut self, index: usize) -> bool {
+        if self.filtered_indices.is_empty() || index == self.selected {
+            return false;
+        }
+        self.selected = index.min(self.filtered_indices.len() - 1);
+        self.clear_preview();
+        true
+    }
+
+    /// Moves the selection by `rows`, clamped to the list bounds.
+    fn select_by(&mut self, rows: isize) -> bool {
+        self.select_index(self.selected.saturating_add_signed(rows))
+    }
+
     fn activate_selected(&mut self, tx: &tokio_mpsc::UnboundedSender<AppEvent>) -> bool {
         let Some(entry) = self.selected_entry() else {
             return false;
         };
+        if self.flatten {
+            // Results are shown where they live, with the plain listing back.
+            let path = entry.path.clone();
+            self.flatten = false;
+            self.reveal_path(&path, tx);
+            return true;
+        }
         if entry.is_dir {
-            self.current_dir = entry.path.clone();
-            self.selected = 0;
-            self.pending_selection = None;
-            self.clear_preview();
-            self.refresh_dirs(tx);
+            if entry.is_symlink && self.config.symlink_dirs == SymlinkDirs::Reveal {
+                let entry = entry.clone();
+                self.reveal_link_target(&entry, tx);
+            } else {
+                // The link path is kept as is, so the parent is the directory holding it.
+                self.enter_dir(entry.path.clone(), None, tx);
+            }
             return true;
         }
-        spawn_open(entry.path.clone());
+        let path = entry.path.clone();
+        self.check_and_open(path, tx);
         false
     }
 
+    /// Runs the mismatch and executable checks for `path` in the background, reusing the
+    /// preview's mismatch result when it has one; [`AppEvent::OpenChecked`] then opens the
+    /// file or asks first.
+    fn check_and_open(&self, path: PathBuf, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        let check_mismatch = self.config.check_mismatch
+            && self.config.security.on_mismatch == MismatchAction::Confirm;
+        let check_executable = self.config.security.confirm_executables;
+        let known = self
+            .preview
+            .as_ref()
+            .filter(|preview| preview.path == path)
+            .and_then(|preview| preview.mismatch.clone());
+        let equivalents = self.config.security.equivalent_extensions.clone();
+        let tx = tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let mismatch = if check_mismatch {
+                known.or_else(|| security::check_path_mismatch(&path, &equivalents).ok())
+            } else {
+                None
+            };
+            let detail = match mismatch {
+                Some(MismatchStatus::Mismatch {
+                    detected,
+                    extension,
+                }) => Some(format!(
+                    "Looks like {} ({}), not .{extension}",
+                    detected.extension, detected.mime
+                )),
+                _ => None,
+            }
+            .or_else(|| check_executable.then(|| executable_detail(&path)).flatten());
+            let _ = tx.send(AppEvent::OpenChecked { path, detail });
+        });
+    }
+
     fn navigate_parent(&mut self, tx: &tokio_mpsc::UnboundedSender<AppEvent>) -> bool {
         let Some(parent) = self.current_dir.parent() else {
             return false;
         };
         let previous = self.current_dir.clone();
-        self.current_dir = parent.to_path_buf();
+        self.enter_dir(parent.to_path_buf(), Some(previous), tx);
+        true
+    }
+
+    fn enter_dir(
+        &mut self,
+        path: PathBuf,
+        selection: Option<PathBuf>,
+        tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    ) {
+        if let Some(entry) = self.selected_entry() {
+            let selected = entry.path.clone();
+            self.dir_positions
+                .insert(self.current_dir.clone(), selected);
+        }
+        let selection = selection.or_else(|| self.dir_positions.get(&path).cloned());
+        self.current_dir = path;
+        self.flatten = false;
         self.selected = 0;
-        self.pending_selection = Some(previous);
+        self.pending_selection = selection;
+        self.marked.clear();
+        if !self.sticky_filter {
+            self.filter.clear();
+        }
         self.clear_preview();
         self.refresh_dirs(tx);
-        true
+    }
+
+    fn toggle_mark(&mut self) -> bool {
+        let Some(path) = self.selected_entry().map(|entry| entry.path.clone()) else {
+            return false;
+        };
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+        self.select_down()
+    }
+
+    /// Resolves a bulk rename template against the target entries. Fails without renaming
+    /// anything when a name is invalid or a target collides.
+    fn bulk_rename_plan(&self, template: &str) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+        let paths = self.target_paths();
+        let width = paths.len().to_string().len();
+        let mut targets = HashSet::new();
+        let mut plan = Vec::new();
+        for (index, path) in paths.iter().enumerate() {
+            let Some(name) = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+            else {
+                continue;
+            };
+            let new_name = core::bulk_rename_name(&name, template, index, width);
+            if new_name.is_empty() || new_name.chars().any(std::path::is_separator) {
+                return Err(format!("invalid name {new_name:?} for {name}"));
+            }
+            if new_name == name {
+                continue;
+            }
+            let dest = path.with_file_name(&new_name);
+            if !targets.insert(dest.clone()) {
+                return Err(format!("{new_name} would be used more than once"));
+            }
+            if dest.exists() {
+                return Err(format!("{new_name} already exists"));
+            }
+            plan.push((path.clone(), dest));
+        }
+        Ok(plan)
+    }
+
+    /// Marked paths in listing order, or the selected entry when nothing is marked.
+    fn target_paths(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            return self
+                .selected_entry()
+                .map(|entry| vec![entry.path.clone()])
+                .unwrap_or_default();
+        }
+        self.current_entries
+            .iter()
+            .filter(|entry| self.marked.contains(&entry.path))
+            .map(|entry| entry.path.clone())
+            .collect()
     }
 
     fn request_preview(&mut self, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        if let Some(task) = self.preview_task.take() {
+            task.abort();
+        }
+        if !self.preview_shown() {
+            self.preview_skipped = true;
+            self.clear_preview();
+            return;
+        }
+        self.preview_skipped = false;
         let Some(entry) = self.selected_entry() else {
             self.preview_pending = false;
             self.preview = None;
             return;
         };
         let path = entry.path.clone();
+        // Directory previews depend on sorting and hidden files, so only files are cached.
+        // The listing may predate an edit, so the key comes from a fresh stat.
+        let key = if entry.is_dir {
+            None
+        } else {
+            std::fs::metadata(&path).ok().and_then(|metadata| {
+                let modified = metadata.modified().ok()?;
+                Some((path.clone(), modified, metadata.len()))
+            })
+        };
         self.preview_request_id = self.preview_request_id.wrapping_add(1);
+        if let Some((preview, highlighted)) =
+            key.as_ref().and_then(|key| self.preview_cache.get(key))
+        {
+            self.preview_pending = false;
+            self.preview_key = None;
+            self.show_preview(preview, highlighted);
+            return;
+        }
+        self.preview_key = key;
         let request_id = self.preview_request_id;
         let config = self.config.clone();
         let tx = tx.clone();
+        let sort = self.sort_options();
+        let show_hidden = self.show_hidden;
         self.preview_pending = true;
-        tokio::spawn(async move {
-            let result = core::load_preview(&path, &config).await;
+        // A single move previews at once; while the selection keeps changing faster than
+        // the debounce, each request waits and is aborted by the next one.
+        let now = Instant::now();
+        let rapid = self
+            .last_preview_request
+            .is_some_and(|last| now.duration_since(last) < PREVIEW_DEBOUNCE);
+        self.last_preview_request = Some(now);
+        let task = tokio::spawn(async move {
+            if rapid {
+                tokio::time::sleep(PREVIEW_DEBOUNCE).await;
+            }
+            let result = core::load_preview(&path, &config, sort, show_hidden).await;
             let _ = tx.send(AppEvent::Preview {
                 id: request_id,
                 result,
             });
         });
+        self.preview_task = Some(task.abort_handle());
     }
 
     fn apply_preview(&mut self, id: u64, result: Result<Preview, core::CoreError>) -> bool {
@@ -862,20 +2350,13 @@ impl App {
         }
         self.preview_pending = false;
         match result {
-            Ok(mut preview) => {
-                self.image_state = None;
-                self.highlighted_preview = ui::highlight_preview(&preview);
-                if let Some(image) = preview.image.take() {
-                    self.image_version = self.image_version.wrapping_add(1);
-                    let version = self.image_version;
-                    let protocol = self.picker.new_resize_protocol(image);
-                    self.image_state = Some(ui::ThreadProtocol::new(
-                        self.image_worker_tx.clone(),
-                        protocol,
-                        version,
-                    ));
+            Ok(preview) => {
+                let highlighted = ui::highlight_preview(&preview, &self.config);
+                if let Some(key) = self.preview_key.take() {
+                    self.preview_cache
+                        .insert(key, preview.clone(), highlighted.clone());
                 }
-                self.preview = Some(preview);
+                self.show_preview(preview, highlighted);
             }
             Err(_) => {
                 self.preview = None;
@@ -886,11 +2367,96 @@ impl App {
         true
     }
 
+    fn show_preview(&mut self, mut preview: Preview, highlighted: Option<ui::HighlightedText>) {
+        self.image_state = None;
+        self.highlighted_preview = highlighted;
+        if let Some(image) = preview.image.take() {
+            self.image_version = self.image_version.wrapping_add(1);
+            let version = self.image_version;
+            let protocol = self.picker.new_resize_protocol(image);
+            self.image_state = Some(ui::ThreadProtocol::new(
+                self.image_worker_tx.clone(),
+                protocol,
+                version,
+            ));
+        }
+        self.preview = Some(preview);
+    }
+
+    /// Starts walking `path` for its total size, replacing any earlier calculation.
+    fn compute_dir_size(&mut self, path: PathBuf, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        if let Some(previous) = self.dir_size.take() {
+            previous.cancel.store(true, Ordering::Relaxed);
+        }
+        let cancel = Arc::new(AtomicBool::new(false));
+        let task = {
+            let tx = tx.clone();
+            let path = path.clone();
+            let cancel = cancel.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Some(size) = core::dir_size(&path, &cancel) {
+                    let _ = tx.send(AppEvent::DirSize { path, size });
+                }
+            })
+        };
+        spawn_progress_ticker(tx, task.abort_handle());
+        self.dir_size = Some(DirSize {
+            path,
+            size: None,
+            started: Instant::now(),
+            cancel,
+        });
+    }
+
+    /// The computed size of the selected directory, or a spinner while it is computed.
+    fn selected_dir_size(&self) -> Option<String> {
+        let dir_size = self.dir_size.as_ref()?;
+        if self.selected_entry()?.path != dir_size.path {
+            return None;
+        }
+        Some(match dir_size.size {
+            Some(size) => ui::human_size(size),
+            None => format!("{} computing", dir_size.spinner()),
+        })
+    }
+
     fn selected_entry(&self) -> Option<&FileEntry> {
         let index = *self.filtered_indices.get(self.selected)?;
         self.current_entries.get(index)
     }
 
+    /// Whether a paste or delete is still running, in which case another one is refused
+    /// with a status message: only one can be followed and cancelled at a time.
+    fn operation_running(&mut self) -> bool {
+        let running = self
+            .operation
+            .as_ref()
+            .is_some_and(|operation| !operation.task.is_finished());
+        if running {
+            self.status = Some(StatusMessage::warning(
+                "Another operation is still running (esc to cancel it)",
+            ));
+        }
+        running
+    }
+
+    /// Tracks a background paste or delete so its progress is shown and it can be
+    /// cancelled.
+    fn start_operation(
+        &mut self,
+        label: &'static str,
+        task: tokio::task::AbortHandle,
+        progress: Arc<core::Progress>,
+        tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    ) {
+        spawn_progress_ticker(tx, task.clone());
+        self.operation = Some(Operation {
+            label,
+            task,
+            progress,
+        });
+    }
+
     fn refresh_dirs(&mut self, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
         self.listing_id = self.listing_id.wrapping_add(1);
         let listing_id = self.listing_id;
@@ -898,28 +2464,253 @@ impl App {
         self.parent_entries.clear();
         self.filtered_indices.clear();
         self.clear_preview();
-        spawn_dir_listing(
-            tx.clone(),
-            DirTarget::Current,
-            listing_id,
-            self.current_dir.clone(),
-        );
-        if let Some(parent) = self.current_dir.parent() {
+        self.current_loading = true;
+        self.parent_loading = self.current_dir.parent().is_some();
+        let current_dir = self.current_dir.clone();
+        if self.flatten {
+            spawn_flat_listing(
+                tx.clone(),
+                listing_id,
+                current_dir.clone(),
+                self.config.search.flatten_depth,
+                self.show_hidden,
+                false,
+            );
+        } else {
+            let cached = self.restore_listing(DirTarget::Current, &current_dir);
+            if cached {
+                self.apply_filter(self.pending_selection.clone());
+            }
+            spawn_dir_listing(
+                tx.clone(),
+                DirTarget::Current,
+                listing_id,
+                current_dir.clone(),
+                self.show_list_size,
+                cached,
+            );
+        }
+        if let Some(parent) = current_dir.parent() {
+            let cached = self.restore_listing(DirTarget::Parent, parent);
             spawn_dir_listing(
                 tx.clone(),
                 DirTarget::Parent,
                 listing_id,
                 parent.to_path_buf(),
+                false,
+                cached,
+            );
+        }
+        if let Some(dir) = self.other_pane.as_mut().map(|pane| {
+            pane.entries.clear();
+            pane.dir.clone()
+        }) {
+            self.other_loading = true;
+            let cached = self.restore_listing(DirTarget::Other, &dir);
+            spawn_dir_listing(tx.clone(), DirTarget::Other, listing_id, dir, false, cached);
+        }
+        spawn_free_space(tx.clone(), listing_id, self.current_dir.clone());
+        self.watch_current_dir(tx);
+    }
+
+    /// Shows a second directory in place of the preview, starting at the current one, or
+    /// hides it again.
+    fn toggle_dual_pane(&mut self, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        if self.other_pane.take().is_none() {
+            let selected = self.selected_entry().map(|entry| entry.path.clone());
+            self.other_pane = Some(Pane {
+                dir: self.current_dir.clone(),
+                entries: Vec::new(),
+                selected: selected.clone(),
+            });
+            self.preview_focused = false;
+            self.pending_selection = selected;
+            self.refresh_dirs(tx);
+        }
+    }
+
+    /// Makes the other pane the active one; the active pane keeps its place on the other
+    /// side.
+    fn switch_pane(&mut self, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        let Some(other) = self.other_pane.take() else {
+            return;
+        };
+        // Read before the entries are taken away.
+        let selected = self.selected_entry().map(|entry| entry.path.clone());
+        self.other_pane = Some(Pane {
+            dir: self.current_dir.clone(),
+            entries: std::mem::take(&mut self.current_entries),
+            selected,
+        });
+        self.enter_dir(other.dir, other.selected, tx);
+    }
+
+    /// A path typed into a prompt, expanded, with relative paths starting from the current
+    /// directory.
+    fn prompt_path(&self, typed: &str) -> PathBuf {
+        self.current_dir.join(core::expand_path(typed))
+    }
+
+    /// Where pastes go: the other pane's directory in dual-pane mode, else the current one.
+    fn paste_dir(&self) -> &Path {
+        self.other_pane
+            .as_ref()
+            .map_or(&self.current_dir, |pane| &pane.dir)
+    }
+
+    /// Moves the directory watcher to `current_dir`, creating it on first use.
+    fn watch_current_dir(&mut self, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        if !self.config.watch_dir || self.watched_dir.as_ref() == Some(&self.current_dir) {
+            return;
+        }
+        if self.dir_watcher.is_none() {
+            self.dir_watcher = spawn_dir_watcher(tx.clone());
+        }
+        let Some(watcher) = self.dir_watcher.as_mut() else {
+            return;
+        };
+        if let Some(old) = self.watched_dir.take() {
+            let _ = watcher.unwatch(&old);
+        }
+        if watcher
+            .watch(&self.current_dir, RecursiveMode::NonRecursive)
+            .is_ok()
+        {
+            self.watched_dir = Some(self.current_dir.clone());
+        }
+    }
+
+    /// Re-lists the current directory after an outside change, keeping the selected entry
+    /// or, if it was removed, the nearest one still there. The old listing and the preview
+    /// stay up until the new listing replaces them whole.
+    fn refresh_watched(&mut self, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        // A listing still coming in is dropped by the new listing id; start over instead.
+        if self.current_loading || self.parent_loading || self.other_loading {
+            self.refresh_dirs(tx);
+            return;
+        }
+        if let Some(selected) = self.selected_entry() {
+            let selected = selected.path.clone();
+            let after = self.filtered_indices[self.selected + 1..]
+                .iter()
+                .take(SELECTION_NEIGHBORS);
+            let before = self.filtered_indices[..self.selected]
+                .iter()
+                .rev()
+                .take(SELECTION_NEIGHBORS);
+            self.selection_fallback = after
+                .chain(before)
+                .map(|&index| self.current_entries[index].path.clone())
+                .collect();
+            self.pending_selection.get_or_insert(selected);
+        }
+        self.listing_id = self.listing_id.wrapping_add(1);
+        let listing_id = self.listing_id;
+        let current_dir = self.current_dir.clone();
+        if self.flatten {
+            spawn_flat_listing(
+                tx.clone(),
+                listing_id,
+                current_dir.clone(),
+                self.config.search.flatten_depth,
+                self.show_hidden,
+                true,
+            );
+        } else {
+            spawn_dir_listing(
+                tx.clone(),
+                DirTarget::Current,
+                listing_id,
+                current_dir.clone(),
+                self.show_list_size,
+                true,
             );
         }
+        spawn_free_space(tx.clone(), listing_id, current_dir);
+    }
+
+    /// `preferred` if it is still listed, else the first remembered neighbor that is.
+    fn surviving_selection(&mut self, preferred: Option<PathBuf>) -> Option<PathBuf> {
+        let fallback = std::mem::take(&mut self.selection_fallback);
+        let listed = |path: &PathBuf| self.current_entries.iter().any(|entry| &entry.path == path);
+        match &preferred {
+            Some(path) if !listed(path) => fallback.into_iter().find(listed).or(preferred),
+            _ => preferred,
+        }
+    }
+
+    /// Shows the cached listing of `dir` in `target` right away while it is re-scanned,
+    /// returning whether there was one.
+    fn restore_listing(&mut self, target: DirTarget, dir: &Path) -> bool {
+        let count_children = matches!(target, DirTarget::Current) && self.show_list_size;
+        let Some(cached) = self
+            .listing_cache
+            .get(dir, self.show_hidden, count_children)
+        else {
+            return false;
+        };
+        let mut entries = cached.entries.clone();
+        core::sort_entries(&mut entries, self.sort_options());
+        match target {
+            DirTarget::Parent => self.parent_entries = entries,
+            DirTarget::Current => self.current_entries = entries,
+            DirTarget::Other => {
+                if let Some(pane) = self.other_pane.as_mut() {
+                    pane.entries = entries;
+                }
+            }
+        }
+        true
+    }
+
+    /// Reads the content category of the listed files that have no extension, in the
+    /// background; the filter is applied again once they are known.
+    fn sniff_categories(&mut self, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        self.sniff_wanted = false;
+        if self.sniffing {
+            return;
+        }
+        let paths: Vec<PathBuf> = self
+            .current_entries
+            .iter()
+            .filter(|entry| entry.category.is_none())
+            .map(|entry| entry.path.clone())
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+        self.sniffing = true;
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let mut categories = Vec::with_capacity(paths.len());
+            for path in paths {
+                let category = FileCategory::sniff(&path).await;
+                categories.push((path, category));
+            }
+            let _ = tx.send(AppEvent::Categories(categories));
+        });
     }
 
     fn apply_filter(&mut self, preferred: Option<PathBuf>) -> bool {
         let had_entries = !self.filtered_indices.is_empty();
         let previous_selected = self.selected;
-        let raw_query = self.filter.trim();
+        let (inverted, filter) = split_inverted(&self.filter);
+        let (categories, name_query) = parse_type_filter(filter);
+        // Files still waiting to be sniffed match no type until they are.
+        let matches_type = |entry: &FileEntry| {
+            categories.is_empty()
+                || entry
+                    .category
+                    .is_some_and(|category| categories.contains(&Some(category)))
+        };
+        self.sniff_wanted = !categories.is_empty()
+            && self
+                .current_entries
+                .iter()
+                .any(|entry| entry.category.is_none());
+        let raw_query = name_query.as_str();
         let query_lower = raw_query.to_ascii_lowercase();
-        let regex = if raw_query.is_empty() {
+        let regex = if raw_query.is_empty() || self.filter_fuzzy {
             None
         } else {
             RegexBuilder::new(raw_query)
@@ -927,24 +2718,56 @@ impl App {
                 .build()
                 .ok()
         };
-        self.filtered_indices = if raw_query.is_empty() {
-            (0..self.current_entries.len()).collect()
-        } else {
+        let matched: Vec<usize> = if raw_query.is_empty() {
             self.current_entries
                 .iter()
                 .enumerate()
-                .filter(|(_, entry)| {
-                    if let Some(regex) = regex.as_ref() {
-                        regex.is_match(entry.name.as_str())
-                    } else {
-                        entry
-                            .name
-                            .to_ascii_lowercase()
-                            .contains(query_lower.as_str())
-                    }
-                })
+                .filter(|(_, entry)| matches_type(entry))
                 .map(|(index, _)| index)
                 .collect()
+        } else if let Some(regex) = regex.as_ref() {
+            self.current_entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| matches_type(entry) && regex.is_match(entry.name.as_str()))
+                .map(|(index, _)| index)
+                .collect()
+        } else {
+            let query: Vec<char> = query_lower
+                .chars()
+                .filter(|ch| !ch.is_whitespace())
+                .collect();
+            let mut scored: Vec<(i64, usize)> = self
+                .current_entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| matches_type(entry))
+                .filter_map(|(index, entry)| {
+                    fuzzy::score(&query, &entry.name).map(|score| (score, index))
+                })
+                .collect();
+            scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            scored.into_iter().map(|(_, index)| index).collect()
+        };
+        self.filter_highlight = if inverted || raw_query.is_empty() {
+            None
+        } else if let Some(regex) = regex {
+            Some(FilterHighlight::Regex(regex))
+        } else {
+            Some(FilterHighlight::Fuzzy(
+                query_lower
+                    .chars()
+                    .filter(|ch| !ch.is_whitespace())
+                    .collect(),
+            ))
+        };
+        self.filtered_indices = if inverted {
+            let matched: HashSet<usize> = matched.into_iter().collect();
+            (0..self.current_entries.len())
+                .filter(|index| !matched.contains(index))
+                .collect()
+        } else {
+            matched
         };
         let mut new_selected = 0usize;
         if let Some(preferred) = preferred {
@@ -968,12 +2791,84 @@ impl App {
         changed
     }
 
-    fn update_filter(&mut self, value: String) -> bool {
+    fn set_sort_mode(&mut self, mode: SortMode) {
+        if self.sort_mode == mode {
+            return;
+        }
+        self.sort_mode = mode;
+        self.resort_entries();
+    }
+
+    fn view_state(&self) -> ViewState {
+        ViewState {
+            show_hidden: self.show_hidden,
+            hidden_last: self.hidden_last,
+            show_metadata: self.show_metadata,
+            show_permissions: self.show_permissions,
+            show_dates: self.show_dates,
+            relative_dates: self.relative_dates,
+            show_owner: self.show_owner,
+            show_size: self.show_size,
+            show_list_permissions: self.show_list_permissions,
+            show_list_owner: self.show_list_owner,
+            show_list_size: self.show_list_size,
+            preview_wrap: self.preview_wrap,
+        }
+    }
+
+    fn set_view_state(&mut self, view: ViewState) {
+        self.show_hidden = view.show_hidden;
+        self.hidden_last = view.hidden_last;
+        self.show_metadata = view.show_metadata;
+        self.show_permissions = view.show_permissions;
+        self.show_dates = view.show_dates;
+        self.relative_dates = view.relative_dates;
+        self.show_owner = view.show_owner;
+        self.show_size = view.show_size;
+        self.show_list_permissions = view.show_list_permissions;
+        self.show_list_owner = view.show_list_owner;
+        self.show_list_size = view.show_list_size;
+        self.preview_wrap = view.preview_wrap;
+    }
+
+    fn sort_options(&self) -> SortOptions {
+        SortOptions {
+            mode: self.sort_mode,
+            reverse: self.sort_reverse,
+            natural: self.config.sort.natural,
+            dirs_first: self.config.sort.dirs_first,
+            hidden_last: self.show_hidden && self.hidden_last,
+        }
+    }
+
+    fn resort_entries(&mut self) {
         let selected_path = self.selected_entry().map(|entry| entry.path.clone());
+        let options = self.sort_options();
+        core::sort_entries(&mut self.parent_entries, options);
+        core::sort_entries(&mut self.current_entries, options);
+        self.apply_filter(selected_path);
+    }
+
+    fn update_filter(&mut self, value: String) -> bool {
+        // Fuzzy results are ranked, so the best match should take the cursor.
+        let selected_path = if self.filter_fuzzy {
+            None
+        } else {
+            self.selected_entry().map(|entry| entry.path.clone())
+        };
         self.filter = value;
         self.apply_filter(selected_path)
     }
 
+    fn set_filter_fuzzy(&mut self, fuzzy: bool) -> bool {
+        if self.filter_fuzzy == fuzzy {
+            return false;
+        }
+        let selected_path = self.selected_entry().map(|entry| entry.path.clone());
+        self.filter_fuzzy = fuzzy;
+        self.apply_filter(selected_path)
+    }
+
     fn clear_filter(&mut self) -> bool {
         let selected_path = self.selected_entry().map(|entry| entry.path.clone());
         self.filter.clear();
@@ -1005,10 +2900,129 @@ impl App {
 
     fn open_program_list(&mut self) {
         self.pending_prefix = None;
-        self.program_list = Some(ProgramListState::new(&self.programs));
+        let remembered = self
+            .selected_entry()
+            .and_then(|entry| self.last_programs.get(&entry.path))
+            .map(str::to_string);
+        self.program_list = Some(ProgramListState::new(&self.programs, remembered));
         self.mode = Mode::ProgramList;
     }
 
+    fn open_palette(&mut self) {
+        self.pending_prefix = None;
+        self.palette = Some(PaletteState::new(&self.config.keys));
+        self.mode = Mode::Palette;
+    }
+
+    fn start_content_search(&mut self, query: String, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        self.close_content_search();
+        let regex = RegexBuilder::new(&query)
+            .case_insensitive(true)
+            .build()
+            .or_else(|_| {
+                RegexBuilder::new(&regex::escape(&query))
+                    .case_insensitive(true)
+                    .build()
+            });
+        let Ok(regex) = regex else {
+            return;
+        };
+        let cancel = self.open_search_results(SearchKind::Contents, query);
+        spawn_content_search(
+            tx.clone(),
+            self.content_search_id,
+            self.current_dir.clone(),
+            regex,
+            self.show_hidden,
+            cancel,
+        );
+    }
+
+    /// Checks every file in the current directory for content that does not match its
+    /// extension and lists the flagged ones in the results popup.
+    fn start_mismatch_scan(&mut self, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        self.close_content_search();
+        let files: Vec<(String, PathBuf)> = self
+            .current_entries
+            .iter()
+            .filter(|entry| !entry.is_dir && !entry.broken_link)
+            .map(|entry| (entry.name.clone(), entry.path.clone()))
+            .collect();
+        let query = self.current_dir.to_string_lossy().to_string();
+        let cancel = self.open_search_results(SearchKind::Mismatches, query);
+        let equivalents = self.config.security.equivalent_extensions.clone();
+        spawn_mismatch_scan(
+            tx.clone(),
+            self.content_search_id,
+            files,
+            equivalents,
+            cancel,
+        );
+    }
+
+    fn open_search_results(&mut self, kind: SearchKind, query: String) -> Arc<AtomicBool> {
+        self.content_search_id = self.content_search_id.wrapping_add(1);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.content_search = Some(ContentSearchState {
+            id: self.content_search_id,
+            kind,
+            query,
+            matches: Vec::new(),
+            selected: 0,
+            done: false,
+            cancel: cancel.clone(),
+        });
+        self.mode = Mode::SearchResults;
+        cancel
+    }
+
+    fn close_content_search(&mut self) {
+        if let Some(search) = self.content_search.take() {
+            search.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Enters a directory marker, or selects a file marker in its parent directory.
+    fn jump_to_marker(&mut self, path: PathBuf, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        if !path.is_file() {
+            self.enter_dir(path, None, tx);
+            return;
+        }
+        self.reveal_path(&path, tx);
+        if self.config.markers.open_files {
+            spawn_open(path);
+        }
+    }
+
+    /// Goes to the directory a symlink's target really lives in, with the target selected.
+    /// Broken links go to where the target would be.
+    fn reveal_link_target(
+        &mut self,
+        entry: &FileEntry,
+        tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    ) {
+        let target = std::fs::canonicalize(&entry.path).ok().or_else(|| {
+            let link_target = entry.link_target.as_ref()?;
+            Some(entry.path.parent()?.join(link_target))
+        });
+        match target {
+            Some(target) => self.reveal_path(&target, tx),
+            None => {
+                self.status = Some(StatusMessage::error(format!(
+                    "Cannot resolve link {}",
+                    entry.name
+                )));
+            }
+        }
+    }
+
+    fn reveal_path(&mut self, path: &Path, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        let Some(parent) = path.parent() else {
+            return;
+        };
+        self.enter_dir(parent.to_path_buf(), Some(path.to_path_buf()), tx);
+    }
+
     fn resolve_program_path(&self, name: &str) -> PathBuf {
         self.programs
             .iter()
@@ -1017,75 +3031,120 @@ impl App {
             .unwrap_or_else(|| PathBuf::from(name))
     }
 
+    /// Copies the selected entry next to itself as `name copy`, `name copy 2`, … and
+    /// selects the copy once it exists.
+    fn duplicate_selected(&self, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let name = core::duplicate_name(&entry.name, entry.is_dir, |candidate| {
+            self.current_entries
+                .iter()
+                .any(|entry| entry.name == candidate)
+        });
+        let src = entry.path.clone();
+        let dest = src.with_file_name(name);
+        spawn_refresh(tx, Some(dest.clone()), async move {
+            let progress = core::Progress::default();
+            core::copy_recursively(&src, &dest, ConflictPolicy::Rename, &progress).await?;
+            Ok(Some(UndoEntry::Copy(dest)))
+        });
+    }
+
+    /// Opens the selected file in `$EDITOR`, then `$VISUAL`, then `vi`. Extra words in
+    /// the variable (e.g. `emacs -nw`) are passed as arguments.
+    fn open_in_editor(&self) -> Option<SuspendAction> {
+        let target = self.selected_entry().filter(|entry| !entry.is_dir)?;
+        let editor = ["EDITOR", "VISUAL"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| "vi".to_string());
+        let mut words = editor.split_whitespace().map(str::to_string);
+        let program = words.next()?;
+        Some(SuspendAction::OpenWith {
+            program: self.resolve_program_path(&program),
+            args: words.collect(),
+            path: target.path.clone(),
+            cwd: self.current_dir.clone(),
+        })
+    }
+
+    /// Runs the program of a quick slot; words after it (e.g. `mpv --loop`) are arguments.
     fn open_with_quick(&self, key: char) -> Option<SuspendAction> {
         let digit = key.to_digit(10)?;
-        let program = self.config.open_with.quick.get(&digit.to_string())?;
+        let command = self.config.open_with.quick.get(&digit.to_string())?;
         let target = self.selected_entry()?;
+        let mut words = core::split_args(command).into_iter();
+        let program = words.next()?;
         Some(SuspendAction::OpenWith {
-            program: self.resolve_program_path(program),
+            program: self.resolve_program_path(&program),
+            args: words.collect(),
             path: target.path.clone(),
             cwd: self.current_dir.clone(),
         })
     }
-}
 
-fn is_hidden_name(name: &str) -> bool {
-    name.starts_with('.')
-}
+    /// The configured quick slots as `digit: command`, sorted by digit.
+    fn quick_slots(&self) -> Vec<(&str, &str)> {
+        let mut slots: Vec<(&str, &str)> = self
+            .config
+            .open_with
+            .quick
+            .iter()
+            .map(|(digit, command)| (digit.as_str(), command.as_str()))
+            .collect();
+        slots.sort();
+        slots
+    }
 
-fn scan_programs() -> Vec<ProgramEntry> {
-    let mut entries = Vec::new();
-    let mut seen = HashSet::new();
-    let Some(path_var) = env::var_os("PATH") else {
-        return entries;
-    };
-    for dir in env::split_paths(&path_var) {
-        let Ok(read_dir) = std::fs::read_dir(dir) else {
-            continue;
-        };
-        for entry in read_dir.flatten() {
-            let path = entry.path();
-            if !is_executable(&path) {
-                continue;
-            }
-            let name = entry.file_name().to_string_lossy().to_string();
-            if seen.insert(name.clone()) {
-                entries.push(ProgramEntry { name, path });
-            }
+    /// Shown while the quick open-with prefix waits for a digit.
+    fn quick_slot_hint(&self) -> StatusMessage {
+        let slots = self.quick_slots();
+        if slots.is_empty() {
+            return StatusMessage::info(
+                "No quick open-with slots ([open_with] quick in the config)",
+            );
         }
+        let slots: Vec<String> = slots
+            .iter()
+            .map(|(digit, command)| format!("{digit} {command}"))
+            .collect();
+        StatusMessage::info(format!("Open with: {}", slots.join("  ")))
     }
-    entries.sort_by(|a, b| {
-        a.name
-            .to_ascii_lowercase()
-            .cmp(&b.name.to_ascii_lowercase())
-    });
-    entries
-}
 
-fn is_executable(path: &Path) -> bool {
-    let Ok(metadata) = std::fs::metadata(path) else {
-        return false;
-    };
-    if !metadata.is_file() {
-        return false;
-    }
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        return metadata.permissions().mode() & 0o111 != 0;
+    /// Warns about quick slots whose program is neither on `PATH` nor an existing file.
+    fn quick_slot_warning(&self) -> Option<StatusMessage> {
+        let missing: Vec<String> = self
+            .quick_slots()
+            .into_iter()
+            .filter(|(_, command)| {
+                !core::split_args(command)
+                    .first()
+                    .is_some_and(|program| self.resolve_program_path(program).is_file())
+            })
+            .map(|(digit, command)| format!("{digit} ({command})"))
+            .collect();
+        (!missing.is_empty()).then(|| {
+            StatusMessage::warning(format!(
+                "Quick open-with programs not found: {}",
+                missing.join(", ")
+            ))
+        })
     }
-    #[cfg(windows)]
-    {
-        let ext = path
-            .extension()
-            .and_then(|value| value.to_str())
-            .unwrap_or("")
-            .to_ascii_lowercase();
-        return matches!(ext.as_str(), "exe" | "cmd" | "bat" | "com");
-    }
-    #[cfg(not(any(unix, windows)))]
-    {
-        true
+}
+
+fn is_hidden_name(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+/// Why opening `path` might run it: a `#!` interpreter line or the executable bit.
+fn executable_detail(path: &Path) -> Option<String> {
+    match security::read_shebang(path) {
+        Some(interpreter) if !interpreter.is_empty() => Some(format!("Script for {interpreter}")),
+        Some(_) => Some("Script".to_string()),
+        None if is_executable(path) => Some("Executable file".to_string()),
+        None => None,
     }
 }
 
@@ -1097,12 +3156,20 @@ impl InputHandler {
         key: KeyEvent,
         tx: &tokio_mpsc::UnboundedSender<AppEvent>,
     ) -> InputEffect {
-        match &mut app.mode {
+        let view = app.view_state();
+        let effect = match &mut app.mode {
             Mode::Input(_) => Self::handle_input(app, key, tx),
             Mode::MarkerList => Self::handle_marker_list(app, key, tx),
             Mode::ProgramList => Self::handle_program_list(app, key, tx),
+            Mode::SearchResults => Self::handle_search_results(app, key, tx),
+            Mode::Palette => Self::handle_palette(app, key, tx),
             Mode::Normal => Self::handle_normal(app, key, tx),
+        };
+        // View toggles are remembered for the next session as soon as they change.
+        if app.view_state() != view {
+            tokio::spawn(app.view_state().save_task());
         }
+        effect
     }
 
     fn handle_normal(
@@ -1113,9 +3180,42 @@ impl InputHandler {
         if let Some(prefix) = app.pending_prefix.take() {
             return Self::handle_prefix(app, prefix, key, tx);
         }
+        if app.preview_focused {
+            if let Some(effect) = Self::handle_preview_key(app, key) {
+                return effect;
+            }
+        }
         Self::handle_normal_key(app, key, tx)
     }
 
+    /// Keys for the focused preview. Returns `None` for keys that fall through to
+    /// normal mode.
+    fn handle_preview_key(app: &mut App, key: KeyEvent) -> Option<InputEffect> {
+        let keys = &app.keymap.preview;
+        let page = i32::from(app.preview_scroll.viewport.saturating_sub(1).max(1));
+        let lines = if matches_any(key, &keys.up) {
+            -1
+        } else if matches_any(key, &keys.down) {
+            1
+        } else if matches_any(key, &keys.page_up) {
+            -page
+        } else if matches_any(key, &keys.page_down) {
+            page
+        } else if matches_any(key, &keys.close) {
+            app.preview_focused = false;
+            return Some(InputEffect {
+                redraw: true,
+                ..InputEffect::default()
+            });
+        } else {
+            return None;
+        };
+        Some(InputEffect {
+            redraw: app.scroll_preview(lines),
+            ..InputEffect::default()
+        })
+    }
+
     fn handle_prefix(
         app: &mut App,
         prefix: PendingPrefix,
@@ -1157,33 +3257,69 @@ impl InputHandler {
                     effect.redraw = true;
                     return effect;
                 }
+                if matches_any(key, &keys.toggle_relative_dates) {
+                    app.relative_dates = !app.relative_dates;
+                    app.show_dates = true;
+                    app.show_metadata = true;
+                    effect.redraw = true;
+                    return effect;
+                }
                 if matches_any(key, &keys.toggle_owner) {
                     app.show_owner = !app.show_owner;
                     app.show_metadata = true;
                     effect.redraw = true;
                     return effect;
                 }
+                if matches_any(key, &keys.toggle_size) {
+                    app.show_size = !app.show_size;
+                    app.show_metadata = true;
+                    effect.redraw = true;
+                    return effect;
+                }
                 if matches_any(key, &keys.toggle_metadata) {
                     app.show_metadata = !app.show_metadata;
                     effect.redraw = true;
                     return effect;
                 }
                 if matches_any(key, &keys.toggle_hidden) {
-                    app.show_hidden = !app.show_hidden;
-                    app.pending_selection = app.selected_entry().map(|entry| entry.path.clone());
-                    app.refresh_dirs(tx);
+                    // Cycles shown -> shown last -> off; only showing or hiding needs a relist.
+                    if app.show_hidden && !app.hidden_last {
+                        app.hidden_last = true;
+                        app.resort_entries();
+                    } else {
+                        app.show_hidden = !app.show_hidden;
+                        app.hidden_last = false;
+                        app.pending_selection =
+                            app.selected_entry().map(|entry| entry.path.clone());
+                        app.refresh_dirs(tx);
+                    }
+                    effect.redraw = true;
+                    return effect;
+                }
+                if matches_any(key, &keys.toggle_sticky_filter) {
+                    app.sticky_filter = !app.sticky_filter;
                     effect.redraw = true;
                     return effect;
                 }
                 return Self::handle_normal_key(app, key, tx);
             }
             PendingPrefix::Copy => {
-                if matches_any(key, &app.keymap.copy.copy_path) {
+                if let Some(derive) = copy_path_text(&app.keymap.copy, key) {
                     if let Some(entry) = app.selected_entry() {
-                        spawn_copy_path(entry.path.clone());
+                        spawn_copy_path(derive(entry));
                     }
                     return effect;
                 }
+                if matches_any(key, &app.keymap.copy.copy_contents) {
+                    if let Some(entry) = app.selected_entry() {
+                        spawn_copy_contents(tx, entry.path.clone());
+                    }
+                    return effect;
+                }
+                if matches_any(key, &app.keymap.copy.duplicate) {
+                    app.duplicate_selected(tx);
+                    return effect;
+                }
                 return Self::handle_normal_key(app, key, tx);
             }
             PendingPrefix::View => {
@@ -1198,12 +3334,41 @@ impl InputHandler {
                     effect.redraw = true;
                     return effect;
                 }
+                if matches_any(key, &keys.toggle_preview_wrap) {
+                    app.preview_wrap = !app.preview_wrap;
+                    effect.redraw = true;
+                    return effect;
+                }
+                if matches_any(key, &keys.toggle_dual_pane) {
+                    app.toggle_dual_pane(tx);
+                    effect.redraw = true;
+                    return effect;
+                }
+                if matches_any(key, &keys.toggle_flatten) {
+                    app.flatten = !app.flatten;
+                    app.pending_selection = app.selected_entry().map(|entry| entry.path.clone());
+                    app.refresh_dirs(tx);
+                    effect.redraw = true;
+                    return effect;
+                }
+                if matches_any(key, &keys.toggle_list_size) {
+                    app.show_list_size = !app.show_list_size;
+                    if app.show_list_size {
+                        // Directory child counts are only gathered while the column is visible.
+                        app.pending_selection =
+                            app.selected_entry().map(|entry| entry.path.clone());
+                        app.refresh_dirs(tx);
+                    }
+                    effect.redraw = true;
+                    return effect;
+                }
                 return Self::handle_normal_key(app, key, tx);
             }
             PendingPrefix::Delete => {
                 if matches_any(key, &app.keymap.delete.confirm) {
-                    if app.selected_entry().is_some() {
-                        Self::start_input(app, InputAction::ConfirmDelete);
+                    let paths = app.target_paths();
+                    if !paths.is_empty() && !app.operation_running() {
+                        Self::confirm_or_run(app, Destructive::Delete(paths), tx);
                         effect.redraw = true;
                     }
                     return effect;
@@ -1219,6 +3384,58 @@ impl InputHandler {
                 }
                 return Self::handle_normal_key(app, key, tx);
             }
+            PendingPrefix::QuickMarkerSet => {
+                if let KeyCode::Char(digit) = key.code {
+                    if digit.is_ascii_digit() {
+                        app.markers.set_quick(digit, app.current_dir.clone());
+                        tokio::spawn(app.markers.save_task());
+                        app.status = Some(StatusMessage::info(format!("Quick marker {digit} set")));
+                        effect.redraw = true;
+                    }
+                }
+                effect
+            }
+            PendingPrefix::QuickMarkerJump => {
+                if let KeyCode::Char(digit) = key.code {
+                    if digit.is_ascii_digit() {
+                        match app.markers.quick(digit).cloned() {
+                            Some(path) => app.jump_to_marker(path, tx),
+                            None => {
+                                app.status = Some(StatusMessage::warning(format!(
+                                    "Quick marker {digit} is not set"
+                                )))
+                            }
+                        }
+                        effect.redraw = true;
+                    }
+                }
+                effect
+            }
+            PendingPrefix::Sort => {
+                let keys = &app.keymap.sort;
+                if matches_any(key, &keys.by_name) {
+                    app.set_sort_mode(SortMode::Name);
+                    effect.redraw = true;
+                    return effect;
+                }
+                if matches_any(key, &keys.by_size) {
+                    app.set_sort_mode(SortMode::Size);
+                    effect.redraw = true;
+                    return effect;
+                }
+                if matches_any(key, &keys.by_modified) {
+                    app.set_sort_mode(SortMode::Modified);
+                    effect.redraw = true;
+                    return effect;
+                }
+                if matches_any(key, &keys.reverse) {
+                    app.sort_reverse = !app.sort_reverse;
+                    app.resort_entries();
+                    effect.redraw = true;
+                    return effect;
+                }
+                Self::handle_normal_key(app, key, tx)
+            }
         }
     }
 
@@ -1233,7 +3450,28 @@ impl InputHandler {
             app.open_program_list();
             effect.redraw = true;
         } else if matches_any(key, &keys.quit) {
-            effect.exit = true;
+            if app.pending_actions == 0 {
+                effect.exit = true;
+            } else {
+                app.mode = Mode::Input(InputState::new(InputAction::ConfirmQuit, String::new()));
+                effect.redraw = true;
+            }
+        } else if matches_any(key, &keys.cancel) {
+            // Cancelling is cooperative: the operation stops at its next check and
+            // reports back, so a partial copy still gets cleaned up.
+            if let Some(operation) = &app.operation {
+                operation.progress.cancel();
+                effect.redraw = true;
+            } else if app
+                .dir_size
+                .as_ref()
+                .is_some_and(|dir_size| dir_size.size.is_none())
+            {
+                if let Some(dir_size) = app.dir_size.take() {
+                    dir_size.cancel.store(true, Ordering::Relaxed);
+                }
+                effect.redraw = true;
+            }
         } else if matches_any(key, &keys.up) {
             if app.select_up() {
                 effect.redraw = true;
@@ -1244,6 +3482,21 @@ impl InputHandler {
                 effect.redraw = true;
                 effect.request_preview = true;
             }
+        } else if let Some(rows) = list_page_rows(app, key) {
+            if app.select_by(rows) {
+                effect.redraw = true;
+                effect.request_preview = true;
+            }
+        } else if matches_any(key, &keys.top) {
+            if app.select_index(0) {
+                effect.redraw = true;
+                effect.request_preview = true;
+            }
+        } else if matches_any(key, &keys.bottom) {
+            if app.select_index(app.filtered_indices.len().saturating_sub(1)) {
+                effect.redraw = true;
+                effect.request_preview = true;
+            }
         } else if matches_any(key, &keys.parent) {
             if app.navigate_parent(tx) {
                 effect.redraw = true;
@@ -1252,9 +3505,51 @@ impl InputHandler {
             if app.activate_selected(tx) {
                 effect.redraw = true;
             }
-        } else if matches_any(key, &keys.search) {
-            Self::start_input(app, InputAction::Search);
+        } else if matches_any(key, &keys.search) {
+            if app.set_filter_fuzzy(false) {
+                app.clear_preview();
+                effect.request_preview = true;
+            }
+            Self::start_input(app, InputAction::Search);
+            effect.redraw = true;
+        } else if matches_any(key, &keys.fuzzy_search) {
+            if app.set_filter_fuzzy(true) {
+                app.clear_preview();
+                effect.request_preview = true;
+            }
+            Self::start_input(app, InputAction::FuzzySearch);
+            effect.redraw = true;
+        } else if matches_any(key, &keys.content_search) {
+            Self::start_input(app, InputAction::ContentSearch);
+            effect.redraw = true;
+        } else if matches_any(key, &keys.mismatch_scan) {
+            app.start_mismatch_scan(tx);
+            effect.redraw = true;
+        } else if matches_any(key, &keys.reveal_link) {
+            match app
+                .selected_entry()
+                .filter(|entry| entry.is_symlink)
+                .cloned()
+            {
+                Some(entry) => app.reveal_link_target(&entry, tx),
+                None => app.status = Some(StatusMessage::warning("Not a symbolic link")),
+            }
+            effect.redraw = true;
+        } else if matches_any(key, &keys.dir_size) {
+            match app.selected_entry().filter(|entry| entry.is_dir) {
+                Some(entry) => {
+                    let path = entry.path.clone();
+                    app.compute_dir_size(path, tx);
+                }
+                None => app.status = Some(StatusMessage::warning("Not a directory")),
+            }
             effect.redraw = true;
+        } else if matches_any(key, &keys.command_palette) {
+            app.open_palette();
+            effect.redraw = true;
+        } else if matches_any(key, &keys.file_manager) {
+            let selected = app.selected_entry().map(|entry| entry.path.clone());
+            spawn_file_manager(tx, selected, app.current_dir.clone());
         } else if matches_any(key, &keys.add) {
             app.pending_prefix = Some(PendingPrefix::Add);
         } else if matches_any(key, &keys.rename) {
@@ -1262,6 +3557,15 @@ impl InputHandler {
                 Self::start_input(app, InputAction::Rename);
                 effect.redraw = true;
             }
+        } else if matches_any(key, &keys.chmod) {
+            if cfg!(unix) {
+                if app.selected_entry().is_some() {
+                    Self::start_input(app, InputAction::Chmod);
+                }
+            } else {
+                app.status = Some(StatusMessage::warning("Chmod is only supported on Unix"));
+            }
+            effect.redraw = true;
         } else if matches_any(key, &keys.delete) {
             app.pending_prefix = Some(PendingPrefix::Delete);
         } else if matches_any(key, &keys.marker_set) {
@@ -1273,10 +3577,19 @@ impl InputHandler {
         } else if matches_any(key, &keys.marker_jump) {
             Self::start_input(app, InputAction::MarkerJump);
             effect.redraw = true;
+        } else if matches_any(key, &keys.go_to_path) {
+            Self::start_input(app, InputAction::GoToPath);
+            effect.redraw = true;
+        } else if matches_any(key, &keys.quick_marker_set) {
+            app.pending_prefix = Some(PendingPrefix::QuickMarkerSet);
+        } else if matches_any(key, &keys.quick_marker_jump) {
+            app.pending_prefix = Some(PendingPrefix::QuickMarkerJump);
         } else if matches_any(key, &keys.settings) {
             app.pending_prefix = Some(PendingPrefix::Settings);
         } else if matches_any(key, &keys.view) {
             app.pending_prefix = Some(PendingPrefix::View);
+        } else if matches_any(key, &keys.sort) {
+            app.pending_prefix = Some(PendingPrefix::Sort);
         } else if matches_any(key, &keys.copy) {
             Self::copy_selection(app, ClipboardOp::Copy);
             app.pending_prefix = Some(PendingPrefix::Copy);
@@ -1284,14 +3597,101 @@ impl InputHandler {
             Self::copy_selection(app, ClipboardOp::Cut);
         } else if matches_any(key, &keys.paste) {
             Self::paste_selection(app, tx);
+        } else if matches_any(key, &keys.paste_system) {
+            spawn_read_clipboard(tx, AppEvent::SystemClipboard);
         } else if matches_any(key, &keys.open_with_quick) {
             app.pending_prefix = Some(PendingPrefix::OpenWith);
+            app.status = Some(app.quick_slot_hint());
+            effect.redraw = true;
         } else if matches_any(key, &keys.open_shell) {
             effect.suspend = Some(SuspendAction::Shell(app.current_dir.clone()));
+        } else if matches_any(key, &keys.open_terminal) {
+            let command = app.config.terminal_cmd.trim();
+            if command.is_empty() {
+                effect.suspend = Some(SuspendAction::Shell(app.current_dir.clone()));
+            } else if let Err(err) = spawn_terminal(command, &app.current_dir) {
+                app.status = Some(StatusMessage::error(format!(
+                    "Failed to open terminal: {err}"
+                )));
+                effect.redraw = true;
+            }
+        } else if matches_any(key, &keys.focus_preview) {
+            // The preview is hidden in dual-pane mode, so the key switches panes instead.
+            if app.other_pane.is_some() {
+                app.switch_pane(tx);
+            } else {
+                app.preview_focused = true;
+            }
+            effect.redraw = true;
+        } else if matches_any(key, &keys.edit) {
+            effect.suspend = app.open_in_editor();
+        } else if matches_any(key, &keys.bulk_rename) {
+            if !app.target_paths().is_empty() {
+                Self::start_input(app, InputAction::BulkRename);
+                effect.redraw = true;
+            }
+        } else if matches_any(key, &keys.undo) {
+            match app.undo_stack.pop() {
+                Some(entry) => spawn_undo(tx, entry, app.config.delete.use_trash),
+                None => app.status = Some(StatusMessage::info("Nothing to undo")),
+            }
+            effect.redraw = true;
+        } else if matches_any(key, &keys.clear_filter) {
+            app.sticky_filter = false;
+            if app.clear_filter() {
+                app.clear_preview();
+                effect.request_preview = true;
+            }
+            effect.redraw = true;
+        } else if matches_any(key, &keys.toggle_mark) {
+            if app.toggle_mark() {
+                effect.request_preview = true;
+            }
+            effect.redraw = true;
+        }
+        effect
+    }
+
+    /// Inserts clipboard `text` at the caret of the open text prompt. Line breaks become
+    /// spaces since prompts hold a single line.
+    fn paste_into_input(app: &mut App, text: &str) -> InputEffect {
+        let mut effect = InputEffect::default();
+        let mode = std::mem::replace(&mut app.mode, Mode::Normal);
+        let mut input = match mode {
+            Mode::Input(input) if input.takes_text() => input,
+            other => {
+                app.mode = other;
+                return effect;
+            }
+        };
+        for ch in text.trim_end_matches(['\r', '\n']).chars() {
+            match ch {
+                '\n' => input.insert(' '),
+                ch if ch.is_control() => {}
+                ch => input.insert(ch),
+            }
         }
+        Self::refilter(app, &input, &mut effect);
+        app.mode = Mode::Input(input);
+        effect.redraw = true;
         effect
     }
 
+    /// Re-applies the live filter of a search prompt after its text changed.
+    fn refilter(app: &mut App, input: &InputState, effect: &mut InputEffect) {
+        match input.action {
+            InputAction::Search | InputAction::FuzzySearch => {
+                let selection_changed = app.update_filter(input.buffer.clone());
+                if selection_changed {
+                    app.clear_preview();
+                    effect.request_preview = true;
+                }
+            }
+            InputAction::MarkerSearch => app.update_marker_filter(input.buffer.clone()),
+            _ => {}
+        }
+    }
+
     fn handle_input(
         app: &mut App,
         key: KeyEvent,
@@ -1307,9 +3707,47 @@ impl InputHandler {
             }
         };
 
+        if key.code != KeyCode::Tab {
+            input.completion = None;
+        }
+        if key.code == KeyCode::Tab && input.completes_paths() {
+            input.complete(&app.current_dir);
+            app.mode = Mode::Input(input);
+            effect.redraw = true;
+            return effect;
+        }
+        if input.takes_text() {
+            let edited = if key.modifiers.contains(KeyModifiers::CONTROL) {
+                match key.code {
+                    KeyCode::Char('v') => {
+                        spawn_read_clipboard(tx, AppEvent::InputClipboard);
+                        true
+                    }
+                    KeyCode::Char('u') => {
+                        input.clear();
+                        Self::refilter(app, &input, &mut effect);
+                        true
+                    }
+                    KeyCode::Char('w') => {
+                        input.erase_word();
+                        Self::refilter(app, &input, &mut effect);
+                        true
+                    }
+                    _ => false,
+                }
+            } else {
+                input.move_cursor(key.code)
+            };
+            if edited {
+                app.mode = Mode::Input(input);
+                effect.redraw = true;
+                return effect;
+            }
+        }
+
         let mut keep_input = true;
         match input.action.clone() {
-            InputAction::Search => match key.code {
+            InputAction::Search | InputAction::FuzzySearch => match key.code {
                 KeyCode::Esc => {
                     let selection_changed = app.clear_filter();
                     keep_input = false;
@@ -1320,11 +3758,26 @@ impl InputHandler {
                     }
                 }
                 KeyCode::Enter => {
+                    app.search_history.push(&input.buffer);
+                    if app.config.search.save_history {
+                        tokio::spawn(app.search_history.save_task());
+                    }
                     keep_input = false;
                     effect.redraw = true;
                 }
-                KeyCode::Backspace => {
-                    input.buffer.pop();
+                KeyCode::Up | KeyCode::Down => {
+                    let recalled = input.recall(&app.search_history, key.code == KeyCode::Up);
+                    if recalled {
+                        let selection_changed = app.update_filter(input.buffer.clone());
+                        effect.redraw = true;
+                        if selection_changed {
+                            app.clear_preview();
+                            effect.request_preview = true;
+                        }
+                    }
+                }
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
                     let selection_changed = app.update_filter(input.buffer.clone());
                     effect.redraw = true;
                     if selection_changed {
@@ -1333,7 +3786,7 @@ impl InputHandler {
                     }
                 }
                 KeyCode::Char(ch) if !ch.is_control() => {
-                    input.buffer.push(ch);
+                    input.insert(ch);
                     let selection_changed = app.update_filter(input.buffer.clone());
                     effect.redraw = true;
                     if selection_changed {
@@ -1343,6 +3796,29 @@ impl InputHandler {
                 }
                 _ => {}
             },
+            InputAction::ContentSearch => match key.code {
+                KeyCode::Esc => {
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Enter => {
+                    let query = input.buffer.trim();
+                    if !query.is_empty() {
+                        app.start_content_search(query.to_string(), tx);
+                    }
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
+                    effect.redraw = true;
+                }
+                KeyCode::Char(ch) if !ch.is_control() => {
+                    input.insert(ch);
+                    effect.redraw = true;
+                }
+                _ => {}
+            },
             InputAction::MarkerSearch => match key.code {
                 KeyCode::Esc => {
                     app.clear_marker_filter();
@@ -1353,13 +3829,13 @@ impl InputHandler {
                     keep_input = false;
                     effect.redraw = true;
                 }
-                KeyCode::Backspace => {
-                    input.buffer.pop();
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
                     app.update_marker_filter(input.buffer.clone());
                     effect.redraw = true;
                 }
                 KeyCode::Char(ch) if !ch.is_control() => {
-                    input.buffer.push(ch);
+                    input.insert(ch);
                     app.update_marker_filter(input.buffer.clone());
                     effect.redraw = true;
                 }
@@ -1374,29 +3850,33 @@ impl InputHandler {
                     if !input.buffer.trim().is_empty() {
                         let name = input.buffer.trim().to_string();
                         let path = app.current_dir.join(&name);
-                        let select = Some(path.clone());
+                        // The cursor goes to the entry in this directory that leads to the
+                        // new path.
+                        let select = path
+                            .strip_prefix(&app.current_dir)
+                            .ok()
+                            .and_then(|relative| relative.components().next())
+                            .map(|first| app.current_dir.join(first))
+                            .or_else(|| Some(path.clone()));
                         let is_dir = matches!(input.action, InputAction::AddDir);
-                        if is_dir {
-                            let path = path.clone();
-                            spawn_refresh(tx, select, async move { core::create_dir(&path).await });
-                        } else {
-                            let path = path.clone();
-                            spawn_refresh(
-                                tx,
-                                select,
-                                async move { core::create_file(&path).await },
-                            );
-                        }
+                        spawn_refresh(tx, select, async move {
+                            let parents = if is_dir {
+                                core::create_dir(&path).await?
+                            } else {
+                                core::create_file(&path).await?
+                            };
+                            Ok(Some(UndoEntry::Create { path, parents }))
+                        });
                     }
                     keep_input = false;
                     effect.redraw = true;
                 }
-                KeyCode::Backspace => {
-                    input.buffer.pop();
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
                     effect.redraw = true;
                 }
                 KeyCode::Char(ch) if !ch.is_control() => {
-                    input.buffer.push(ch);
+                    input.insert(ch);
                     effect.redraw = true;
                 }
                 _ => {}
@@ -1407,27 +3887,85 @@ impl InputHandler {
                     effect.redraw = true;
                 }
                 KeyCode::Enter => {
-                    let new_name = input.buffer.trim();
-                    if !new_name.is_empty() {
-                        if let Some(entry) = app.selected_entry() {
-                            let src = entry.path.clone();
-                            let dest = src.with_file_name(new_name);
-                            if src != dest {
-                                spawn_refresh(tx, Some(dest.clone()), async move {
-                                    core::rename_path(&src, &dest).await
-                                });
+                    let new_name = input.buffer.trim().to_string();
+                    keep_input = false;
+                    if let Some(entry) = app.selected_entry().filter(|_| !new_name.is_empty()) {
+                        let src = entry.path.clone();
+                        let dest = src.with_file_name(&new_name);
+                        let dropped = if entry.is_dir {
+                            None
+                        } else {
+                            dropped_extension(&src, &dest)
+                        };
+                        match dropped {
+                            Some(extension) => {
+                                input = InputState::new(
+                                    InputAction::ConfirmRename {
+                                        src,
+                                        dest,
+                                        extension,
+                                    },
+                                    new_name,
+                                );
+                                keep_input = true;
                             }
+                            None => spawn_rename(tx, src, dest),
+                        }
+                    }
+                    effect.redraw = true;
+                }
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
+                    effect.redraw = true;
+                }
+                KeyCode::Char(ch) if !ch.is_control() => {
+                    input.insert(ch);
+                    effect.redraw = true;
+                }
+                _ => {}
+            },
+            InputAction::ConfirmRename { src, dest, .. } => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    spawn_rename(tx, src, dest);
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                // Back to the name as typed, to add the extension again.
+                KeyCode::Char('n') | KeyCode::Char('N') => {
+                    input = InputState::new(InputAction::Rename, input.buffer.clone());
+                    effect.redraw = true;
+                }
+                KeyCode::Esc => {
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                _ => {}
+            },
+            InputAction::Chmod => match key.code {
+                KeyCode::Esc => {
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Enter => {
+                    let mode = input.buffer.trim().to_string();
+                    if !mode.is_empty() {
+                        if let Some(entry) = app.selected_entry() {
+                            let path = entry.path.clone();
+                            spawn_refresh(tx, Some(path.clone()), async move {
+                                core::set_mode(&path, &mode).await?;
+                                Ok(None)
+                            });
                         }
                     }
                     keep_input = false;
                     effect.redraw = true;
                 }
-                KeyCode::Backspace => {
-                    input.buffer.pop();
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
                     effect.redraw = true;
                 }
                 KeyCode::Char(ch) if !ch.is_control() => {
-                    input.buffer.push(ch);
+                    input.insert(ch);
                     effect.redraw = true;
                 }
                 _ => {}
@@ -1449,12 +3987,12 @@ impl InputHandler {
                     keep_input = false;
                     effect.redraw = true;
                 }
-                KeyCode::Backspace => {
-                    input.buffer.pop();
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
                     effect.redraw = true;
                 }
                 KeyCode::Char(ch) if !ch.is_control() => {
-                    input.buffer.push(ch);
+                    input.insert(ch);
                     effect.redraw = true;
                 }
                 _ => {}
@@ -1467,22 +4005,18 @@ impl InputHandler {
                 KeyCode::Enter => {
                     let name = input.buffer.trim();
                     if let Some(path) = app.markers.get(name).cloned() {
-                        app.current_dir = path;
-                        app.pending_selection = None;
-                        app.selected = 0;
-                        app.clear_preview();
-                        app.refresh_dirs(tx);
+                        app.jump_to_marker(path, tx);
                         effect.redraw = true;
                     }
                     keep_input = false;
                     effect.redraw = true;
                 }
-                KeyCode::Backspace => {
-                    input.buffer.pop();
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
                     effect.redraw = true;
                 }
                 KeyCode::Char(ch) if !ch.is_control() => {
-                    input.buffer.push(ch);
+                    input.insert(ch);
                     effect.redraw = true;
                 }
                 _ => {}
@@ -1505,12 +4039,12 @@ impl InputHandler {
                     keep_input = false;
                     effect.redraw = true;
                 }
-                KeyCode::Backspace => {
-                    input.buffer.pop();
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
                     effect.redraw = true;
                 }
                 KeyCode::Char(ch) if !ch.is_control() => {
-                    input.buffer.push(ch);
+                    input.insert(ch);
                     effect.redraw = true;
                 }
                 _ => {}
@@ -1523,7 +4057,7 @@ impl InputHandler {
                 KeyCode::Enter => {
                     let path = input.buffer.trim();
                     if !path.is_empty() {
-                        app.markers.set(name.clone(), PathBuf::from(path));
+                        app.markers.set(name.clone(), app.prompt_path(path));
                         let save_task = app.markers.save_task();
                         tokio::spawn(save_task);
                         app.sync_marker_list(Some(&name));
@@ -1531,12 +4065,12 @@ impl InputHandler {
                     keep_input = false;
                     effect.redraw = true;
                 }
-                KeyCode::Backspace => {
-                    input.buffer.pop();
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
                     effect.redraw = true;
                 }
                 KeyCode::Char(ch) if !ch.is_control() => {
-                    input.buffer.push(ch);
+                    input.insert(ch);
                     effect.redraw = true;
                 }
                 _ => {}
@@ -1546,64 +4080,275 @@ impl InputHandler {
                     keep_input = false;
                     effect.redraw = true;
                 }
-                KeyCode::Enter => {
-                    let name = input.buffer.trim();
-                    if !name.is_empty() {
-                        let buffer = app.current_dir.to_string_lossy().to_string();
-                        input = InputState::new(
-                            InputAction::MarkerCreatePath {
-                                name: name.to_string(),
-                            },
-                            buffer,
-                        );
-                        effect.redraw = true;
-                    } else {
-                        keep_input = false;
-                        effect.redraw = true;
-                    }
-                }
-                KeyCode::Backspace => {
-                    input.buffer.pop();
+                KeyCode::Enter => {
+                    let name = input.buffer.trim();
+                    if !name.is_empty() {
+                        let buffer = app.current_dir.to_string_lossy().to_string();
+                        input = InputState::new(
+                            InputAction::MarkerCreatePath {
+                                name: name.to_string(),
+                            },
+                            buffer,
+                        );
+                        effect.redraw = true;
+                    } else {
+                        keep_input = false;
+                        effect.redraw = true;
+                    }
+                }
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
+                    effect.redraw = true;
+                }
+                KeyCode::Char(ch) if !ch.is_control() => {
+                    input.insert(ch);
+                    effect.redraw = true;
+                }
+                _ => {}
+            },
+            InputAction::MarkerCreatePath { name } => match key.code {
+                KeyCode::Esc => {
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Enter => {
+                    let path = input.buffer.trim();
+                    if !path.is_empty() {
+                        app.markers.set(name.clone(), app.prompt_path(path));
+                        let save_task = app.markers.save_task();
+                        tokio::spawn(save_task);
+                        app.sync_marker_list(Some(&name));
+                    }
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
+                    effect.redraw = true;
+                }
+                KeyCode::Char(ch) if !ch.is_control() => {
+                    input.insert(ch);
+                    effect.redraw = true;
+                }
+                _ => {}
+            },
+            InputAction::MarkerExport => match key.code {
+                KeyCode::Esc => {
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Enter => {
+                    let typed = input.buffer.trim();
+                    let path = app.prompt_path(typed);
+                    if typed.is_empty() {
+                        keep_input = false;
+                    } else if path.exists() {
+                        input =
+                            InputState::new(InputAction::ConfirmMarkerExport(path), String::new());
+                    } else {
+                        spawn_marker_export(tx, app.markers.export_task(path));
+                        keep_input = false;
+                    }
+                    effect.redraw = true;
+                }
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
+                    effect.redraw = true;
+                }
+                KeyCode::Char(ch) if !ch.is_control() => {
+                    input.insert(ch);
+                    effect.redraw = true;
+                }
+                _ => {}
+            },
+            InputAction::GoToPath => match key.code {
+                KeyCode::Esc => {
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Enter => {
+                    let typed = input.buffer.trim();
+                    if typed.is_empty() {
+                        keep_input = false;
+                    } else {
+                        // A file is revealed.
+                        let path = app.prompt_path(typed);
+                        match std::fs::canonicalize(&path) {
+                            Ok(path) if path.is_dir() => {
+                                app.enter_dir(path, None, tx);
+                                keep_input = false;
+                            }
+                            Ok(path) => {
+                                app.reveal_path(&path, tx);
+                                keep_input = false;
+                            }
+                            Err(err) => {
+                                app.status = Some(StatusMessage::error(format!(
+                                    "{}: {err}",
+                                    path.display()
+                                )));
+                            }
+                        }
+                    }
+                    effect.redraw = true;
+                }
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
+                    effect.redraw = true;
+                }
+                KeyCode::Char(ch) if !ch.is_control() => {
+                    input.insert(ch);
+                    effect.redraw = true;
+                }
+                _ => {}
+            },
+            InputAction::MarkerImport => match key.code {
+                KeyCode::Esc => {
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Enter => {
+                    let path = input.buffer.trim();
+                    if !path.is_empty() {
+                        spawn_marker_import(tx, app.prompt_path(path));
+                    }
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
+                    effect.redraw = true;
+                }
+                KeyCode::Char(ch) if !ch.is_control() => {
+                    input.insert(ch);
+                    effect.redraw = true;
+                }
+                _ => {}
+            },
+            InputAction::ConfirmMarkerExport(path) => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    spawn_marker_export(tx, app.markers.export_task(path));
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                _ => {}
+            },
+            InputAction::ConfirmOpen { path, .. } => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    spawn_open(path);
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                _ => {}
+            },
+            InputAction::ConfirmQuit => match key.code {
+                KeyCode::Char('q')
+                | KeyCode::Char('Q')
+                | KeyCode::Char('y')
+                | KeyCode::Char('Y') => {
+                    keep_input = false;
+                    effect.exit = true;
+                }
+                KeyCode::Char('w') | KeyCode::Char('W') => {
+                    app.quit_when_idle = true;
+                    app.status = Some(StatusMessage::info(
+                        "Quitting when the running operations finish",
+                    ));
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+                _ => {}
+            },
+            InputAction::Confirm(Destructive::Overwrite(request)) => {
+                let policy = match key.code {
+                    KeyCode::Char('o') | KeyCode::Char('O') => Some(ConflictPolicy::Overwrite),
+                    KeyCode::Char('s') | KeyCode::Char('S') => Some(ConflictPolicy::Skip),
+                    KeyCode::Char('r') | KeyCode::Char('R') => Some(ConflictPolicy::Rename),
+                    _ => None,
+                };
+                if let Some(policy) = policy {
+                    keep_input = false;
+                    Self::finish_paste(app, request, policy, tx);
+                    effect.redraw = true;
+                } else if matches!(key.code, KeyCode::Esc) {
+                    keep_input = false;
+                    effect.redraw = true;
+                }
+            }
+            InputAction::Confirm(action) => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    keep_input = false;
+                    Self::run_destructive(app, action, tx);
                     effect.redraw = true;
                 }
-                KeyCode::Char(ch) if !ch.is_control() => {
-                    input.buffer.push(ch);
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    keep_input = false;
                     effect.redraw = true;
                 }
                 _ => {}
             },
-            InputAction::MarkerCreatePath { name } => match key.code {
+            InputAction::BulkRename => match key.code {
                 KeyCode::Esc => {
                     keep_input = false;
                     effect.redraw = true;
                 }
                 KeyCode::Enter => {
-                    let path = input.buffer.trim();
-                    if !path.is_empty() {
-                        app.markers.set(name.clone(), PathBuf::from(path));
-                        let save_task = app.markers.save_task();
-                        tokio::spawn(save_task);
-                        app.sync_marker_list(Some(&name));
+                    match app.bulk_rename_plan(&input.buffer) {
+                        Ok(plan) if plan.is_empty() => {
+                            app.status = Some(StatusMessage::info("No names change"));
+                            keep_input = false;
+                        }
+                        Ok(plan) => {
+                            input = InputState::new(
+                                InputAction::ConfirmBulkRename(plan),
+                                String::new(),
+                            );
+                        }
+                        Err(err) => {
+                            app.status =
+                                Some(StatusMessage::error(format!("Bulk rename aborted: {err}")));
+                            keep_input = false;
+                        }
                     }
-                    keep_input = false;
                     effect.redraw = true;
                 }
-                KeyCode::Backspace => {
-                    input.buffer.pop();
+                KeyCode::Backspace | KeyCode::Delete => {
+                    input.erase(key.code);
                     effect.redraw = true;
                 }
                 KeyCode::Char(ch) if !ch.is_control() => {
-                    input.buffer.push(ch);
+                    input.insert(ch);
                     effect.redraw = true;
                 }
                 _ => {}
             },
-            InputAction::ConfirmDelete => match key.code {
+            InputAction::ConfirmBulkRename(plan) => match key.code {
                 KeyCode::Char('y') | KeyCode::Char('Y') => {
-                    if let Some(entry) = app.selected_entry() {
-                        let path = entry.path.clone();
-                        spawn_refresh(tx, None, async move { core::remove_path(&path).await });
-                    }
+                    let select = plan.first().map(|(_, to)| to.clone());
+                    let progress = Arc::new(core::Progress::default());
+                    spawn_operation(tx, select, progress, async move {
+                        let mut undo = Vec::new();
+                        for (from, to) in plan {
+                            if let Err(err) = core::rename_path(&from, &to).await {
+                                return (undo, Err(err));
+                            }
+                            undo.push(UndoEntry::Rename { from, to });
+                        }
+                        (undo, Ok(()))
+                    });
+                    app.marked.clear();
                     keep_input = false;
                     effect.redraw = true;
                 }
@@ -1617,10 +4362,14 @@ impl InputHandler {
 
         if keep_input {
             app.mode = Mode::Input(input);
+        } else if matches!(app.mode, Mode::Input(_)) {
+            // The action asked a follow-up question, e.g. to confirm a move it led to.
         } else if app.marker_list.is_some() {
             app.mode = Mode::MarkerList;
         } else if app.program_list.is_some() {
             app.mode = Mode::ProgramList;
+        } else if app.content_search.is_some() {
+            app.mode = Mode::SearchResults;
         } else {
             app.mode = Mode::Normal;
         }
@@ -1691,16 +4440,18 @@ impl InputHandler {
             } else if matches_any(key, &keys.search) {
                 action = Some(MarkerListAction::StartInput(InputAction::MarkerSearch));
                 effect.redraw = true;
+            } else if matches_any(key, &keys.export) {
+                action = Some(MarkerListAction::StartInput(InputAction::MarkerExport));
+                effect.redraw = true;
+            } else if matches_any(key, &keys.import) {
+                action = Some(MarkerListAction::StartInput(InputAction::MarkerImport));
+                effect.redraw = true;
             }
         }
 
         match action {
             Some(MarkerListAction::Jump(path)) => {
-                app.current_dir = path;
-                app.pending_selection = None;
-                app.selected = 0;
-                app.clear_preview();
-                app.refresh_dirs(tx);
+                app.jump_to_marker(path, tx);
             }
             Some(MarkerListAction::StartInput(action)) => {
                 Self::start_input(app, action);
@@ -1722,6 +4473,52 @@ impl InputHandler {
         effect
     }
 
+    /// Clicks select entries in the current list (entering directories, opening files on
+    /// double click) and the wheel moves the selection. Only active in normal mode.
+    fn handle_mouse(
+        app: &mut App,
+        mouse: MouseEvent,
+        tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    ) -> InputEffect {
+        let mut effect = InputEffect::default();
+        if !matches!(app.mode, Mode::Normal) {
+            return effect;
+        }
+        let moved = match mouse.kind {
+            MouseEventKind::ScrollUp => app.select_up(),
+            MouseEventKind::ScrollDown => app.select_down(),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let Some(index) = app
+                    .list_geometry
+                    .index_at(mouse.column, mouse.row)
+                    .filter(|&index| index < app.filtered_indices.len())
+                else {
+                    return effect;
+                };
+                let now = Instant::now();
+                let double = app.last_click.is_some_and(|(time, clicked)| {
+                    clicked == index && now.duration_since(time) < DOUBLE_CLICK
+                });
+                app.last_click = Some((now, index));
+                let moved = app.select_index(index);
+                let is_dir = app.selected_entry().is_some_and(|entry| entry.is_dir);
+                if double || is_dir {
+                    app.last_click = None;
+                    app.activate_selected(tx);
+                    effect.redraw = true;
+                    return effect;
+                }
+                moved
+            }
+            _ => false,
+        };
+        if moved {
+            effect.redraw = true;
+            effect.request_preview = true;
+        }
+        effect
+    }
+
     fn handle_program_list(
         app: &mut App,
         key: KeyEvent,
@@ -1754,8 +4551,12 @@ impl InputHandler {
             } else if matches_any(key, &keys.open) {
                 if let (Some(program), Some(target)) = (list.selected_entry(), target_path.as_ref())
                 {
+                    if app.last_programs.set(target, &program.name) {
+                        tokio::spawn(app.last_programs.save_task());
+                    }
                     action = Some(SuspendAction::OpenWith {
                         program: program.path.clone(),
+                        args: list.args(),
                         path: target.clone(),
                         cwd: cwd.clone(),
                     });
@@ -1786,17 +4587,130 @@ impl InputHandler {
         effect
     }
 
+    fn handle_palette(
+        app: &mut App,
+        key: KeyEvent,
+        tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    ) -> InputEffect {
+        let mut effect = InputEffect::default();
+        let Some(palette) = app.palette.as_mut() else {
+            app.mode = Mode::Normal;
+            return effect;
+        };
+        let keys = &app.keymap.palette;
+        effect.redraw = true;
+        if matches_any(key, &keys.close) {
+            app.palette = None;
+            app.mode = Mode::Normal;
+        } else if matches_any(key, &keys.up) {
+            palette.selected = palette.selected.saturating_sub(1);
+        } else if matches_any(key, &keys.down) {
+            if palette.selected + 1 < palette.filtered_indices.len() {
+                palette.selected += 1;
+            }
+        } else if matches_any(key, &keys.run) {
+            let command = palette
+                .selected_command()
+                .map(|command| (command.name, command.keys.clone()));
+            let Some((name, keys)) = command else {
+                return effect;
+            };
+            app.palette = None;
+            app.mode = Mode::Normal;
+            app.preview_focused = false;
+            let Some(keys) = keys else {
+                app.status = Some(StatusMessage::warning(format!("{name} has no key binding")));
+                return effect;
+            };
+            // Replays the command's keys so it behaves exactly as when typed.
+            for binding in keys {
+                let step =
+                    Self::handle_normal(app, KeyEvent::new(binding.code, binding.modifiers), tx);
+                effect.exit |= step.exit;
+                effect.redraw |= step.redraw;
+                effect.request_preview |= step.request_preview;
+                if step.suspend.is_some() {
+                    effect.suspend = step.suspend;
+                }
+            }
+            app.pending_prefix = None;
+        } else if matches_any(key, &keys.backspace) {
+            let mut next = palette.filter.clone();
+            next.pop();
+            palette.update_filter(next);
+        } else if let KeyCode::Char(ch) = key.code {
+            if ch.is_control() {
+                effect.redraw = false;
+            } else {
+                let mut next = palette.filter.clone();
+                next.push(ch);
+                palette.update_filter(next);
+            }
+        } else {
+            effect.redraw = false;
+        }
+        effect
+    }
+
+    fn handle_search_results(
+        app: &mut App,
+        key: KeyEvent,
+        tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    ) -> InputEffect {
+        let mut effect = InputEffect::default();
+        let mut target: Option<PathBuf> = None;
+        let mut close = false;
+        {
+            let Some(search) = app.content_search.as_mut() else {
+                app.mode = Mode::Normal;
+                return effect;
+            };
+            let keys = &app.keymap.search_results;
+            if matches_any(key, &keys.close) {
+                close = true;
+                effect.redraw = true;
+            } else if matches_any(key, &keys.up) {
+                if search.selected > 0 {
+                    search.selected -= 1;
+                    effect.redraw = true;
+                }
+            } else if matches_any(key, &keys.down) {
+                if search.selected + 1 < search.matches.len() {
+                    search.selected += 1;
+                    effect.redraw = true;
+                }
+            } else if matches_any(key, &keys.open) {
+                if let Some(item) = search.matches.get(search.selected) {
+                    target = Some(item.path.clone());
+                    close = true;
+                    effect.redraw = true;
+                }
+            }
+        }
+
+        if close {
+            app.close_content_search();
+            app.mode = Mode::Normal;
+        }
+        if let Some(path) = target {
+            app.reveal_path(&path, tx);
+        }
+        effect
+    }
+
     fn start_input(app: &mut App, action: InputAction) {
         let buffer = match &action {
-            InputAction::Search => app.filter.clone(),
+            InputAction::Search | InputAction::FuzzySearch => app.filter.clone(),
             InputAction::MarkerSearch => app
                 .marker_list
                 .as_ref()
                 .map(|list| list.filter.clone())
                 .unwrap_or_default(),
+            // Flattened entries are named by their relative path; only the name is renamed.
             InputAction::Rename => app
                 .selected_entry()
-                .map(|entry| entry.name.clone())
+                .and_then(|entry| entry.path.file_name())
+                .map(|name| name.to_string_lossy().to_string())
                 .unwrap_or_default(),
             InputAction::MarkerRename { name } => name.clone(),
             InputAction::MarkerEditPath { name } => app
@@ -1805,6 +4719,13 @@ impl InputHandler {
                 .map(|path| path.to_string_lossy().to_string())
                 .unwrap_or_default(),
             InputAction::MarkerCreatePath { .. } => app.current_dir.to_string_lossy().to_string(),
+            // Ends in a separator so tab completion lists the current directory.
+            InputAction::GoToPath => app.current_dir.join("").to_string_lossy().to_string(),
+            InputAction::MarkerExport | InputAction::MarkerImport => app
+                .current_dir
+                .join("markers.toml")
+                .to_string_lossy()
+                .to_string(),
             _ => String::new(),
         };
         app.pending_prefix = None;
@@ -1812,11 +4733,9 @@ impl InputHandler {
     }
 
     fn copy_selection(app: &mut App, op: ClipboardOp) {
-        if let Some(entry) = app.selected_entry() {
-            app.clipboard = Some(ClipboardEntry {
-                op,
-                path: entry.path.clone(),
-            });
+        let paths = app.target_paths();
+        if !paths.is_empty() {
+            app.clipboard = Some(ClipboardEntry { op, paths });
         }
     }
 
@@ -1824,28 +4743,159 @@ impl InputHandler {
         let Some(clipboard) = app.clipboard.clone() else {
             return;
         };
-        let Some(file_name) = clipboard.path.file_name() else {
+        Self::paste_paths(app, clipboard.op, &clipboard.paths, tx);
+    }
+
+    /// Copies the paths listed in system clipboard `text` (one per line, plain or
+    /// `file://` URIs) into the current directory. Paths that do not exist are reported
+    /// and left out.
+    fn paste_clipboard_text(app: &mut App, text: &str, tx: &tokio_mpsc::UnboundedSender<AppEvent>) {
+        let (paths, missing): (Vec<PathBuf>, Vec<PathBuf>) = core::clipboard_paths(text)
+            .into_iter()
+            .map(|path| app.current_dir.join(path))
+            .partition(|path| path.exists());
+        if !missing.is_empty() {
+            let names: Vec<_> = missing.iter().map(|path| path.to_string_lossy()).collect();
+            app.status = Some(StatusMessage::warning(format!(
+                "Not found: {}",
+                names.join(", ")
+            )));
+        } else if paths.is_empty() {
+            app.status = Some(StatusMessage::warning("No paths on the clipboard"));
+        }
+        if !paths.is_empty() {
+            Self::paste_paths(app, ClipboardOp::Copy, &paths, tx);
+        }
+    }
+
+    fn paste_paths(
+        app: &mut App,
+        op: ClipboardOp,
+        paths: &[PathBuf],
+        tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    ) {
+        if app.operation_running() {
             return;
+        }
+        let pairs: Vec<(PathBuf, PathBuf)> = paths
+            .iter()
+            .filter_map(|src| {
+                let file_name = src.file_name()?;
+                Some((src.clone(), app.paste_dir().join(file_name)))
+            })
+            .collect();
+        let conflicts = pairs.iter().filter(|(_, dest)| dest.exists()).count();
+        let request = PasteRequest {
+            op,
+            pairs,
+            conflicts,
         };
-        let dest = app.current_dir.join(file_name);
-        let select = Some(dest.clone());
-        match clipboard.op {
+        if conflicts > 0 {
+            Self::confirm_or_run(app, Destructive::Overwrite(request), tx);
+        } else {
+            Self::finish_paste(app, request, ConflictPolicy::Overwrite, tx);
+        }
+    }
+
+    /// Runs a paste whose conflicts are settled, first checking whether a move crosses
+    /// filesystems.
+    fn finish_paste(
+        app: &mut App,
+        request: PasteRequest,
+        policy: ConflictPolicy,
+        tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    ) {
+        let crosses_devices = request.op == ClipboardOp::Cut
+            && request.pairs.iter().any(|(src, dest)| {
+                dest.parent()
+                    .is_some_and(|dir| !core::same_filesystem(src, dir))
+            });
+        if crosses_devices {
+            Self::confirm_or_run(app, Destructive::CrossDeviceMove(request, policy), tx);
+        } else {
+            Self::run_paste(app, request, policy, tx);
+        }
+    }
+
+    /// Asks before `action` when the `[confirm]` config wants it, else runs it right away.
+    fn confirm_or_run(
+        app: &mut App,
+        action: Destructive,
+        tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    ) {
+        if action.needs_confirmation(&app.config.confirm) {
+            Self::start_input(app, InputAction::Confirm(action));
+        } else {
+            Self::run_destructive(app, action, tx);
+        }
+    }
+
+    fn run_destructive(
+        app: &mut App,
+        action: Destructive,
+        tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    ) {
+        match action {
+            Destructive::Delete(paths) => {
+                let progress = Arc::new(core::Progress::default());
+                let task = spawn_delete(tx, paths, app.config.delete.use_trash, progress.clone());
+                app.start_operation("Deleting", task, progress, tx);
+                app.marked.clear();
+            }
+            Destructive::Overwrite(request) => {
+                Self::finish_paste(app, request, ConflictPolicy::Overwrite, tx);
+            }
+            Destructive::CrossDeviceMove(request, policy) => {
+                Self::run_paste(app, request, policy, tx);
+            }
+        }
+    }
+
+    fn run_paste(
+        app: &mut App,
+        request: PasteRequest,
+        policy: ConflictPolicy,
+        tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    ) {
+        let pairs = request.pairs;
+        let select = pairs.first().map(|(_, dest)| dest.clone());
+        let progress = Arc::new(core::Progress::default());
+        let task_progress = progress.clone();
+        match request.op {
             ClipboardOp::Cut => {
-                let src = clipboard.path.clone();
-                let dest = dest.clone();
-                spawn_refresh(
-                    tx,
-                    select,
-                    async move { core::rename_path(&src, &dest).await },
-                );
+                let task = spawn_operation(tx, select, progress.clone(), async move {
+                    let mut undo = Vec::new();
+                    for (src, dest) in pairs {
+                        if task_progress.is_cancelled() {
+                            break;
+                        }
+                        match core::move_path(&src, &dest, policy, &task_progress).await {
+                            Ok(Some(to)) => undo.push(UndoEntry::Move { from: src, to }),
+                            Ok(None) => {}
+                            Err(err) => return (undo, Err(err)),
+                        }
+                    }
+                    (undo, Ok(()))
+                });
+                app.start_operation("Moving", task, progress, tx);
                 app.clipboard = None;
             }
             ClipboardOp::Copy => {
-                let src = clipboard.path.clone();
-                let dest = dest.clone();
-                spawn_refresh(tx, select, async move {
-                    core::copy_recursively(&src, &dest).await
+                let task = spawn_operation(tx, select, progress.clone(), async move {
+                    let mut result = Ok(());
+                    for (src, dest) in pairs {
+                        if let Err(err) =
+                            core::copy_recursively(&src, &dest, policy, &task_progress).await
+                        {
+                            if err.kind() == io::ErrorKind::Interrupted {
+                                return (Vec::new(), Err(err));
+                            }
+                            result = result.and(Err(err));
+                        }
+                    }
+                    (Vec::new(), result)
                 });
+                app.start_operation("Copying", task, progress, tx);
             }
         }
     }
@@ -1856,7 +4906,7 @@ struct TerminalGuard;
 impl TerminalGuard {
     fn enter() -> Result<Self, Box<dyn Error>> {
         enable_raw_mode()?;
-        execute!(io::stdout(), EnterAlternateScreen)?;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
         Ok(Self)
     }
 }
@@ -1864,7 +4914,12 @@ impl TerminalGuard {
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
         let _ = disable_raw_mode();
-        let _ = execute!(io::stdout(), LeaveAlternateScreen, cursor::Show);
+        let _ = execute!(
+            io::stdout(),
+            DisableMouseCapture,
+            LeaveAlternateScreen,
+            cursor::Show
+        );
     }
 }
 
@@ -1892,13 +4947,42 @@ fn spawn_input(
     })
 }
 
+/// Loads the saved program scan if it is still fresh, otherwise rescans `PATH` (and
+/// saves the result when `use_cache` is set).
+fn spawn_program_scan(tx: tokio_mpsc::UnboundedSender<AppEvent>, use_cache: bool) {
+    tokio::task::spawn_blocking(move || {
+        let scan = match ProgramScan::load_fresh().filter(|_| use_cache) {
+            Some(scan) => scan,
+            None => {
+                let scan = ProgramScan::scan();
+                if use_cache {
+                    let _ = scan.save();
+                }
+                scan
+            }
+        };
+        let _ = tx.send(AppEvent::Programs(scan.programs));
+    });
+}
+
+fn spawn_free_space(tx: tokio_mpsc::UnboundedSender<AppEvent>, id: u64, dir: PathBuf) {
+    tokio::task::spawn_blocking(move || {
+        let bytes = core::free_space(&dir);
+        let _ = tx.send(AppEvent::FreeSpace { id, bytes });
+    });
+}
+
 fn spawn_dir_listing(
     tx: tokio_mpsc::UnboundedSender<AppEvent>,
     target: DirTarget,
     id: u64,
     path: PathBuf,
+    count_children: bool,
+    replace: bool,
 ) {
     tokio::spawn(async move {
+        // A listing restored from the cache is only a first paint; the fresh one is sent
+        // whole to replace it.
         let stream = match core::read_dir_stream(&path).await {
             Ok(stream) => stream,
             Err(_) => {
@@ -1906,7 +4990,9 @@ fn spawn_dir_listing(
                     id,
                     target,
                     entries: Vec::new(),
+                    replace,
                     done: true,
+                    cache: false,
                 });
                 return;
             }
@@ -1919,28 +5005,214 @@ fn spawn_dir_listing(
                     batch.push(file_entry);
                 }
             }
-            if batch.len() >= DIR_BATCH_SIZE {
-                let entries = std::mem::take(&mut batch);
+            if !replace && batch.len() >= DIR_BATCH_SIZE {
+                let mut entries = std::mem::take(&mut batch);
+                if count_children {
+                    count_dir_children(&mut entries).await;
+                }
                 let _ = tx.send(AppEvent::DirEntries {
                     id,
                     target,
                     entries,
+                    replace: false,
+                    done: false,
+                    cache: false,
+                });
+            }
+        }
+        if count_children {
+            count_dir_children(&mut batch).await;
+        }
+        let _ = tx.send(AppEvent::DirEntries {
+            id,
+            target,
+            entries: batch,
+            replace,
+            done: true,
+            cache: true,
+        });
+    });
+}
+
+/// Fills in the child counts of the directories among `entries`, reading them in
+/// parallel.
+async fn count_dir_children(entries: &mut [FileEntry]) {
+    let counts: Vec<_> = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.is_dir)
+        .map(|(index, entry)| {
+            let path = entry.path.clone();
+            let count = tokio::task::spawn_blocking(move || core::count_children(&path));
+            (index, count)
+        })
+        .collect();
+    for (index, count) in counts {
+        entries[index].child_count = count.await.ok();
+    }
+}
+
+/// Lists the files below `root`, down to `max_depth` levels of subdirectories, named by
+/// their path relative to `root`. Hidden entries are skipped, and not descended into,
+/// unless `show_hidden`; linked directories are not followed. The walk stops after
+/// `FLAT_LISTING_LIMIT` files. With `replace` the listing is sent whole, to replace the one
+/// shown.
+fn spawn_flat_listing(
+    tx: tokio_mpsc::UnboundedSender<AppEvent>,
+    id: u64,
+    root: PathBuf,
+    max_depth: usize,
+    show_hidden: bool,
+    replace: bool,
+) {
+    tokio::spawn(async move {
+        let mut dirs = vec![(root.clone(), 0usize)];
+        let mut batch = Vec::with_capacity(DIR_BATCH_SIZE);
+        let mut listed = 0;
+        let mut truncated = false;
+        'walk: while let Some((dir, depth)) = dirs.pop() {
+            let Ok(mut stream) = core::read_dir_stream(&dir).await else {
+                continue;
+            };
+            while let Some(entry) = stream.next().await {
+                if listed == FLAT_LISTING_LIMIT {
+                    truncated = true;
+                    break 'walk;
+                }
+                let Ok(entry) = entry else {
+                    continue;
+                };
+                if !show_hidden && is_hidden_name(&entry.file_name().to_string_lossy()) {
+                    continue;
+                }
+                let Ok(mut file_entry) = FileEntry::from_dir_entry(entry).await else {
+                    continue;
+                };
+                if file_entry.is_dir {
+                    if !file_entry.is_symlink && depth < max_depth {
+                        dirs.push((file_entry.path, depth + 1));
+                    }
+                    continue;
+                }
+                if let Ok(relative) = file_entry.path.strip_prefix(&root) {
+                    file_entry.name = relative.to_string_lossy().to_string();
+                }
+                batch.push(file_entry);
+                listed += 1;
+                if !replace && batch.len() >= DIR_BATCH_SIZE {
+                    let entries = std::mem::take(&mut batch);
+                    let sent = tx.send(AppEvent::DirEntries {
+                        id,
+                        target: DirTarget::Current,
+                        entries,
+                        replace: false,
+                        done: false,
+                        cache: false,
+                    });
+                    if sent.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        // The flattened listing is never cached.
+        let _ = tx.send(AppEvent::DirEntries {
+            id,
+            target: DirTarget::Current,
+            entries: batch,
+            replace,
+            done: true,
+            cache: false,
+        });
+        if truncated {
+            let _ = tx.send(AppEvent::Status(StatusMessage::warning(format!(
+                "Flattened listing stopped at {FLAT_LISTING_LIMIT} files"
+            ))));
+        }
+    });
+}
+
+fn spawn_content_search(
+    tx: tokio_mpsc::UnboundedSender<AppEvent>,
+    id: u64,
+    root: PathBuf,
+    regex: regex::Regex,
+    show_hidden: bool,
+    cancel: Arc<AtomicBool>,
+) {
+    tokio::task::spawn_blocking(move || {
+        core::search_contents(
+            &root,
+            &regex,
+            show_hidden,
+            CONTENT_SEARCH_LIMIT,
+            &cancel,
+            |matches| {
+                tx.send(AppEvent::ContentMatches {
+                    id,
+                    matches,
                     done: false,
+                })
+                .is_ok()
+            },
+        );
+        let _ = tx.send(AppEvent::ContentMatches {
+            id,
+            matches: Vec::new(),
+            done: true,
+        });
+    });
+}
+
+fn spawn_mismatch_scan(
+    tx: tokio_mpsc::UnboundedSender<AppEvent>,
+    id: u64,
+    files: Vec<(String, PathBuf)>,
+    equivalents: Vec<Vec<String>>,
+    cancel: Arc<AtomicBool>,
+) {
+    tokio::task::spawn_blocking(move || {
+        let mut batch = Vec::new();
+        for (name, path) in files {
+            if cancel.load(Ordering::Relaxed) {
+                break;
+            }
+            // Reading a FIFO or a device node could block or never end.
+            if !std::fs::metadata(&path).is_ok_and(|metadata| metadata.is_file()) {
+                continue;
+            }
+            if let Ok(MismatchStatus::Mismatch {
+                detected,
+                extension,
+            }) = security::check_path_mismatch(&path, &equivalents)
+            {
+                batch.push(ContentMatch {
+                    path,
+                    relative: name,
+                    line: 0,
+                    text: format!(
+                        "looks like .{} ({}), not .{extension}",
+                        detected.extension, detected.mime
+                    ),
                 });
             }
+            if batch.len() == MISMATCH_SCAN_BATCH_SIZE {
+                let matches = std::mem::take(&mut batch);
+                if tx
+                    .send(AppEvent::ContentMatches {
+                        id,
+                        matches,
+                        done: false,
+                    })
+                    .is_err()
+                {
+                    return;
+                }
+            }
         }
-        if !batch.is_empty() {
-            let _ = tx.send(AppEvent::DirEntries {
-                id,
-                target,
-                entries: batch,
-                done: false,
-            });
-        }
-        let _ = tx.send(AppEvent::DirEntries {
+        let _ = tx.send(AppEvent::ContentMatches {
             id,
-            target,
-            entries: Vec::new(),
+            matches: batch,
             done: true,
         });
     });
@@ -1959,15 +5231,294 @@ fn spawn_image_worker(
     worker_tx
 }
 
+/// Watches the config file's directory, since editors often save by replacing the file.
+fn spawn_config_watcher(
+    path: PathBuf,
+    tx: tokio_mpsc::UnboundedSender<AppEvent>,
+) -> Option<RecommendedWatcher> {
+    let dir = path.parent()?.to_path_buf();
+    let file_name = path.file_name()?.to_os_string();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if matches!(event.kind, notify::EventKind::Access(_)) {
+            return;
+        }
+        if event
+            .paths
+            .iter()
+            .any(|path| path.file_name() == Some(file_name.as_os_str()))
+        {
+            let _ = tx.send(AppEvent::ConfigChanged);
+        }
+    })
+    .ok()?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive).ok()?;
+    Some(watcher)
+}
+
+/// Reports additions, removals and renames in the watched directory; contents changing
+/// within a file do not affect the listing.
+fn spawn_dir_watcher(tx: tokio_mpsc::UnboundedSender<AppEvent>) -> Option<RecommendedWatcher> {
+    notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if !matches!(
+            event.kind,
+            notify::EventKind::Create(_)
+                | notify::EventKind::Remove(_)
+                | notify::EventKind::Modify(notify::event::ModifyKind::Name(_))
+        ) {
+            return;
+        }
+        if let Some(dir) = event.paths.first().and_then(|path| path.parent()) {
+            let _ = tx.send(AppEvent::DirChanged(dir.to_path_buf()));
+        }
+    })
+    .ok()
+}
+
+/// Runs a file operation and refreshes the listing. A successful operation may return
+/// the entry that undoes it.
 fn spawn_refresh<F>(tx: &tokio_mpsc::UnboundedSender<AppEvent>, select: Option<PathBuf>, action: F)
+where
+    F: Future<Output = std::io::Result<Option<UndoEntry>>> + Send + 'static,
+{
+    let _ = tx.send(AppEvent::ActionStarted);
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let (message, undo) = match action.await {
+            Ok(undo) => (None, undo),
+            Err(err) => (
+                Some(StatusMessage::error(format!("Operation failed: {err}"))),
+                None,
+            ),
+        };
+        let _ = tx.send(AppEvent::Action(ActionResult::Refresh {
+            select,
+            message,
+            undo,
+        }));
+    });
+}
+
+fn spawn_rename(tx: &tokio_mpsc::UnboundedSender<AppEvent>, src: PathBuf, dest: PathBuf) {
+    if src == dest {
+        return;
+    }
+    spawn_refresh(tx, Some(dest.clone()), async move {
+        core::rename_path(&src, &dest).await?;
+        Ok(Some(UndoEntry::Rename {
+            from: src,
+            to: dest,
+        }))
+    });
+}
+
+/// The extension of `src` when `dest` has none.
+fn dropped_extension(src: &Path, dest: &Path) -> Option<String> {
+    if dest.extension().is_some() {
+        return None;
+    }
+    src.extension()
+        .map(|extension| extension.to_string_lossy().to_string())
+}
+
+/// Like [`spawn_refresh`], for an action over several items that can be cancelled through
+/// `progress`. The action returns the undo steps of the items it finished along with how
+/// it ended, so whatever was done before a cancel or a failure stays undoable.
+fn spawn_operation<F>(
+    tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    select: Option<PathBuf>,
+    progress: Arc<core::Progress>,
+    action: F,
+) -> tokio::task::AbortHandle
+where
+    F: Future<Output = (Vec<UndoEntry>, std::io::Result<()>)> + Send + 'static,
+{
+    let _ = tx.send(AppEvent::ActionStarted);
+    let tx = tx.clone();
+    let handle = tokio::spawn(async move {
+        let (undo, result) = action.await;
+        let message = if progress.is_cancelled() {
+            Some(StatusMessage::warning("Cancelled"))
+        } else {
+            result
+                .err()
+                .map(|err| StatusMessage::error(format!("Operation failed: {err}")))
+        };
+        let _ = tx.send(AppEvent::Action(ActionResult::Refresh {
+            select,
+            message,
+            undo: UndoEntry::batch(undo),
+        }));
+    });
+    handle.abort_handle()
+}
+
+/// Wakes the UI every 250ms until `task` ends, so progress shown for it stays current.
+fn spawn_progress_ticker(
+    tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    task: tokio::task::AbortHandle,
+) {
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_millis(250));
+        loop {
+            interval.tick().await;
+            let finished = task.is_finished();
+            if tx.send(AppEvent::OperationProgress).is_err() || finished {
+                break;
+            }
+        }
+    });
+}
+
+fn spawn_marker_export<F>(tx: &tokio_mpsc::UnboundedSender<AppEvent>, export: F)
 where
     F: Future<Output = std::io::Result<()>> + Send + 'static,
 {
+    let _ = tx.send(AppEvent::ActionStarted);
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let message = match export.await {
+            Ok(()) => StatusMessage::info("Markers exported"),
+            Err(err) => StatusMessage::error(format!("Export failed: {err}")),
+        };
+        let _ = tx.send(AppEvent::Action(ActionResult::Refresh {
+            select: None,
+            message: Some(message),
+            undo: None,
+        }));
+    });
+}
+
+fn spawn_marker_import(tx: &tokio_mpsc::UnboundedSender<AppEvent>, path: PathBuf) {
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let _ = tx.send(AppEvent::MarkersRead(MarkerStore::read_import(&path).await));
+    });
+}
+
+fn spawn_undo(tx: &tokio_mpsc::UnboundedSender<AppEvent>, entry: UndoEntry, use_trash: bool) {
+    let _ = tx.send(AppEvent::ActionStarted);
     let tx = tx.clone();
     tokio::spawn(async move {
-        let _ = action.await;
-        let _ = tx.send(AppEvent::Action(ActionResult::Refresh { select }));
+        let select = entry.restored_path();
+        let message = match undo_entry(&entry, use_trash).await {
+            Ok(()) => StatusMessage::info(format!("Undid {}", entry.label())),
+            Err(err) => StatusMessage::error(format!("Undo failed: {err}")),
+        };
+        let _ = tx.send(AppEvent::Action(ActionResult::Refresh {
+            select,
+            message: Some(message),
+            undo: None,
+        }));
+    });
+}
+
+async fn undo_entry(entry: &UndoEntry, use_trash: bool) -> std::io::Result<()> {
+    match entry {
+        UndoEntry::Create { path, parents } => {
+            core::remove_created(path).await?;
+            // Innermost first; a parent that holds anything else stops the walk.
+            for parent in parents.iter().rev() {
+                core::remove_created(parent).await?;
+            }
+            Ok(())
+        }
+        UndoEntry::Copy(path) => match delete_path(path, use_trash).await {
+            Some(message) if message.kind == StatusKind::Error => {
+                Err(io::Error::other(message.text))
+            }
+            _ => Ok(()),
+        },
+        UndoEntry::Rename { from, to } | UndoEntry::Move { from, to } => {
+            if tokio::fs::try_exists(from).await? {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", from.display()),
+                ));
+            }
+            core::rename_path(to, from).await
+        }
+        UndoEntry::Trash(path) => core::restore_from_trash(path).await,
+        UndoEntry::Delete(path) => Err(io::Error::other(format!(
+            "{} was deleted permanently",
+            path.display()
+        ))),
+        UndoEntry::Batch(entries) => {
+            let mut result = Ok(());
+            for entry in entries.iter().rev() {
+                result = result.and(Box::pin(undo_entry(entry, use_trash)).await);
+            }
+            result
+        }
+    }
+}
+
+fn spawn_delete(
+    tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    paths: Vec<PathBuf>,
+    use_trash: bool,
+    progress: Arc<core::Progress>,
+) -> tokio::task::AbortHandle {
+    let _ = tx.send(AppEvent::ActionStarted);
+    let tx = tx.clone();
+    let handle = tokio::spawn(async move {
+        let mut message = None;
+        let mut undo = Vec::new();
+        for path in paths {
+            if progress.is_cancelled() {
+                message.get_or_insert(StatusMessage::warning("Cancelled"));
+                break;
+            }
+            let item_message = delete_path(&path, use_trash).await;
+            let deleted = !item_message
+                .as_ref()
+                .is_some_and(|message| message.kind == StatusKind::Error);
+            if deleted {
+                undo.push(if use_trash && item_message.is_none() {
+                    UndoEntry::Trash(path)
+                } else {
+                    UndoEntry::Delete(path)
+                });
+            }
+            if let Some(item_message) = item_message {
+                message.get_or_insert(item_message);
+            }
+            progress.files.fetch_add(1, Ordering::Relaxed);
+        }
+        let _ = tx.send(AppEvent::Action(ActionResult::Refresh {
+            select: None,
+            message,
+            undo: UndoEntry::batch(undo),
+        }));
     });
+    handle.abort_handle()
+}
+
+async fn delete_path(path: &Path, use_trash: bool) -> Option<StatusMessage> {
+    if !use_trash {
+        return core::remove_path(path)
+            .await
+            .err()
+            .map(|err| StatusMessage::error(format!("Delete failed: {err}")));
+    }
+    match core::trash_path(path).await {
+        Ok(()) => None,
+        Err(err) if err.kind() == io::ErrorKind::Unsupported => Some(StatusMessage::error(
+            format!("Not deleted: {err}. Set delete.use_trash = false to delete permanently."),
+        )),
+        Err(err) => match core::remove_path(path).await {
+            Ok(()) => Some(StatusMessage::warning(format!(
+                "Trash failed ({err}); deleted permanently"
+            ))),
+            Err(err) => Some(StatusMessage::error(format!("Delete failed: {err}"))),
+        },
+    }
 }
 
 fn spawn_open(path: PathBuf) {
@@ -1976,8 +5527,7 @@ fn spawn_open(path: PathBuf) {
     });
 }
 
-fn spawn_copy_path(path: PathBuf) {
-    let value = path.to_string_lossy().to_string();
+fn spawn_copy_path(value: String) {
     tokio::task::spawn_blocking(move || {
         if let Ok(mut clipboard) = Clipboard::new() {
             let _ = clipboard.set_text(value);
@@ -1985,15 +5535,208 @@ fn spawn_copy_path(path: PathBuf) {
     });
 }
 
+/// Shows `selected` in the desktop file manager, selected where the platform supports it,
+/// and otherwise opens `dir`.
+fn spawn_file_manager(
+    tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    selected: Option<PathBuf>,
+    dir: PathBuf,
+) {
+    let tx = tx.clone();
+    tokio::task::spawn_blocking(move || {
+        if selected.is_some_and(|path| show_in_file_manager(&path)) {
+            return;
+        }
+        if let Err(err) = open::that(&dir) {
+            let _ = tx.send(AppEvent::Status(StatusMessage::error(format!(
+                "Failed to open file manager: {err}"
+            ))));
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+fn show_in_file_manager(path: &Path) -> bool {
+    Command::new("open")
+        .arg("-R")
+        .arg(path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Asks the file manager over D-Bus (`org.freedesktop.FileManager1.ShowItems`), which
+/// fails when no file manager provides the interface.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show_in_file_manager(path: &Path) -> bool {
+    Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+        ])
+        .arg(format!("array:string:{}", core::file_uri(path)))
+        .arg("string:")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Explorer's exit code does not tell whether it worked, so starting it counts.
+#[cfg(windows)]
+fn show_in_file_manager(path: &Path) -> bool {
+    let mut select = std::ffi::OsString::from("/select,");
+    select.push(path);
+    Command::new("explorer").arg(select).spawn().is_ok()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn show_in_file_manager(_path: &Path) -> bool {
+    false
+}
+
+/// Puts a text file's contents on the system clipboard, refusing binary and large files.
+fn spawn_copy_contents(tx: &tokio_mpsc::UnboundedSender<AppEvent>, path: PathBuf) {
+    let tx = tx.clone();
+    tokio::task::spawn_blocking(move || {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let message = match read_clipboard_text(&path) {
+            Ok(text) => match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+                Ok(()) => StatusMessage::info(format!("Copied the contents of {name}")),
+                Err(err) => StatusMessage::error(format!("Clipboard unavailable: {err}")),
+            },
+            Err(reason) => StatusMessage::warning(format!("Not copying {name}: {reason}")),
+        };
+        let _ = tx.send(AppEvent::Status(message));
+    });
+}
+
+/// The file as text for the clipboard, or why it cannot be copied.
+fn read_clipboard_text(path: &Path) -> Result<String, String> {
+    let metadata = std::fs::metadata(path).map_err(|err| err.to_string())?;
+    if !metadata.is_file() {
+        return Err("not a file".to_string());
+    }
+    if metadata.len() > COPY_CONTENTS_LIMIT {
+        return Err(format!(
+            "{} is over the {} limit",
+            ui::human_size(metadata.len()),
+            ui::human_size(COPY_CONTENTS_LIMIT)
+        ));
+    }
+    let bytes = std::fs::read(path).map_err(|err| err.to_string())?;
+    match String::from_utf8(bytes) {
+        Ok(text) if !text.contains('\0') => Ok(text),
+        _ => Err("not a text file".to_string()),
+    }
+}
+
+/// Reads the system clipboard off the UI thread and sends the text wrapped in `event`.
+fn spawn_read_clipboard(
+    tx: &tokio_mpsc::UnboundedSender<AppEvent>,
+    event: fn(Result<String, String>) -> AppEvent,
+) {
+    let tx = tx.clone();
+    tokio::task::spawn_blocking(move || {
+        let text = Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .map_err(|err| err.to_string());
+        let _ = tx.send(event(text));
+    });
+}
+
+/// Where TFM opens.
+struct StartLocation {
+    dir: PathBuf,
+    /// A file given as the start path, selected in `dir`.
+    select: Option<PathBuf>,
+    /// Why the configured `start_dir` was not used.
+    warning: Option<String>,
+}
+
+/// The start path given on the command line, else the configured `start_dir`, else the
+/// working directory. A bad command-line path is an error; a bad `start_dir` only warns.
+fn start_location(arg: Option<&str>, config: &Config) -> Result<StartLocation, String> {
+    if let Some(arg) = arg {
+        let (dir, select) = resolve_start(Path::new(arg))?;
+        return Ok(StartLocation {
+            dir,
+            select,
+            warning: None,
+        });
+    }
+    let current_dir =
+        env::current_dir().map_err(|err| format!("cannot read the working directory: {err}"))?;
+    let configured = config.start_dir.trim();
+    if configured.is_empty() {
+        return Ok(StartLocation {
+            dir: current_dir,
+            select: None,
+            warning: None,
+        });
+    }
+    Ok(match resolve_start(&core::expand_path(configured)) {
+        Ok((dir, select)) => StartLocation {
+            dir,
+            select,
+            warning: None,
+        },
+        Err(err) => StartLocation {
+            dir: current_dir,
+            select: None,
+            warning: Some(format!("start_dir ignored, {err}")),
+        },
+    })
+}
+
+/// The directory to open for `path` and, when `path` is a file, the file to select. Only
+/// the parent is resolved, so a symlink given as the last component is kept as is.
+fn resolve_start(path: &Path) -> Result<(PathBuf, Option<PathBuf>), String> {
+    let error = |err: io::Error| format!("{}: {err}", path.display());
+    let metadata = std::fs::metadata(path).map_err(error)?;
+    let resolved = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            std::fs::canonicalize(parent).map_err(error)?.join(name)
+        }
+        // `/`, or a path ending in `..`.
+        _ => std::fs::canonicalize(path).map_err(error)?,
+    };
+    if metadata.is_dir() {
+        return Ok((resolved, None));
+    }
+    match resolved.parent() {
+        Some(parent) => Ok((parent.to_path_buf(), Some(resolved.clone()))),
+        None => Err(format!("{}: not a directory", path.display())),
+    }
+}
+
 fn suspend_terminal() -> io::Result<()> {
     disable_raw_mode()?;
-    execute!(io::stdout(), LeaveAlternateScreen, cursor::Show)?;
+    execute!(
+        io::stdout(),
+        DisableMouseCapture,
+        LeaveAlternateScreen,
+        cursor::Show
+    )?;
     Ok(())
 }
 
 fn resume_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
     enable_raw_mode()?;
-    execute!(io::stdout(), EnterAlternateScreen)?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
     terminal.clear()?;
     Ok(())
 }
@@ -2003,12 +5746,41 @@ fn run_shell(path: &Path) -> io::Result<()> {
     Command::new(shell).current_dir(path).status().map(|_| ())
 }
 
-fn run_program(program: &Path, path: &Path, cwd: &Path) -> io::Result<()> {
-    Command::new(program)
+/// Starts `command` followed by `$SHELL` in its own window, leaving the TUI running.
+fn spawn_terminal(command: &str, cwd: &Path) -> io::Result<()> {
+    let shell = env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+    let mut words = command.split_whitespace();
+    let program = words.next().unwrap_or_default();
+    let mut child = Command::new(program)
         .current_dir(cwd)
-        .arg(path)
-        .status()
-        .map(|_| ())
+        .args(words)
+        .arg(shell)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the terminal when it closes so it does not linger as a zombie.
+    tokio::task::spawn_blocking(move || child.wait());
+    Ok(())
+}
+
+/// Runs `program` on `path`, which replaces `{}` in the arguments or is appended when
+/// there is no placeholder.
+fn run_program(program: &Path, args: &[String], path: &Path, cwd: &Path) -> io::Result<()> {
+    let mut command = Command::new(program);
+    command.current_dir(cwd);
+    if args.iter().any(|arg| arg.contains("{}")) {
+        for arg in args {
+            if arg == "{}" {
+                command.arg(path);
+            } else {
+                command.arg(arg.replace("{}", &path.to_string_lossy()));
+            }
+        }
+    } else {
+        command.args(args).arg(path);
+    }
+    command.status().map(|_| ())
 }
 
 fn run_suspend_action(
@@ -2025,7 +5797,12 @@ fn run_suspend_action(
 
     let action_result = match action {
         SuspendAction::Shell(path) => run_shell(&path),
-        SuspendAction::OpenWith { program, path, cwd } => run_program(&program, &path, &cwd),
+        SuspendAction::OpenWith {
+            program,
+            args,
+            path,
+            cwd,
+        } => run_program(&program, &args, &path, &cwd),
     };
 
     let resume_result = resume_terminal(terminal);
@@ -2039,14 +5816,37 @@ fn run_suspend_action(
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--init-config") {
+        let force = args.iter().any(|arg| arg == "--force");
+        match Config::init(force) {
+            Ok(path) => println!("Wrote default config to {}", path.display()),
+            Err(err) => {
+                eprintln!("Error: {err}");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
     let config = match Config::load() {
         Ok(config) => config,
         Err(err) => {
             eprintln!(
                 "Warning: failed to load config ({err}). Starting with defaults.\n\
-Fix your config and restart, or set TFM_CONFIG to a valid config file."
+Fix your config (it is reloaded on save), or set TFM_CONFIG to a valid config file."
             );
-            Config::default()
+            Config {
+                source: Config::resolve_path(),
+                ..Config::default()
+            }
+        }
+    };
+    let start_arg = args.iter().find(|arg| !arg.starts_with("--"));
+    let start = match start_location(start_arg.map(String::as_str), &config) {
+        Ok(start) => start,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            std::process::exit(1);
         }
     };
     let guard = TerminalGuard::enter()?;
@@ -2069,8 +5869,13 @@ Fix your config and restart, or set TFM_CONFIG to a valid config file."
     let _input_handle = spawn_input(tx.clone(), input_paused.clone());
     let image_worker_tx = spawn_image_worker(tx.clone());
 
-    let mut app = App::new(config, picker, image_worker_tx, &tx).await?;
+    let _config_watcher = config
+        .source
+        .clone()
+        .and_then(|path| spawn_config_watcher(path, tx.clone()));
+    let mut app = App::new(config, start, picker, image_worker_tx, &tx).await;
     terminal.draw(|frame| ui::render(frame, app.ui_state()))?;
+    let mut exit_dir = None;
 
     while let Some(event) = rx.recv().await {
         let mut redraw = false;
@@ -2080,14 +5885,21 @@ Fix your config and restart, or set TFM_CONFIG to a valid config file."
                 if key.kind != KeyEventKind::Press {
                     continue;
                 }
+                if app.status.take().is_some() {
+                    redraw = true;
+                }
                 let effect = InputHandler::handle_key(&mut app, key, &tx);
                 if let Some(action) = effect.suspend {
                     if let Err(err) = run_suspend_action(&mut terminal, &input_paused, action) {
                         eprintln!("Failed to run command: {err}");
                     }
+                    // The program may have changed the selected file.
+                    app.clear_preview();
+                    request_preview = true;
                     redraw = true;
                 }
                 if effect.exit {
+                    exit_dir = Some(app.current_dir.clone());
                     break;
                 }
                 if effect.redraw {
@@ -2097,6 +5909,15 @@ Fix your config and restart, or set TFM_CONFIG to a valid config file."
                     request_preview = true;
                 }
             }
+            AppEvent::Input(Event::Mouse(mouse)) => {
+                let effect = InputHandler::handle_mouse(&mut app, mouse, &tx);
+                if effect.redraw {
+                    redraw = true;
+                }
+                if effect.request_preview {
+                    request_preview = true;
+                }
+            }
             AppEvent::Input(Event::Resize(_, _)) => {
                 redraw = true;
             }
@@ -2109,27 +5930,66 @@ Fix your config and restart, or set TFM_CONFIG to a valid config file."
                 id,
                 target,
                 entries,
+                replace,
                 done,
+                cache,
             } => {
                 if id != app.listing_id {
                     continue;
                 }
                 let selected_path = app.selected_entry().map(|entry| entry.path.clone());
+                let sort_options = app.sort_options();
+                let other_dir = app.other_pane.as_ref().map(|pane| pane.dir.clone());
                 let list = match target {
                     DirTarget::Parent => &mut app.parent_entries,
                     DirTarget::Current => &mut app.current_entries,
+                    DirTarget::Other => match app.other_pane.as_mut() {
+                        Some(pane) => &mut pane.entries,
+                        None => continue,
+                    },
                 };
                 let mut entries = entries;
                 if !app.show_hidden {
                     entries.retain(|entry| !is_hidden_name(&entry.name));
                 }
+                if replace {
+                    list.clear();
+                }
                 list.extend(entries);
                 if done {
-                    core::sort_entries(list);
+                    match target {
+                        DirTarget::Parent => app.parent_loading = false,
+                        DirTarget::Current => app.current_loading = false,
+                        DirTarget::Other => app.other_loading = false,
+                    }
+                    core::sort_entries(list, sort_options);
+                    let dir = match target {
+                        DirTarget::Parent => app.current_dir.parent(),
+                        DirTarget::Current => Some(app.current_dir.as_path()),
+                        DirTarget::Other => other_dir.as_deref(),
+                    };
+                    if let Some(dir) = dir.filter(|_| cache) {
+                        let counted = matches!(target, DirTarget::Current) && app.show_list_size;
+                        app.listing_cache.insert(
+                            dir.to_path_buf(),
+                            list.clone(),
+                            app.show_hidden,
+                            counted,
+                        );
+                    }
                 }
                 if matches!(target, DirTarget::Current) {
+                    if done && !app.marked.is_empty() {
+                        let present: HashSet<&Path> = app
+                            .current_entries
+                            .iter()
+                            .map(|entry| entry.path.as_path())
+                            .collect();
+                        app.marked.retain(|path| present.contains(path.as_path()));
+                    }
                     let preferred = if done {
-                        app.pending_selection.take().or(selected_path)
+                        let preferred = app.pending_selection.take().or(selected_path);
+                        app.surviving_selection(preferred)
                     } else {
                         selected_path
                     };
@@ -2155,9 +6015,194 @@ Fix your config and restart, or set TFM_CONFIG to a valid config file."
                     }
                 }
             }
-            AppEvent::Action(ActionResult::Refresh { select }) => {
+            AppEvent::ContentMatches { id, matches, done } => {
+                if let Some(search) = app.content_search.as_mut() {
+                    if search.id == id {
+                        search.matches.extend(matches);
+                        search.done = done;
+                        redraw = true;
+                    }
+                }
+            }
+            AppEvent::ConfigChanged => {
+                app.reload_config();
+                redraw = true;
+            }
+            AppEvent::Status(message) => {
+                app.status = Some(message);
+                redraw = true;
+            }
+            AppEvent::OpenChecked { path, detail } => {
+                match detail {
+                    // Nothing is opened if the user moved on to something else meanwhile.
+                    Some(detail) if matches!(app.mode, Mode::Normal) => {
+                        app.mode = Mode::Input(InputState::new(
+                            InputAction::ConfirmOpen { path, detail },
+                            String::new(),
+                        ));
+                        redraw = true;
+                    }
+                    Some(_) => {}
+                    None => spawn_open(path),
+                }
+            }
+            AppEvent::DirSize { path, size } => {
+                // A result for a calculation that was replaced or cancelled is dropped.
+                if let Some(dir_size) = app
+                    .dir_size
+                    .as_mut()
+                    .filter(|dir_size| dir_size.path == path && dir_size.size.is_none())
+                {
+                    dir_size.size = Some(size);
+                    if !app.show_metadata {
+                        let name = path.file_name().unwrap_or(path.as_os_str());
+                        app.status = Some(StatusMessage::info(format!(
+                            "{}: {}",
+                            name.to_string_lossy(),
+                            ui::human_size(size)
+                        )));
+                    }
+                    redraw = true;
+                }
+            }
+            AppEvent::DirChanged(dir) if dir == app.current_dir && !app.watch_refresh_pending => {
+                app.watch_refresh_pending = true;
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(WATCH_DEBOUNCE).await;
+                    let _ = tx.send(AppEvent::WatchRefresh(dir));
+                });
+            }
+            AppEvent::WatchRefresh(dir) => {
+                app.watch_refresh_pending = false;
+                // A running file operation re-lists the directory when it finishes.
+                if dir == app.current_dir && app.pending_actions == 0 {
+                    app.refresh_watched(&tx);
+                    redraw = true;
+                }
+            }
+            AppEvent::MarkersRead(result) => {
+                app.status = Some(match result {
+                    Ok(imported) => {
+                        let added = app.markers.import(imported);
+                        tokio::spawn(app.markers.save_task());
+                        app.sync_marker_list(None);
+                        StatusMessage::info(format!("Imported {added} markers"))
+                    }
+                    Err(err) => StatusMessage::error(format!("Import failed: {err}")),
+                });
+                redraw = true;
+            }
+            AppEvent::Programs(programs) => {
+                if let Some(list) = app.program_list.as_mut() {
+                    list.set_programs(&programs);
+                    redraw = true;
+                }
+                app.programs = programs;
+                if app.status.is_none() {
+                    app.status = app.quick_slot_warning();
+                    redraw = true;
+                }
+            }
+            AppEvent::FreeSpace { id, bytes } if id == app.listing_id => {
+                app.free_space = bytes;
+                redraw = true;
+            }
+            AppEvent::Categories(categories) => {
+                app.sniffing = false;
+                let categories: HashMap<PathBuf, FileCategory> = categories.into_iter().collect();
+                for entry in &mut app.current_entries {
+                    if let Some(category) = categories.get(&entry.path) {
+                        entry.category = Some(*category);
+                    }
+                }
+                let selected = app.selected_entry().map(|entry| entry.path.clone());
+                if app.apply_filter(selected) {
+                    app.clear_preview();
+                    request_preview = true;
+                }
+                redraw = true;
+            }
+            AppEvent::SystemClipboard(result) => {
+                match result {
+                    Ok(text) => InputHandler::paste_clipboard_text(&mut app, &text, &tx),
+                    Err(err) => {
+                        app.status = Some(StatusMessage::error(format!(
+                            "Clipboard unavailable: {err}"
+                        )));
+                    }
+                }
+                redraw = true;
+            }
+            AppEvent::InputClipboard(result) => {
+                match result {
+                    Ok(text) => {
+                        let effect = InputHandler::paste_into_input(&mut app, &text);
+                        if effect.request_preview {
+                            app.request_preview(&tx);
+                        }
+                    }
+                    Err(err) => {
+                        app.status = Some(StatusMessage::error(format!(
+                            "Clipboard unavailable: {err}"
+                        )));
+                    }
+                }
+                redraw = true;
+            }
+            AppEvent::ActionStarted => {
+                app.pending_actions += 1;
+            }
+            AppEvent::OperationProgress => {
+                if app
+                    .operation
+                    .as_ref()
+                    .is_some_and(|operation| operation.task.is_finished())
+                {
+                    app.operation = None;
+                }
+                redraw = true;
+            }
+            AppEvent::Action(ActionResult::Refresh {
+                select,
+                message,
+                undo,
+            }) => {
+                // File operations can change what cached previews and listings show (e.g.
+                // chmod keeps the modification time), so start over.
+                app.preview_cache.clear();
+                app.listing_cache.clear();
                 if let Some(path) = select {
-                    app.pending_selection = Some(path);
+                    let in_other_pane = app.other_pane.as_ref().is_some_and(|pane| {
+                        pane.dir != app.current_dir && path.parent() == Some(pane.dir.as_path())
+                    });
+                    if in_other_pane {
+                        if let Some(pane) = app.other_pane.as_mut() {
+                            pane.selected = Some(path);
+                        }
+                    } else {
+                        // The action changed the selected entry in place (e.g. chmod), so
+                        // its preview and metadata are stale.
+                        if app.selected_entry().is_some_and(|entry| entry.path == path) {
+                            app.clear_preview();
+                            request_preview = true;
+                        }
+                        app.pending_selection = Some(path);
+                    }
+                }
+                if let Some(entry) = undo {
+                    if app.undo_stack.len() == UNDO_LIMIT {
+                        app.undo_stack.remove(0);
+                    }
+                    app.undo_stack.push(entry);
+                }
+                if message.is_some() {
+                    app.status = message;
+                }
+                app.pending_actions = app.pending_actions.saturating_sub(1);
+                if app.quit_when_idle && app.pending_actions == 0 {
+                    exit_dir = Some(app.current_dir.clone());
+                    break;
                 }
                 app.refresh_dirs(&tx);
                 redraw = true;
@@ -2165,9 +6210,16 @@ Fix your config and restart, or set TFM_CONFIG to a valid config file."
             _ => {}
         }
 
+        // The preview, or the metadata bar, came back into view.
+        if app.preview_skipped && app.preview_shown() {
+            request_preview = true;
+        }
         if request_preview {
             app.request_preview(&tx);
         }
+        if app.sniff_wanted {
+            app.sniff_categories(&tx);
+        }
 
         if redraw {
             terminal.draw(|frame| ui::render(frame, app.ui_state()))?;
@@ -2177,5 +6229,30 @@ Fix your config and restart, or set TFM_CONFIG to a valid config file."
     drop(terminal);
     drop(guard);
 
+    if let (Some(dir), Some(target)) = (exit_dir, env::var_os("TFM_CWD_FILE")) {
+        if let Err(err) = write_cwd_file(Path::new(&target), &dir) {
+            eprintln!("Failed to write TFM_CWD_FILE: {err}");
+        }
+    }
+
     Ok(())
 }
+
+/// Writes `dir` as the only content of `target` (no trailing newline). The file is
+/// written next to `target` first and renamed so a shell wrapper never reads a partial path.
+fn write_cwd_file(target: &Path, dir: &Path) -> io::Result<()> {
+    let mut temp_name = target.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp = target.with_file_name(temp_name);
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        dir.as_os_str().as_bytes().to_vec()
+    };
+    #[cfg(not(unix))]
+    let bytes = dir.to_string_lossy().into_owned().into_bytes();
+    std::fs::write(&temp, bytes)?;
+    std::fs::rename(&temp, target).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
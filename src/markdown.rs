@@ -0,0 +1,242 @@
+use crate::config::Config;
+use crate::ui::{self, HighlightedText};
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+
+const RULE_WIDTH: usize = 40;
+
+/// Renders Markdown with terminal styling: headings, emphasis, lists, quotes and
+/// highlighted code blocks. Line breaks within paragraphs are kept as in the source, so
+/// tables and hard-wrapped text still line up.
+pub fn render(text: &str, config: &Config, accent: Style) -> HighlightedText {
+    let mut renderer = Renderer {
+        config,
+        accent,
+        lines: Vec::new(),
+        spans: Vec::new(),
+        styles: Vec::new(),
+        lists: Vec::new(),
+        quotes: 0,
+        marker: None,
+        code: None,
+    };
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    for event in Parser::new_ext(text, options) {
+        renderer.event(event);
+    }
+    renderer.flush();
+    while renderer
+        .lines
+        .last()
+        .is_some_and(|line| line.spans.is_empty())
+    {
+        renderer.lines.pop();
+    }
+    Text::from(renderer.lines)
+}
+
+struct Renderer<'a> {
+    config: &'a Config,
+    accent: Style,
+    lines: Vec<Line<'static>>,
+    /// The line being built.
+    spans: Vec<Span<'static>>,
+    /// Inline styles in effect, innermost last.
+    styles: Vec<Style>,
+    /// Next number of each open list, `None` for bullet lists.
+    lists: Vec<Option<u64>>,
+    quotes: usize,
+    /// Bullet or number starting the next line of a list item.
+    marker: Option<String>,
+    /// Language and source of the code block being collected.
+    code: Option<(String, String)>,
+}
+
+impl Renderer<'_> {
+    fn event(&mut self, event: Event<'_>) {
+        if let Some((_, source)) = self.code.as_mut() {
+            match event {
+                Event::Text(text) => source.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => self.end_code_block(),
+                _ => {}
+            }
+            return;
+        }
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.push(text.into_string(), self.style()),
+            Event::Code(code) => self.push(code.into_string(), self.accent),
+            Event::Html(html) | Event::InlineHtml(html) => {
+                let style = self.style().add_modifier(Modifier::DIM);
+                for (index, line) in html.lines().enumerate() {
+                    if index > 0 {
+                        self.flush();
+                    }
+                    self.push(line.to_string(), style);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.flush();
+                let rule = "─".repeat(RULE_WIDTH);
+                self.push(rule, self.accent);
+                self.flush();
+                self.blank();
+            }
+            Event::TaskListMarker(done) => {
+                let marker = if done { "[x] " } else { "[ ] " };
+                self.push(marker.to_string(), self.accent);
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush();
+                let style = match level {
+                    HeadingLevel::H1 => self
+                        .accent
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                    HeadingLevel::H2 => self.accent.add_modifier(Modifier::BOLD),
+                    _ => Style::default().add_modifier(Modifier::BOLD),
+                };
+                self.styles.push(style);
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quotes += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((language, String::new()));
+            }
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let depth = self.lists.len().saturating_sub(1);
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.marker = Some(format!("{}{bullet}", "  ".repeat(depth)));
+            }
+            Tag::Emphasis => self
+                .styles
+                .push(self.style().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.styles.push(self.style().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => self
+                .styles
+                .push(self.style().add_modifier(Modifier::CROSSED_OUT)),
+            Tag::Link { .. } | Tag::Image { .. } => self
+                .styles
+                .push(self.style().add_modifier(Modifier::UNDERLINED)),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => {
+                self.styles.pop();
+                self.flush();
+                self.blank();
+            }
+            TagEnd::Paragraph => {
+                self.flush();
+                if self.lists.is_empty() {
+                    self.blank();
+                }
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quotes = self.quotes.saturating_sub(1);
+                self.blank();
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank();
+                }
+            }
+            TagEnd::Item => self.flush(),
+            TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Link
+            | TagEnd::Image => {
+                self.styles.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn end_code_block(&mut self) {
+        let Some((language, source)) = self.code.take() else {
+            return;
+        };
+        for line in ui::highlight_code(&source, &language, self.config) {
+            let mut spans = self.line_prefix();
+            spans.extend(line.spans);
+            self.lines.push(Line::from(spans));
+        }
+        self.blank();
+    }
+
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    fn push(&mut self, text: String, style: Style) {
+        if self.spans.is_empty() {
+            self.spans = self.line_prefix();
+        }
+        self.spans.push(Span::styled(text, style));
+    }
+
+    /// Quote bars and list indentation (or the item's marker) that start a line.
+    fn line_prefix(&mut self) -> Vec<Span<'static>> {
+        let mut prefix = Vec::new();
+        if self.quotes > 0 {
+            prefix.push(Span::styled("│ ".repeat(self.quotes), self.accent));
+        }
+        match self.marker.take() {
+            Some(marker) => prefix.push(Span::styled(marker, self.accent)),
+            None if !self.lists.is_empty() => {
+                prefix.push(Span::raw("  ".repeat(self.lists.len())));
+            }
+            None => {}
+        }
+        prefix
+    }
+
+    fn flush(&mut self) {
+        if !self.spans.is_empty() {
+            let spans = std::mem::take(&mut self.spans);
+            self.lines.push(Line::from(spans));
+        }
+    }
+
+    /// Separates blocks with one empty line.
+    fn blank(&mut self) {
+        if self.lines.last().is_some_and(|line| !line.spans.is_empty()) {
+            self.lines.push(Line::default());
+        }
+    }
+}
@@ -1,8 +1,9 @@
+use crate::core;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::future::Future;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
 #[derive(Debug)]
@@ -34,6 +35,15 @@ impl MarkerStore {
         self.markers.insert(key.into(), path);
     }
 
+    /// Quick markers use the reserved names `0`-`9`.
+    pub fn quick(&self, digit: char) -> Option<&PathBuf> {
+        self.markers.get(digit.to_string().as_str())
+    }
+
+    pub fn set_quick(&mut self, digit: char, path: PathBuf) {
+        self.markers.insert(digit.to_string(), path);
+    }
+
     pub fn remove(&mut self, key: &str) -> bool {
         self.markers.remove(key).is_some()
     }
@@ -54,21 +64,64 @@ impl MarkerStore {
     }
 
     pub fn save_task(&self) -> impl Future<Output = io::Result<()>> + Send + 'static {
-        let path = self.path.clone();
+        self.export_task(self.path.clone())
+    }
+
+    /// Writes all markers to `path` in the same format as `markers.toml`.
+    pub fn export_task(
+        &self,
+        path: PathBuf,
+    ) -> impl Future<Output = io::Result<()>> + Send + 'static {
         let markers = self.markers.clone();
         async move { save_markers(path, markers).await }
     }
+
+    /// Reads the markers from a file written by `export_task`, to be merged with
+    /// `import`.
+    pub async fn read_import(path: &Path) -> io::Result<HashMap<String, PathBuf>> {
+        let content = fs::read_to_string(path).await?;
+        let file: MarkerFile = toml::from_str(&content)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        Ok(markers_from_file(file))
+    }
+
+    /// Merges markers read by `read_import`. A marker whose name is taken by a different
+    /// path is added as `name-2`, `name-3`, ...; markers already present are skipped.
+    /// Returns how many markers were added.
+    pub fn import(&mut self, imported: HashMap<String, PathBuf>) -> usize {
+        let mut imported: Vec<(String, PathBuf)> = imported.into_iter().collect();
+        imported.sort();
+        let mut added = 0;
+        'markers: for (name, path) in imported {
+            let mut unique = name.clone();
+            let mut suffix = 2;
+            while let Some(existing) = self.markers.get(&unique) {
+                if *existing == path {
+                    continue 'markers;
+                }
+                unique = format!("{name}-{suffix}");
+                suffix += 1;
+            }
+            self.markers.insert(unique, path);
+            added += 1;
+        }
+        added
+    }
 }
 
 fn parse_markers(content: &str) -> HashMap<String, PathBuf> {
-    let file: MarkerFile = toml::from_str(content).unwrap_or_default();
+    markers_from_file(toml::from_str(content).unwrap_or_default())
+}
+
+fn markers_from_file(file: MarkerFile) -> HashMap<String, PathBuf> {
     let mut markers = HashMap::new();
     for (key, value) in file.markers {
         let name = key.trim();
         if name.is_empty() {
             continue;
         }
-        markers.insert(name.to_string(), PathBuf::from(value));
+        // Hand-edited files may use `~` or `$VARS`.
+        markers.insert(name.to_string(), core::expand_path(&value));
     }
     markers
 }
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// The program last picked in the open-with list for each file extension, preselected the
+/// next time the list opens for a file with that extension.
+#[derive(Debug)]
+pub struct LastPrograms {
+    path: PathBuf,
+    programs: HashMap<String, String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct LastProgramsFile {
+    programs: HashMap<String, String>,
+}
+
+impl LastPrograms {
+    pub async fn load() -> Self {
+        let path = default_last_programs_path();
+        let programs = match fs::read_to_string(&path).await {
+            Ok(content) => toml::from_str::<LastProgramsFile>(&content)
+                .map(|file| file.programs)
+                .unwrap_or_default(),
+            Err(_) => HashMap::new(),
+        };
+        Self { path, programs }
+    }
+
+    /// The program name remembered for the extension of `target`.
+    pub fn get(&self, target: &Path) -> Option<&str> {
+        self.programs
+            .get(&extension_key(target)?)
+            .map(String::as_str)
+    }
+
+    /// Remembers `program` for the extension of `target`; returns whether anything changed.
+    pub fn set(&mut self, target: &Path, program: &str) -> bool {
+        let Some(extension) = extension_key(target) else {
+            return false;
+        };
+        if self.programs.get(&extension).map(String::as_str) == Some(program) {
+            return false;
+        }
+        self.programs.insert(extension, program.to_string());
+        true
+    }
+
+    pub fn save_task(&self) -> impl Future<Output = io::Result<()>> + Send + 'static {
+        let path = self.path.clone();
+        let file = LastProgramsFile {
+            programs: self.programs.clone(),
+        };
+        async move {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            let content = toml::to_string(&file)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+            fs::write(&path, content).await
+        }
+    }
+}
+
+/// Lowercase extension of `path`; files without one are not remembered.
+fn extension_key(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_str()?;
+    Some(extension.to_ascii_lowercase())
+}
+
+fn default_last_programs_path() -> PathBuf {
+    if let Some(dir) = dirs::config_dir() {
+        return dir.join("tfm").join("open_with.toml");
+    }
+    if let Some(home) = dirs::home_dir() {
+        return home.join(".tfm.open_with.toml");
+    }
+    PathBuf::from("open_with.toml")
+}
@@ -1,25 +1,56 @@
-use crate::config::Config;
+use crate::archive::{self, ArchiveEntry, ARCHIVE_PREVIEW_LIMIT};
+use crate::audio;
+use crate::config::{Config, MismatchAction};
+use crate::core::{self, FileEntry, SortOptions};
 use crate::security::{self, MismatchStatus};
-use image::DynamicImage;
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use image::codecs::jpeg::JpegDecoder;
+use image::{DynamicImage, ImageDecoder};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
-use time::format_description::well_known::Rfc3339;
+use time::format_description::{self, well_known::Rfc3339};
 use time::OffsetDateTime;
 use tokio::fs::{self, File};
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
-#[cfg(unix)]
-use std::os::unix::fs::MetadataExt;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
-const PREVIEW_LIMIT: usize = 65536;
+/// Bounds for `preview.max_bytes`.
+const MIN_PREVIEW_BYTES: usize = 1024;
+const MAX_PREVIEW_BYTES: usize = 16 * 1024 * 1024;
+/// `metadata_bar.date_format` values that are not format descriptions.
+pub const RFC3339_DATE_FORMAT: &str = "rfc3339";
+pub const RELATIVE_DATE_FORMAT: &str = "relative";
+/// Directory previews stop reading after this many entries.
+pub const DIRECTORY_PREVIEW_LIMIT: usize = 500;
+/// CSV previews parse at most this many data rows.
+pub const TABLE_PREVIEW_ROWS: usize = 200;
+/// Most symlinks followed when listing a link chain, as many as Linux resolves.
+const MAX_LINK_HOPS: usize = 40;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum PreviewData {
     Text(String),
-    Image { width: u32, height: u32 },
-    Binary { size: u64 },
+    Image {
+        width: u32,
+        height: u32,
+    },
+    Binary {
+        size: u64,
+    },
+    Directory(Vec<FileEntry>),
+    /// A directory that could not be listed, with the reason.
+    Unreadable(String),
+    Archive(Vec<ArchiveEntry>),
+    Table {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
     Empty,
 }
 
@@ -27,18 +58,46 @@ pub enum PreviewData {
 pub struct FileMetadata {
     pub permissions: String,
     pub owner: String,
-    pub created: Option<String>,
-    pub modified: Option<String>,
-    pub accessed: Option<String>,
+    /// `None` for directories.
+    pub size: Option<u64>,
+    /// Kept as times so the metadata bar can format them either way when drawing.
+    pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    pub image: Option<ImageInfo>,
+    /// Content type detected from the file's bytes (`security.on_mismatch = "show"`).
+    pub detected: Option<String>,
+}
+
+/// Details shown in the metadata bar for image previews.
+#[derive(Debug, Clone)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+    /// Frame count of an animated GIF, PNG or WebP; only the first frame is previewed.
+    pub frames: Option<usize>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Preview {
     pub path: PathBuf,
     pub data: PreviewData,
     pub mismatch: Option<MismatchStatus>,
     pub metadata: Option<FileMetadata>,
     pub image: Option<DynamicImage>,
+    pub link: Option<LinkInfo>,
+}
+
+/// Where a previewed symlink points: the resolved path, or the raw target when broken.
+#[derive(Debug, Clone)]
+pub struct LinkInfo {
+    pub target: PathBuf,
+    pub broken: bool,
+    /// Where each link in turn points, ending at the first path that is not a link.
+    pub chain: Vec<PathBuf>,
+    /// The chain comes back to a link it passed (or is longer than `MAX_LINK_HOPS`).
+    pub cycle: bool,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -47,9 +106,40 @@ pub enum PreviewError {
     Io(#[from] std::io::Error),
 }
 
-pub async fn load(path: &Path, config: &Config) -> Result<Preview, PreviewError> {
+pub async fn load(
+    path: &Path,
+    config: &Config,
+    sort: SortOptions,
+    show_hidden: bool,
+) -> Result<Preview, PreviewError> {
+    let link = link_info(path).await;
+    if link.as_ref().is_some_and(|link| link.broken) {
+        let metadata = fs::symlink_metadata(path).await?;
+        return Ok(Preview {
+            path: path.to_path_buf(),
+            data: PreviewData::Empty,
+            mismatch: None,
+            metadata: Some(build_metadata(&metadata)),
+            image: None,
+            link,
+        });
+    }
     let metadata = fs::metadata(path).await?;
-    let file_metadata = build_metadata(&metadata);
+    let mut file_metadata = build_metadata(&metadata);
+    if metadata.is_dir() {
+        let data = match read_directory(path, sort, show_hidden).await {
+            Ok(entries) => PreviewData::Directory(entries),
+            Err(err) => PreviewData::Unreadable(err.to_string()),
+        };
+        return Ok(Preview {
+            path: path.to_path_buf(),
+            data,
+            mismatch: None,
+            metadata: Some(file_metadata),
+            image: None,
+            link,
+        });
+    }
     if !metadata.is_file() {
         return Ok(Preview {
             path: path.to_path_buf(),
@@ -57,38 +147,124 @@ pub async fn load(path: &Path, config: &Config) -> Result<Preview, PreviewError>
             mismatch: None,
             metadata: Some(file_metadata),
             image: None,
+            link,
         });
     }
 
-    let mut file = File::open(path).await?;
-    let mut buf = vec![0u8; PREVIEW_LIMIT];
-    let read_len = file.read(&mut buf).await?;
-    buf.truncate(read_len);
+    let limit = config
+        .preview
+        .max_bytes
+        .clamp(MIN_PREVIEW_BYTES, MAX_PREVIEW_BYTES);
+    let file = File::open(path).await?;
+    let mut buf = Vec::with_capacity(limit.min(metadata.len() as usize));
+    let read_len = file.take(limit as u64).read_to_end(&mut buf).await?;
 
     let mismatch = if config.check_mismatch {
-        Some(security::check_buffer_mismatch(path, &buf))
+        Some(security::check_buffer_mismatch(
+            path,
+            &buf,
+            &config.security.equivalent_extensions,
+        ))
+    } else {
+        None
+    };
+    if config.security.on_mismatch == MismatchAction::Show {
+        file_metadata.detected = infer::get(&buf).map(|kind| match &mismatch {
+            Some(MismatchStatus::Mismatch { extension, .. }) => {
+                format!("{} (not .{extension})", kind.mime_type())
+            }
+            _ => kind.mime_type().to_string(),
+        });
+    }
+
+    if let Some(kind) = archive::detect(path, &buf) {
+        let archive_path = path.to_path_buf();
+        let listed = tokio::task::spawn_blocking(move || {
+            archive::list(&archive_path, kind, ARCHIVE_PREVIEW_LIMIT)
+        })
+        .await
+        .ok()
+        .and_then(Result::ok);
+        if let Some(entries) = listed {
+            return Ok(Preview {
+                path: path.to_path_buf(),
+                data: PreviewData::Archive(entries),
+                mismatch,
+                metadata: Some(file_metadata),
+                image: None,
+                link,
+            });
+        }
+    }
+
+    if infer::get(&buf).is_some_and(|kind| kind.mime_type().starts_with("audio/")) {
+        let audio_path = path.to_path_buf();
+        let info = tokio::task::spawn_blocking(move || audio::read(&audio_path))
+            .await
+            .ok()
+            .and_then(Result::ok);
+        if let Some(info) = info {
+            return Ok(Preview {
+                path: path.to_path_buf(),
+                data: PreviewData::Text(info.to_text()),
+                mismatch,
+                metadata: Some(file_metadata),
+                image: None,
+                link,
+            });
+        }
+    }
+
+    // Logs grow at the end, so large ones show their most recent lines.
+    let tail = if metadata.len() > limit as u64
+        && config
+            .preview
+            .tail_extensions
+            .iter()
+            .any(|extension| has_extension(path, extension))
+        && utf8_prefix(&buf).is_some()
+    {
+        read_tail(path, limit).await.ok()
     } else {
         None
     };
 
-    let is_image = read_len > 0
-        && infer::get(&buf)
-            .map(|kind| kind.mime_type().starts_with("image/"))
-            .unwrap_or(false);
-    let image = if is_image {
-        decode_image(path.to_path_buf()).await
+    let image_kind = infer::get(&buf).filter(|kind| kind.mime_type().starts_with("image/"));
+    let decoded = if read_len > 0 && image_kind.is_some() {
+        decode_image(path.to_path_buf(), config.preview.max_image_pixels).await
     } else {
         None
     };
-    let data = if let Some(image) = image.as_ref() {
-        PreviewData::Image {
-            width: image.width(),
-            height: image.height(),
-        }
+    let (image, info) = decoded.unzip();
+    let data = if let Some(DecodedImage {
+        width,
+        height,
+        frames,
+    }) = info
+    {
+        file_metadata.image = image_kind.map(|kind| ImageInfo {
+            width,
+            height,
+            format: kind.extension().to_ascii_uppercase(),
+            frames,
+        });
+        PreviewData::Image { width, height }
     } else if read_len == 0 {
         PreviewData::Empty
-    } else if let Ok(text) = std::str::from_utf8(&buf) {
-        PreviewData::Text(text.to_string())
+    } else if let Some(text) = tail {
+        PreviewData::Text(text)
+    } else if let Some(text) = utf8_prefix(&buf)
+        .map(Cow::Borrowed)
+        .or_else(|| decode_non_utf8(&buf).map(Cow::Owned))
+    {
+        if has_extension(path, "csv") {
+            let (headers, rows) = parse_csv(&text, TABLE_PREVIEW_ROWS);
+            PreviewData::Table { headers, rows }
+        } else if has_extension(path, "json") {
+            PreviewData::Text(pretty_json(&text).unwrap_or_else(|| text.into_owned()))
+        } else {
+            PreviewData::Text(text.into_owned())
+        }
     } else {
         PreviewData::Binary {
             size: metadata.len(),
@@ -101,38 +277,463 @@ pub async fn load(path: &Path, config: &Config) -> Result<Preview, PreviewError>
         mismatch,
         metadata: Some(file_metadata),
         image,
+        link,
+    })
+}
+
+/// Decodes `buf` as UTF-8, allowing a character cut off by the read limit at the end.
+fn utf8_prefix(buf: &[u8]) -> Option<&str> {
+    match std::str::from_utf8(buf) {
+        Ok(text) => Some(text),
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&buf[..err.valid_up_to()]).ok()
+        }
+        Err(_) => None,
+    }
+}
+
+/// Decodes text that is not UTF-8: UTF-16 (by its byte order mark, or by the zero bytes
+/// of mostly-ASCII text) or a legacy encoding `chardetng` is confident about. `None` when
+/// the content looks binary.
+fn decode_non_utf8(buf: &[u8]) -> Option<String> {
+    let (encoding, buf) = match Encoding::for_bom(buf) {
+        Some((encoding, bom_length)) => (encoding, &buf[bom_length..]),
+        None => match utf16_without_bom(buf) {
+            Some(encoding) => (encoding, buf),
+            None => {
+                if buf.contains(&0) {
+                    return None;
+                }
+                let mut detector = EncodingDetector::new();
+                detector.feed(buf, true);
+                let (encoding, confident) = detector.guess_assess(None, false);
+                if !confident {
+                    return None;
+                }
+                (encoding, buf)
+            }
+        },
+    };
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut text = String::with_capacity(decoder.max_utf8_buffer_length(buf.len())?);
+    // Not the last chunk: a character cut off by the read limit is not an error.
+    let (_, _, had_errors) = decoder.decode_to_string(buf, &mut text, false);
+    let control = text
+        .chars()
+        .filter(|ch| ch.is_control() && !matches!(ch, '\n' | '\r' | '\t' | '\x0c' | '\x1b'))
+        .count();
+    // More than 2% control characters means the guess turned binary data into noise.
+    if had_errors || control * 50 > text.chars().count() {
+        return None;
+    }
+    Some(text)
+}
+
+/// UTF-16 without a byte order mark, recognised by a zero in nearly every high byte and
+/// almost none in the low ones, as in mostly-ASCII text.
+fn utf16_without_bom(buf: &[u8]) -> Option<&'static Encoding> {
+    let pairs = buf.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let zeros_at = |offset: usize| {
+        buf[offset..]
+            .iter()
+            .step_by(2)
+            .take(pairs)
+            .filter(|&&byte| byte == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    let mostly = |count: usize| count * 10 >= pairs * 7;
+    let few = |count: usize| count * 10 <= pairs;
+    if mostly(odd) && few(even) {
+        Some(UTF_16LE)
+    } else if mostly(even) && few(odd) {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// The last `limit` bytes of a file, starting after the first line break so the cut-off
+/// line is left out.
+async fn read_tail(path: &Path, limit: usize) -> std::io::Result<String> {
+    let mut file = File::open(path).await?;
+    file.seek(SeekFrom::End(-(limit as i64))).await?;
+    let mut buf = Vec::with_capacity(limit);
+    // The file may grow while it is read.
+    file.take(limit as u64).read_to_end(&mut buf).await?;
+    let start = buf
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .map_or(0, |index| index + 1);
+    Ok(String::from_utf8_lossy(&buf[start..]).into_owned())
+}
+
+async fn link_info(path: &Path) -> Option<LinkInfo> {
+    let target = fs::read_link(path).await.ok()?;
+    let (chain, cycle) = link_chain(path).await;
+    Some(match fs::canonicalize(path).await {
+        Ok(resolved) => LinkInfo {
+            target: resolved,
+            broken: false,
+            chain,
+            cycle,
+        },
+        Err(_) => LinkInfo {
+            target,
+            broken: true,
+            chain,
+            cycle,
+        },
     })
 }
 
-async fn decode_image(path: PathBuf) -> Option<DynamicImage> {
+/// Follows the link at `path` one hop at a time, stopping at the first path that is not
+/// a link (or does not exist). The flag is set when the chain loops; the repeated path
+/// ends the chain then.
+async fn link_chain(path: &Path) -> (Vec<PathBuf>, bool) {
+    let mut chain = Vec::new();
+    let mut visited = HashSet::from([path.to_path_buf()]);
+    let mut current = path.to_path_buf();
+    while let Ok(target) = fs::read_link(&current).await {
+        // Relative targets are relative to the link's directory; `.` parts are dropped.
+        let next: PathBuf = match current.parent() {
+            Some(parent) => parent.join(target).components().collect(),
+            None => target,
+        };
+        let repeated = !visited.insert(next.clone());
+        chain.push(next.clone());
+        if repeated || chain.len() > MAX_LINK_HOPS {
+            return (chain, true);
+        }
+        current = next;
+    }
+    (chain, false)
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Re-indents JSON for display. Only the already size-limited prefix is parsed, so a
+/// file cut off by `max_bytes` fails to parse and is shown as-is.
+fn pretty_json(text: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(text).ok()?;
+    serde_json::to_string_pretty(&value).ok()
+}
+
+/// Splits CSV text into a header row and up to `limit` data rows. Quoted fields may
+/// contain commas, doubled quotes and line breaks; rows are left as ragged as they are.
+fn parse_csv(text: &str, limit: usize) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => field.push(ch),
+            }
+            continue;
+        }
+        match ch {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+                if records.len() > limit {
+                    break;
+                }
+            }
+            _ => field.push(ch),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.truncate(limit + 1);
+    let mut records = records.into_iter();
+    let headers = records.next().unwrap_or_default();
+    (headers, records.collect())
+}
+
+/// Lists up to `DIRECTORY_PREVIEW_LIMIT` entries of the directory, hidden ones only with
+/// `show_hidden`.
+async fn read_directory(
+    path: &Path,
+    sort: SortOptions,
+    show_hidden: bool,
+) -> std::io::Result<Vec<FileEntry>> {
+    let mut reader = fs::read_dir(path).await?;
+    let mut entries = Vec::new();
+    while entries.len() < DIRECTORY_PREVIEW_LIMIT {
+        let Some(entry) = reader.next_entry().await? else {
+            break;
+        };
+        if !show_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if let Ok(entry) = FileEntry::from_dir_entry(entry).await {
+            entries.push(entry);
+        }
+    }
+    core::sort_entries(&mut entries, sort);
+    Ok(entries)
+}
+
+/// What decoding found besides the pixels.
+struct DecodedImage {
+    /// Original size, before any scaling.
+    width: u32,
+    height: u32,
+    frames: Option<usize>,
+}
+
+/// Decodes the image (the first frame of an animation) and its original size. Images
+/// above `max_pixels` (unless 0) are scaled down, keeping the aspect ratio, so only the
+/// small copy is kept and later resized for the terminal. JPEGs are decoded at a reduced
+/// size directly; other formats are decoded at full size first.
+async fn decode_image(path: PathBuf, max_pixels: u64) -> Option<(DynamicImage, DecodedImage)> {
+    use std::io::{BufReader, Cursor, Read, Seek};
+
     tokio::task::spawn_blocking(move || {
-        let reader = image::io::Reader::open(path).ok()?;
-        reader.with_guessed_format().ok()?.decode().ok()
+        let mut file = std::fs::File::open(path).ok()?;
+        let mut header = Vec::with_capacity(12);
+        (&mut file).take(12).read_to_end(&mut header).ok()?;
+        file.rewind().ok()?;
+        // Counting GIF and WebP frames walks the block structure, so those are read whole;
+        // a PNG's frame count is in a chunk header, and everything else is decoded straight
+        // from the file.
+        let (image, (width, height), frames) = if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            let (image, original) = decode_jpeg(BufReader::new(file), max_pixels)?;
+            (image, original, None)
+        } else if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+            let frames = apng_frames(&mut file);
+            file.rewind().ok()?;
+            let image = decode(BufReader::new(file))?;
+            let original = (image.width(), image.height());
+            (image, original, frames)
+        } else if may_be_animated(&header) {
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes).ok()?;
+            let image = decode(Cursor::new(&bytes))?;
+            let original = (image.width(), image.height());
+            (image, original, animation_frames(&bytes))
+        } else {
+            let image = decode(BufReader::new(file))?;
+            let original = (image.width(), image.height());
+            (image, original, None)
+        };
+        let info = DecodedImage {
+            width,
+            height,
+            frames: frames.filter(|&frames| frames > 1),
+        };
+        match scaled_size(image.width(), image.height(), max_pixels) {
+            Some((width, height)) => Some((image.thumbnail(width, height), info)),
+            None => Some((image, info)),
+        }
     })
     .await
     .ok()
     .flatten()
 }
 
+/// The size to scale a `width` x `height` image down to so it has about `max_pixels`,
+/// or `None` when it is small enough or `max_pixels` is 0.
+fn scaled_size(width: u32, height: u32, max_pixels: u64) -> Option<(u32, u32)> {
+    let pixels = u64::from(width) * u64::from(height);
+    if max_pixels == 0 || pixels <= max_pixels {
+        return None;
+    }
+    let scale = (max_pixels as f64 / pixels as f64).sqrt();
+    let width = ((f64::from(width) * scale) as u32).max(1);
+    let height = ((f64::from(height) * scale) as u32).max(1);
+    Some((width, height))
+}
+
+fn decode(reader: impl std::io::BufRead + std::io::Seek) -> Option<DynamicImage> {
+    image::io::Reader::new(reader)
+        .with_guessed_format()
+        .ok()?
+        .decode()
+        .ok()
+}
+
+/// Decodes a JPEG at the smallest of its built-in scales (1/8 to 1) that still covers
+/// `max_pixels`, returning it with the original size.
+fn decode_jpeg(reader: impl std::io::Read, max_pixels: u64) -> Option<(DynamicImage, (u32, u32))> {
+    let mut decoder = JpegDecoder::new(reader).ok()?;
+    let (width, height) = decoder.dimensions();
+    if let Some((scaled_width, scaled_height)) = scaled_size(width, height, max_pixels) {
+        // JPEG sizes fit in 16 bits, and the scaled size is smaller still.
+        decoder
+            .scale(scaled_width as u16, scaled_height as u16)
+            .ok()?;
+    }
+    let image = DynamicImage::from_decoder(decoder).ok()?;
+    Some((image, (width, height)))
+}
+
+/// Whether the first 12 bytes of a file start a GIF or WebP, the formats besides PNG
+/// that can hold an animation.
+fn may_be_animated(header: &[u8]) -> bool {
+    header.starts_with(b"GIF8")
+        || (header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WEBP"))
+}
+
+/// Number of frames in a GIF or WebP file, read from its block structure without
+/// decoding; `None` for other formats or damaged files.
+fn animation_frames(data: &[u8]) -> Option<usize> {
+    if data.starts_with(b"GIF8") {
+        gif_frames(data)
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        webp_frames(data)
+    } else {
+        None
+    }
+}
+
+/// Counts the image descriptors, skipping extensions and image data.
+fn gif_frames(data: &[u8]) -> Option<usize> {
+    let color_table_len = |flags: u8| {
+        if flags & 0x80 == 0 {
+            0
+        } else {
+            3 << ((flags & 0x07) + 1)
+        }
+    };
+    let skip_sub_blocks = |mut pos: usize| loop {
+        let len = usize::from(*data.get(pos)?);
+        pos += 1 + len;
+        if len == 0 {
+            return Some(pos);
+        }
+    };
+    // Header and logical screen descriptor, then the global color table.
+    let mut pos = 13 + color_table_len(*data.get(10)?);
+    let mut frames = 0;
+    loop {
+        match *data.get(pos)? {
+            0x21 => pos = skip_sub_blocks(pos + 2)?,
+            0x2C => {
+                frames += 1;
+                // Descriptor, local color table and LZW code size precede the data.
+                pos = skip_sub_blocks(pos + 10 + color_table_len(*data.get(pos + 9)?) + 1)?;
+            }
+            _ => return Some(frames),
+        }
+    }
+}
+
+/// The frame count of a PNG's animation control chunk, which comes before the image
+/// data. Only chunk headers are read; the chunk data in between is skipped.
+fn apng_frames(file: &mut std::fs::File) -> Option<usize> {
+    use std::io::{Read, Seek};
+
+    file.seek(SeekFrom::Start(8)).ok()?;
+    let mut header = [0; 8];
+    loop {
+        file.read_exact(&mut header).ok()?;
+        let len = u32::from_be_bytes(header[..4].try_into().ok()?);
+        match &header[4..] {
+            b"acTL" => {
+                let mut frames = [0; 4];
+                file.read_exact(&mut frames).ok()?;
+                return Some(u32::from_be_bytes(frames) as usize);
+            }
+            b"IDAT" => return None,
+            // The data and its CRC.
+            _ => file.seek(SeekFrom::Current(i64::from(len) + 4)).ok()?,
+        };
+    }
+}
+
+/// Counts the animation frame chunks.
+fn webp_frames(data: &[u8]) -> Option<usize> {
+    let mut pos = 12;
+    let mut frames = 0;
+    while let Some(header) = data.get(pos..pos + 8) {
+        let len = u32::from_le_bytes(header[4..].try_into().ok()?) as usize;
+        if &header[..4] == b"ANMF" {
+            frames += 1;
+        }
+        pos += 8 + len + len % 2;
+    }
+    Some(frames)
+}
+
 fn build_metadata(metadata: &std::fs::Metadata) -> FileMetadata {
     FileMetadata {
         permissions: permissions_string(metadata),
-        owner: owner_string(metadata),
-        created: time_string(metadata.created()),
-        modified: time_string(metadata.modified()),
-        accessed: time_string(metadata.accessed()),
+        owner: core::owner_string(metadata),
+        size: (!metadata.is_dir()).then_some(metadata.len()),
+        created: metadata.created().ok(),
+        modified: metadata.modified().ok(),
+        accessed: metadata.accessed().ok(),
+        image: None,
+        detected: None,
     }
 }
 
-fn time_string(value: std::io::Result<SystemTime>) -> Option<String> {
-    value.ok().and_then(format_time)
+pub fn date_format_is_valid(date_format: &str) -> bool {
+    matches!(date_format, RFC3339_DATE_FORMAT | RELATIVE_DATE_FORMAT)
+        || format_description::parse(date_format).is_ok()
 }
 
-fn format_time(time: SystemTime) -> Option<String> {
+/// Formats with a `time` format description such as `[year]-[month]-[day]`. Falls back
+/// to RFC 3339 when the description does not parse.
+pub fn format_time(time: SystemTime, date_format: &str) -> Option<String> {
+    if date_format == RELATIVE_DATE_FORMAT {
+        return Some(relative_time(time));
+    }
     let timestamp = OffsetDateTime::from(time);
+    if date_format != RFC3339_DATE_FORMAT {
+        if let Ok(format) = format_description::parse(date_format) {
+            if let Ok(formatted) = timestamp.format(&format) {
+                return Some(formatted);
+            }
+        }
+    }
     timestamp.format(&Rfc3339).ok()
 }
 
+fn relative_time(time: SystemTime) -> String {
+    const UNITS: [(u64, &str); 6] = [
+        (365 * 24 * 60 * 60, "year"),
+        (30 * 24 * 60 * 60, "month"),
+        (7 * 24 * 60 * 60, "week"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ];
+    let (seconds, suffix) = match SystemTime::now().duration_since(time) {
+        Ok(elapsed) => (elapsed.as_secs(), "ago"),
+        Err(err) => (err.duration().as_secs(), "from now"),
+    };
+    for (unit_seconds, unit) in UNITS {
+        let count = seconds / unit_seconds;
+        if count > 0 {
+            let plural = if count == 1 { "" } else { "s" };
+            return format!("{count} {unit}{plural} {suffix}");
+        }
+    }
+    "just now".to_string()
+}
+
 #[cfg(unix)]
 fn permissions_string(metadata: &std::fs::Metadata) -> String {
     let mode = metadata.permissions().mode();
@@ -172,13 +773,3 @@ fn permissions_string(metadata: &std::fs::Metadata) -> String {
         "rw-rw-rw-".to_string()
     }
 }
-
-#[cfg(unix)]
-fn owner_string(metadata: &std::fs::Metadata) -> String {
-    format!("{}:{}", metadata.uid(), metadata.gid())
-}
-
-#[cfg(not(unix))]
-fn owner_string(_: &std::fs::Metadata) -> String {
-    "-".to_string()
-}
@@ -0,0 +1,102 @@
+use crate::preview::{Preview, PreviewData};
+use crate::ui::HighlightedText;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Most previews kept at once.
+const CACHE_ENTRIES: usize = 32;
+/// Rough upper bound on the memory held by cached previews.
+const CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+/// A file as it was when previewed; a new modification time or size misses the cache.
+pub type CacheKey = (PathBuf, SystemTime, u64);
+
+#[derive(Debug)]
+struct CachedPreview {
+    key: CacheKey,
+    preview: Preview,
+    highlighted: Option<HighlightedText>,
+    bytes: usize,
+}
+
+/// Recently loaded file previews, most recently used first.
+#[derive(Debug, Default)]
+pub struct PreviewCache {
+    entries: VecDeque<CachedPreview>,
+    bytes: usize,
+}
+
+impl PreviewCache {
+    /// A copy of the cached preview for `key`, which becomes the most recently used.
+    pub fn get(&mut self, key: &CacheKey) -> Option<(Preview, Option<HighlightedText>)> {
+        let position = self.entries.iter().position(|entry| &entry.key == key)?;
+        let entry = self.entries.remove(position)?;
+        let hit = (entry.preview.clone(), entry.highlighted.clone());
+        self.entries.push_front(entry);
+        Some(hit)
+    }
+
+    pub fn insert(
+        &mut self,
+        key: CacheKey,
+        preview: Preview,
+        highlighted: Option<HighlightedText>,
+    ) {
+        let bytes = approximate_size(&preview, highlighted.as_ref());
+        if bytes > CACHE_BYTES {
+            return;
+        }
+        if let Some(position) = self.entries.iter().position(|entry| entry.key == key) {
+            if let Some(old) = self.entries.remove(position) {
+                self.bytes -= old.bytes;
+            }
+        }
+        self.bytes += bytes;
+        self.entries.push_front(CachedPreview {
+            key,
+            preview,
+            highlighted,
+            bytes,
+        });
+        while self.entries.len() > CACHE_ENTRIES || self.bytes > CACHE_BYTES {
+            let Some(old) = self.entries.pop_back() else {
+                break;
+            };
+            self.bytes -= old.bytes;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+}
+
+/// Estimates the heap memory of a preview; exact enough to bound the cache.
+fn approximate_size(preview: &Preview, highlighted: Option<&HighlightedText>) -> usize {
+    let data = match &preview.data {
+        PreviewData::Text(text) | PreviewData::Unreadable(text) => text.len(),
+        PreviewData::Directory(entries) => entries.len() * 256,
+        PreviewData::Archive(entries) => entries.iter().map(|entry| entry.path.len() + 64).sum(),
+        PreviewData::Table { headers, rows } => headers
+            .iter()
+            .chain(rows.iter().flatten())
+            .map(|cell| cell.len() + 24)
+            .sum(),
+        PreviewData::Image { .. } | PreviewData::Binary { .. } | PreviewData::Empty => 0,
+    };
+    let image = preview
+        .image
+        .as_ref()
+        .map_or(0, |image| image.as_bytes().len());
+    // Styled spans take several times the size of the plain text.
+    let highlighted = highlighted.map_or(0, |text| {
+        text.lines
+            .iter()
+            .flat_map(|line| &line.spans)
+            .map(|span| span.content.len() + 48)
+            .sum()
+    });
+    data + image + highlighted
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Scanning stops after this many programs so a huge `PATH` cannot stall the list.
+const PROGRAM_LIMIT: usize = 20_000;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProgramEntry {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// The programs on `PATH`, saved between launches together with what they were scanned
+/// from: the scan is stale once `PATH` or the modification time of one of its
+/// directories changes.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ProgramScan {
+    path_var: String,
+    dirs: Vec<(PathBuf, Option<SystemTime>)>,
+    pub programs: Vec<ProgramEntry>,
+}
+
+impl ProgramScan {
+    /// Walks every `PATH` directory; like a shell, the first program with a name wins.
+    pub fn scan() -> Self {
+        let path_var = path_var();
+        let dirs = dir_times(&path_var);
+        let mut programs = Vec::new();
+        let mut seen = HashSet::new();
+        'dirs: for (dir, _) in &dirs {
+            let Ok(read_dir) = fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in read_dir.flatten() {
+                let path = entry.path();
+                if !is_executable(&path) {
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy().to_string();
+                if seen.insert(name.clone()) {
+                    programs.push(ProgramEntry { name, path });
+                    if programs.len() >= PROGRAM_LIMIT {
+                        break 'dirs;
+                    }
+                }
+            }
+        }
+        programs.sort_by(|a, b| {
+            a.name
+                .to_ascii_lowercase()
+                .cmp(&b.name.to_ascii_lowercase())
+        });
+        Self {
+            path_var,
+            dirs,
+            programs,
+        }
+    }
+
+    /// The saved scan, if `PATH` and its directories are unchanged since it was made.
+    pub fn load_fresh() -> Option<Self> {
+        let content = fs::read_to_string(cache_path()).ok()?;
+        let scan: Self = serde_json::from_str(&content).ok()?;
+        let path_var = path_var();
+        (scan.path_var == path_var && scan.dirs == dir_times(&path_var)).then_some(scan)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = cache_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        fs::write(path, content)
+    }
+}
+
+fn path_var() -> String {
+    env::var_os("PATH")
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn dir_times(path_var: &str) -> Vec<(PathBuf, Option<SystemTime>)> {
+    env::split_paths(path_var)
+        .map(|dir| {
+            let modified = fs::metadata(&dir)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            (dir, modified)
+        })
+        .collect()
+}
+
+fn cache_path() -> PathBuf {
+    if let Some(dir) = dirs::cache_dir() {
+        return dir.join("tfm").join("programs.json");
+    }
+    if let Some(home) = dirs::home_dir() {
+        return home.join(".tfm.programs.json");
+    }
+    PathBuf::from("programs.json")
+}
+
+pub fn is_executable(path: &Path) -> bool {
+    let Ok(metadata) = fs::metadata(path) else {
+        return false;
+    };
+    if !metadata.is_file() {
+        return false;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(windows)]
+    {
+        let ext = path
+            .extension()
+            .and_then(|value| value.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        return matches!(ext.as_str(), "exe" | "cmd" | "bat" | "com");
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        true
+    }
+}
@@ -1,5 +1,10 @@
+use std::fs::File;
+use std::io::Read;
 use std::path::Path;
 
+/// Bytes read from the start of a file when checking it outside the preview.
+const CHECK_BYTES: u64 = 8192;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DetectedType {
     pub extension: String,
@@ -16,11 +21,92 @@ pub enum MismatchStatus {
     Unknown,
 }
 
-fn extensions_match(extension: &str, detected: &str) -> bool {
-    normalize_extension(extension) == normalize_extension(detected)
+/// Extensions that stand for the same kind of content, grouped by media family. `infer`
+/// reports a single extension per format, so any member of a group matches any other.
+const EQUIVALENT_EXTENSIONS: &[&[&str]] = &[
+    // Images
+    &["jpg", "jpeg", "jpe", "jfif", "pjpeg", "pjp"],
+    &["tif", "tiff"],
+    &["heif", "heic", "heics", "heifs"],
+    &["ico", "cur"],
+    &["psd", "psb"],
+    // Audio and video containers
+    &[
+        "mp4", "m4v", "m4a", "m4b", "m4p", "m4r", "aac", "mov", "qt", "3gp", "3g2",
+    ],
+    &["mkv", "mka", "mks", "mk3d", "webm"],
+    &["ogg", "oga", "ogv", "ogm", "ogx", "opus", "spx"],
+    &["mpg", "mpeg", "mpe", "m1v", "m2v", "vob"],
+    &["wmv", "wma", "asf"],
+    &["mp3", "mpga", "mp2"],
+    &["wav", "wave"],
+    &["aiff", "aif", "aifc"],
+    &["midi", "mid", "kar", "rmi"],
+    // Fonts
+    &["ttf", "otf", "ttc"],
+    // Documents and packages that are zip files inside
+    &[
+        "zip", "jar", "war", "ear", "apk", "aab", "ipa", "xpi", "whl", "nupkg", "vsix", "docx",
+        "docm", "dotx", "xlsx", "xlsm", "xltx", "pptx", "pptm", "potx", "odt", "ods", "odp", "odg",
+        "ott", "epub", "kmz", "3mf", "ora",
+    ],
+    // OLE2 compound files
+    &["doc", "dot", "xls", "xlt", "ppt", "pot", "msi", "msg"],
+    // Compressed archives
+    &["gz", "gzip", "tgz"],
+    &["bz2", "bzip2", "tbz", "tbz2"],
+    &["xz", "txz"],
+    &["zst", "tzst"],
+    &["z", "taz"],
+    &["lz", "tlz"],
+    &["ar", "a", "deb", "udeb"],
+    // Executables, libraries and certificates
+    &[
+        "exe", "dll", "sys", "efi", "scr", "ocx", "cpl", "drv", "mui",
+    ],
+    &["elf", "obj", "so", "o", "ko", "out", "axf"],
+    &["mach", "dylib", "bundle"],
+    &["der", "pem", "crt", "cer"],
+    &["sqlite", "sqlite3", "db", "db3"],
+    &["ps", "eps"],
+    // Spellings of the same text format
+    &["yml", "yaml"],
+    // Text formats recognised by their first bytes
+    &["html", "htm", "xhtml", "shtml"],
+    &[
+        "xml", "xhtml", "svg", "xsd", "xsl", "xslt", "rss", "atom", "plist", "kml", "gpx", "xaml",
+    ],
+    // `infer` reports any file starting with `#!` as a shell script.
+    &[
+        "sh", "bash", "zsh", "ksh", "fish", "command", "py", "pl", "rb", "php", "lua", "tcl",
+        "awk", "js", "mjs",
+    ],
+];
+
+/// Whether `extension` and the `detected` one name the same content, using the built-in
+/// groups plus user-configured `extra` groups.
+fn extensions_match(extension: &str, detected: &str, extra: &[Vec<String>]) -> bool {
+    let detected = detected.to_ascii_lowercase();
+    if extension == detected {
+        return true;
+    }
+    let builtin = EQUIVALENT_EXTENSIONS
+        .iter()
+        .any(|group| group.contains(&extension) && group.contains(&detected.as_str()));
+    builtin
+        || extra.iter().any(|group| {
+            group.iter().any(|ext| ext.eq_ignore_ascii_case(extension))
+                && group.iter().any(|ext| ext.eq_ignore_ascii_case(&detected))
+        })
 }
 
-pub fn check_buffer_mismatch(path: &Path, buf: &[u8]) -> MismatchStatus {
+/// Compares the content type `infer` finds in `buf` with the extension of `path`.
+/// `equivalents` are extra groups of interchangeable extensions from the config.
+pub fn check_buffer_mismatch(
+    path: &Path,
+    buf: &[u8],
+    equivalents: &[Vec<String>],
+) -> MismatchStatus {
     if buf.is_empty() {
         return MismatchStatus::Unknown;
     }
@@ -38,7 +124,7 @@ pub fn check_buffer_mismatch(path: &Path, buf: &[u8]) -> MismatchStatus {
         _ => return MismatchStatus::Unknown,
     };
 
-    if extensions_match(&extension, &detected.extension) {
+    if extensions_match(&extension, &detected.extension, equivalents) {
         MismatchStatus::Match
     } else {
         MismatchStatus::Mismatch {
@@ -48,13 +134,58 @@ pub fn check_buffer_mismatch(path: &Path, buf: &[u8]) -> MismatchStatus {
     }
 }
 
-fn normalize_extension(extension: &str) -> &str {
-    match extension {
-        "jpeg" | "jpe" => "jpg",
-        "tiff" => "tif",
-        "htm" => "html",
-        "yml" => "yaml",
-        "oga" | "ogv" | "ogm" => "ogg",
-        _ => extension,
+/// Like [`check_buffer_mismatch`], reading the start of the file at `path`. Only regular
+/// files are read; opening a FIFO or a device node could block.
+pub fn check_path_mismatch(
+    path: &Path,
+    equivalents: &[Vec<String>],
+) -> std::io::Result<MismatchStatus> {
+    if !std::fs::metadata(path)?.is_file() {
+        return Ok(MismatchStatus::Unknown);
+    }
+    let mut buf = Vec::new();
+    File::open(path)?.take(CHECK_BYTES).read_to_end(&mut buf)?;
+    Ok(check_buffer_mismatch(path, &buf, equivalents))
+}
+
+/// The interpreter line of a script starting with `#!`, if `path` is one. Only regular
+/// files are read.
+pub fn read_shebang(path: &Path) -> Option<String> {
+    if !std::fs::metadata(path).ok()?.is_file() {
+        return None;
+    }
+    let mut buf = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(256)
+        .read_to_end(&mut buf)
+        .ok()?;
+    let line = buf
+        .strip_prefix(b"#!")?
+        .split(|&byte| byte == b'\n')
+        .next()?;
+    Some(String::from_utf8_lossy(line).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extensions_match;
+
+    #[test]
+    fn builtin_groups_match() {
+        assert!(extensions_match("jpg", "jpeg", &[]));
+        assert!(extensions_match("jpeg", "JPG", &[]));
+        assert!(extensions_match("yml", "yaml", &[]));
+        assert!(extensions_match("webm", "mkv", &[]));
+        assert!(extensions_match("m4a", "mp4", &[]));
+        assert!(!extensions_match("png", "jpg", &[]));
+    }
+
+    #[test]
+    fn configured_groups_match() {
+        let extra = vec![vec!["cbz".to_string(), "ZIP".to_string()]];
+        assert!(extensions_match("cbz", "zip", &extra));
+        assert!(!extensions_match("cbz", "zip", &[]));
+        assert!(!extensions_match("cbr", "zip", &extra));
     }
 }
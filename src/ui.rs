@@ -1,19 +1,31 @@
+use crate::archive::{ArchiveEntry, ARCHIVE_PREVIEW_LIMIT};
 use crate::config::Config;
-use crate::core::FileEntry;
-use crate::preview::{FileMetadata, Preview, PreviewData};
+use crate::core::{ChildCount, FileEntry, SortMode};
+use crate::fuzzy;
+use crate::markdown;
+use crate::preview::{
+    format_time, FileMetadata, LinkInfo, Preview, PreviewData, DIRECTORY_PREVIEW_LIMIT,
+    RELATIVE_DATE_FORMAT, RFC3339_DATE_FORMAT, TABLE_PREVIEW_ROWS,
+};
 use crate::security::MismatchStatus;
 use ratatui::buffer::Buffer;
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Margin, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
-use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, StatefulWidget};
+use ratatui::widgets::{
+    Block, Borders, Clear, List, ListItem, ListState, Paragraph, StatefulWidget, Wrap,
+};
 use ratatui::Frame;
 use ratatui_image::{protocol::StatefulProtocol, Resize};
+use regex::Regex;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
 use std::sync::OnceLock;
 use syntect::easy::HighlightLines;
 use syntect::highlighting::{FontStyle, Style as SyntectStyle, Theme, ThemeSet};
-use syntect::parsing::SyntaxSet;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
 use syntect::util::LinesWithEndings;
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
@@ -82,11 +94,50 @@ impl StatefulWidget for ThreadImage {
 pub struct InputPrompt {
     pub title: String,
     pub value: String,
+    /// Byte offset of the caret in `value`; `None` for prompts that only take a key.
+    pub cursor: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatusKind {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug)]
+pub struct StatusMessage {
+    pub kind: StatusKind,
+    pub text: String,
+}
+
+impl StatusMessage {
+    pub fn info(text: impl Into<String>) -> Self {
+        Self {
+            kind: StatusKind::Info,
+            text: text.into(),
+        }
+    }
+
+    pub fn warning(text: impl Into<String>) -> Self {
+        Self {
+            kind: StatusKind::Warning,
+            text: text.into(),
+        }
+    }
+
+    pub fn error(text: impl Into<String>) -> Self {
+        Self {
+            kind: StatusKind::Error,
+            text: text.into(),
+        }
+    }
 }
 
 pub struct MarkerListItem {
     pub name: String,
     pub path: String,
+    pub is_file: bool,
 }
 
 pub struct MarkerPopup {
@@ -96,7 +147,8 @@ pub struct MarkerPopup {
 
 pub struct ProgramListItem {
     pub name: String,
-    pub path: String,
+    /// Directory on `PATH` the program was found in.
+    pub dir: String,
 }
 
 pub struct ProgramPopup {
@@ -105,6 +157,82 @@ pub struct ProgramPopup {
     pub filter: String,
 }
 
+pub struct OtherPane<'a> {
+    pub dir: String,
+    pub entries: &'a [FileEntry],
+    pub selected: Option<usize>,
+    pub loading: bool,
+}
+
+pub struct PaletteItem {
+    pub name: String,
+    /// Key sequence that runs the command.
+    pub keys: String,
+}
+
+pub struct PalettePopup {
+    pub items: Vec<PaletteItem>,
+    pub selected: usize,
+    pub filter: String,
+}
+
+/// Where the current list was last drawn, for mapping mouse clicks to entries.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ListGeometry {
+    /// The rows inside the panel border.
+    pub area: Rect,
+    /// Index of the entry in the top row.
+    pub offset: usize,
+}
+
+impl ListGeometry {
+    /// The list position (into the filtered entries) of the row at a screen cell.
+    pub fn index_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.area;
+        let inside = column >= area.x
+            && column < area.x + area.width
+            && row >= area.y
+            && row < area.y + area.height;
+        inside.then(|| self.offset + usize::from(row - area.y))
+    }
+}
+
+/// How the current list's filter matches names, to highlight what it matched.
+#[derive(Clone, Debug)]
+pub enum FilterHighlight {
+    Regex(Regex),
+    /// Lowercased query without whitespace.
+    Fuzzy(Vec<char>),
+}
+
+impl FilterHighlight {
+    /// Byte ranges of `name` the filter matched.
+    fn ranges(&self, name: &str) -> Vec<Range<usize>> {
+        match self {
+            Self::Regex(regex) => regex
+                .find(name)
+                .filter(|found| !found.is_empty())
+                .map(|found| found.range())
+                .into_iter()
+                .collect(),
+            Self::Fuzzy(query) => fuzzy::matched_ranges(query, name),
+        }
+    }
+}
+
+/// Preview scroll position. `viewport` is the visible height, updated on every render.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PreviewScroll {
+    pub offset: u16,
+    pub viewport: u16,
+}
+
+pub struct ListPopup {
+    pub title: String,
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+
 pub type HighlightedText = Text<'static>;
 
 pub struct UiState<'a> {
@@ -113,53 +241,107 @@ pub struct UiState<'a> {
     pub current: &'a [FileEntry],
     pub current_indices: &'a [usize],
     pub selected: usize,
+    pub marked: &'a HashSet<PathBuf>,
+    /// Active filter of the current list (regex or fuzzy), empty when unfiltered.
+    pub filter: &'a str,
+    pub filter_highlight: Option<&'a FilterHighlight>,
+    pub sticky_filter: bool,
+    pub show_hidden: bool,
+    pub hidden_last: bool,
+    /// The current list holds the files below the directory rather than its entries.
+    pub flatten: bool,
+    /// The directory listings still streaming in.
+    pub current_loading: bool,
+    pub parent_loading: bool,
+    /// The inactive pane in dual-pane mode, drawn in place of the preview.
+    pub other_pane: Option<OtherPane<'a>>,
+    /// Summary of the internal clipboard, e.g. `2 cut`.
+    pub clipboard: Option<String>,
+    pub sort_mode: SortMode,
+    pub sort_reverse: bool,
     pub preview: Option<&'a Preview>,
     pub highlighted_preview: Option<&'a HighlightedText>,
+    pub preview_scroll: &'a mut PreviewScroll,
+    /// Set to the number of visible rows in the current list.
+    pub list_viewport: &'a mut u16,
+    /// Set to the current list's area and scroll offset.
+    pub list_geometry: &'a mut ListGeometry,
+    pub preview_focused: bool,
+    pub preview_wrap: bool,
     pub show_metadata: bool,
     pub show_permissions: bool,
     pub show_dates: bool,
+    pub relative_dates: bool,
     pub show_owner: bool,
+    pub show_size: bool,
     pub show_list_permissions: bool,
     pub show_list_owner: bool,
+    pub show_list_size: bool,
     pub metadata: Option<&'a FileMetadata>,
+    /// Recursive size of the selected directory, or a spinner while it is computed.
+    pub dir_size: Option<String>,
     pub image_state: Option<&'a mut ThreadProtocol>,
+    pub status: Option<&'a StatusMessage>,
+    /// Free bytes on the current directory's filesystem, if it reports them.
+    pub free_space: Option<u64>,
+    /// Progress of a background operation, shown when there is no status message.
+    pub activity: Option<String>,
     pub input: Option<InputPrompt>,
     pub marker_popup: Option<MarkerPopup>,
     pub program_popup: Option<ProgramPopup>,
+    pub palette_popup: Option<PalettePopup>,
+    pub list_popup: Option<ListPopup>,
 }
 
 pub fn render(frame: &mut Frame, mut state: UiState<'_>) {
     let theme = &state.config.theme;
+    let monochrome = theme.is_monochrome();
+    let color = |value: &str| {
+        if monochrome {
+            Color::Reset
+        } else {
+            parse_color(value)
+        }
+    };
     let base_style = Style::default()
-        .fg(parse_color(&theme.foreground))
-        .bg(parse_color(&theme.background));
-    let accent_style = Style::default().fg(parse_color(&theme.accent));
-    let folder_style = Style::default().fg(parse_color(&theme.folder));
-    let selection_style = Style::default()
-        .fg(parse_color(&theme.selection_fg))
-        .bg(parse_color(&theme.selection_bg))
-        .add_modifier(Modifier::BOLD);
-    let warning_style = Style::default().fg(parse_color(&theme.warning));
-
-    let layout = if state.show_metadata {
-        Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(1), Constraint::Length(3)])
-            .split(frame.area())
+        .fg(color(&theme.foreground))
+        .bg(color(&theme.background));
+    let accent_style = Style::default().fg(color(&theme.accent));
+    let folder_style = Style::default().fg(color(&theme.folder));
+    let warning_style = Style::default().fg(color(&theme.warning));
+    let list_styles = ListStyles {
+        folder: folder_style,
+        marked: Style::default()
+            .fg(color(&theme.marked))
+            .add_modifier(Modifier::BOLD),
+        broken_link: warning_style,
+        matched: accent_style.add_modifier(Modifier::BOLD),
+    };
+    let selection_style = if monochrome {
+        Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD)
     } else {
-        Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(1)])
-            .split(frame.area())
+        Style::default()
+            .fg(color(&theme.selection_fg))
+            .bg(color(&theme.selection_bg))
+            .add_modifier(Modifier::BOLD)
     };
+    let error_style = Style::default().fg(color(&theme.error));
+
+    let mut rows = vec![Constraint::Min(1)];
+    if state.show_metadata {
+        rows.push(Constraint::Length(3));
+    }
+    rows.push(Constraint::Length(1));
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(rows)
+        .split(frame.area());
 
+    let ratios = state.config.layout.ratios();
+    let total = ratios.iter().map(|&ratio| u32::from(ratio)).sum();
     let areas = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(25),
-            Constraint::Percentage(35),
-            Constraint::Percentage(40),
-        ])
+        .constraints(ratios.map(|ratio| Constraint::Ratio(u32::from(ratio), total)))
         .split(layout[0]);
 
     let parent_inner_width = areas[0].width.saturating_sub(2);
@@ -167,10 +349,9 @@ pub fn render(frame: &mut Frame, mut state: UiState<'_>) {
         state.config,
         state.parent,
         None,
-        false,
-        false,
+        ListColumns::default(),
         parent_inner_width,
-        folder_style,
+        list_styles,
     );
     let parent_list = List::new(parent_items).block(
         Block::default()
@@ -180,7 +361,12 @@ pub fn render(frame: &mut Frame, mut state: UiState<'_>) {
             .border_style(accent_style)
             .title_style(accent_style),
     );
-    frame.render_widget(parent_list, areas[0]);
+    if !areas[0].is_empty() {
+        frame.render_widget(parent_list, areas[0]);
+        if state.parent.is_empty() && state.parent_loading {
+            render_placeholder(frame, areas[0], "Loading…", accent_style);
+        }
+    }
 
     let current_inner_width = areas[1].width.saturating_sub(2);
     let highlight_symbol = "> ";
@@ -189,21 +375,49 @@ pub fn render(frame: &mut Frame, mut state: UiState<'_>) {
     let current_items = list_items(
         state.config,
         state.current,
-        Some(state.current_indices),
-        state.show_list_permissions,
-        state.show_list_owner,
+        Some(ListView {
+            indices: state.current_indices,
+            marked: state.marked,
+            highlight: state.filter_highlight,
+        }),
+        ListColumns {
+            permissions: state.show_list_permissions,
+            owner: state.show_list_owner,
+            size: state.show_list_size,
+        },
         current_content_width,
-        folder_style,
+        list_styles,
     );
-    let current_list = List::new(current_items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Current")
-                .style(base_style)
-                .border_style(accent_style)
-                .title_style(accent_style),
+    let sort_label = if state.sort_reverse {
+        format!("{}, reversed", state.sort_mode.label())
+    } else {
+        state.sort_mode.label().to_string()
+    };
+    let current_name = if state.flatten {
+        "Current (flat)"
+    } else {
+        "Current"
+    };
+    let current_title = if state.marked.is_empty() {
+        format!("{current_name} [{sort_label}]")
+    } else {
+        format!(
+            "{current_name} [{sort_label}] {} marked",
+            state.marked.len()
         )
+    };
+    let mut current_block = Block::default()
+        .borders(Borders::ALL)
+        .title(current_title)
+        .style(base_style)
+        .border_style(accent_style)
+        .title_style(accent_style);
+    if let Some(free) = state.free_space {
+        current_block = current_block
+            .title_bottom(Line::from(format!("{} free", human_size(free))).right_aligned());
+    }
+    let current_list = List::new(current_items)
+        .block(current_block)
         .highlight_style(selection_style)
         .highlight_symbol(highlight_symbol);
 
@@ -213,75 +427,195 @@ pub fn render(frame: &mut Frame, mut state: UiState<'_>) {
         list_state.select(Some(selected));
     }
     frame.render_stateful_widget(current_list, areas[1], &mut list_state);
-
-    let (preview_title, has_mismatch) = match state.preview {
-        Some(preview) => preview_title(preview),
-        None => ("Preview".to_string(), false),
+    if state.current.is_empty() {
+        let placeholder = if state.current_loading {
+            "Loading…"
+        } else {
+            "Empty directory"
+        };
+        render_placeholder(frame, areas[1], placeholder, accent_style);
+    }
+    *state.list_viewport = areas[1].height.saturating_sub(2);
+    *state.list_geometry = ListGeometry {
+        area: areas[1].inner(Margin::new(1, 1)),
+        offset: list_state.offset(),
     };
-    let title_style = if has_mismatch {
-        warning_style
+
+    if let Some(other) = &state.other_pane {
+        let other_content_width = areas[2]
+            .width
+            .saturating_sub(2)
+            .saturating_sub(highlight_width);
+        let other_items = list_items(
+            state.config,
+            other.entries,
+            None,
+            ListColumns {
+                permissions: state.show_list_permissions,
+                owner: state.show_list_owner,
+                size: state.show_list_size,
+            },
+            other_content_width,
+            list_styles,
+        );
+        let other_list = List::new(other_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(other.dir.clone())
+                    .style(base_style)
+                    .border_style(accent_style)
+                    .title_style(accent_style),
+            )
+            // Underlined rather than highlighted, to tell the inactive pane apart.
+            .highlight_style(Style::default().add_modifier(Modifier::UNDERLINED))
+            .highlight_symbol(highlight_symbol);
+        let mut other_state = ListState::default();
+        other_state.select(other.selected);
+        frame.render_stateful_widget(other_list, areas[2], &mut other_state);
+        if other.entries.is_empty() {
+            let placeholder = if other.loading {
+                "Loading…"
+            } else {
+                "Empty directory"
+            };
+            render_placeholder(frame, areas[2], placeholder, accent_style);
+        }
     } else {
-        accent_style
-    };
-    let preview_block = Block::default()
-        .borders(Borders::ALL)
-        .title(preview_title)
-        .style(base_style)
-        .border_style(accent_style)
-        .title_style(title_style);
-    let preview_area = preview_block.inner(areas[2]);
-    let mut rendered_image = false;
-    if let (Some(preview), Some(image_state)) = (state.preview, state.image_state.as_deref_mut()) {
-        if matches!(preview.data, PreviewData::Image { .. }) {
-            let image = ThreadImage::new().resize(Resize::Fit);
-            frame.render_stateful_widget(image, preview_area, image_state);
-            rendered_image = true;
+        let (preview_title, has_mismatch) = match state.preview {
+            Some(preview) => preview_title(preview),
+            None => ("Preview".to_string(), false),
+        };
+        let title_style = if has_mismatch {
+            warning_style
+        } else {
+            accent_style
+        };
+        let preview_border_style = if state.preview_focused {
+            Style::default().fg(color(&theme.selection_bg))
+        } else {
+            accent_style
+        };
+        let preview_block = Block::default()
+            .borders(Borders::ALL)
+            .title(preview_title)
+            .style(base_style)
+            .border_style(preview_border_style)
+            .title_style(title_style);
+        let preview_area = preview_block.inner(areas[2]);
+        let mut rendered_image = false;
+        if let (Some(preview), Some(image_state)) =
+            (state.preview, state.image_state.as_deref_mut())
+        {
+            if matches!(preview.data, PreviewData::Image { .. }) {
+                let image = ThreadImage::new().resize(Resize::Fit);
+                frame.render_stateful_widget(image, preview_area, image_state);
+                rendered_image = true;
+            }
         }
-    }
-    if !rendered_image {
-        let preview_widget = match (state.preview, state.highlighted_preview) {
-            (Some(_), Some(highlighted)) => Paragraph::new(highlighted.clone())
-                .block(preview_block)
-                .style(base_style),
-            (Some(preview), None) => Paragraph::new(preview_text(preview))
-                .block(preview_block)
-                .style(base_style),
-            (None, _) => Paragraph::new(String::new())
+        if !rendered_image {
+            let mut text = match (state.preview, state.highlighted_preview) {
+                (Some(_), Some(highlighted)) => highlighted.clone(),
+                (Some(preview), None) => {
+                    preview_text(preview, state.config, folder_style, preview_area.width)
+                }
+                (None, _) => Text::default(),
+            };
+            if let Some(link) = state.preview.and_then(|preview| preview.link.as_ref()) {
+                let lines = link_chain_lines(link, accent_style, warning_style);
+                text.lines.splice(0..0, lines);
+            }
+            let mut preview_widget = Paragraph::new(text).style(base_style);
+            if state.preview_wrap {
+                preview_widget = preview_widget.wrap(Wrap { trim: false });
+            }
+            // Counted before the block is attached so borders are not included.
+            let line_count =
+                u16::try_from(preview_widget.line_count(preview_area.width)).unwrap_or(u16::MAX);
+            let scroll = &mut *state.preview_scroll;
+            scroll.viewport = preview_area.height;
+            scroll.offset = scroll
+                .offset
+                .min(line_count.saturating_sub(preview_area.height));
+            let preview_widget = preview_widget
                 .block(preview_block)
-                .style(base_style),
-        };
-        frame.render_widget(preview_widget, areas[2]);
-    } else {
-        frame.render_widget(preview_block, areas[2]);
+                .scroll((scroll.offset, 0));
+            frame.render_widget(preview_widget, areas[2]);
+        } else {
+            frame.render_widget(preview_block, areas[2]);
+        }
     }
 
     if state.show_metadata && layout.len() > 1 {
-        let metadata = Paragraph::new(metadata_text(
+        let mut text = metadata_text(
             state.config,
             state.metadata,
             state.show_permissions,
             state.show_dates,
+            state.relative_dates,
             state.show_owner,
-        ))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Meta")
-                .style(base_style)
-                .border_style(accent_style)
-                .title_style(accent_style),
-        )
-        .style(base_style);
+            state.show_size,
+        );
+        if let Some(dir_size) = &state.dir_size {
+            if !text.is_empty() {
+                text.push_str("  ");
+            }
+            let icon = &state.config.metadata_bar.icons.size;
+            text.push_str(&format!("{icon} total {dir_size}"));
+        }
+        let metadata = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Meta")
+                    .style(base_style)
+                    .border_style(accent_style)
+                    .title_style(accent_style),
+            )
+            .style(base_style);
         frame.render_widget(metadata, layout[1]);
     }
 
+    let summary = list_summary(&state);
+    let status_areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(summary.width() as u16 + 1),
+        ])
+        .split(layout[layout.len() - 1]);
+    frame.render_widget(
+        Paragraph::new(summary)
+            .alignment(Alignment::Right)
+            .style(base_style.patch(accent_style)),
+        status_areas[1],
+    );
+    let message = if let Some(status) = state.status {
+        let style = match status.kind {
+            StatusKind::Info => base_style,
+            StatusKind::Warning => base_style.patch(warning_style),
+            StatusKind::Error => base_style.patch(error_style),
+        };
+        Paragraph::new(status.text.clone()).style(style)
+    } else {
+        Paragraph::new(state.activity.clone().unwrap_or_default()).style(base_style)
+    };
+    frame.render_widget(message, status_areas[0]);
+
     if let Some(marker_popup) = state.marker_popup {
         let overlay_area = marker_rect(frame.area());
         frame.render_widget(Clear, overlay_area);
         let items: Vec<ListItem<'static>> = marker_popup
             .items
             .iter()
-            .map(|item| ListItem::new(format!("{}  {}", item.name, item.path)))
+            .map(|item| {
+                let icon = if item.is_file {
+                    &state.config.icons.file
+                } else {
+                    &state.config.icons.folder
+                };
+                ListItem::new(format!("{icon} {}  {}", item.name, item.path))
+            })
             .collect();
         let list = List::new(items)
             .block(
@@ -302,12 +636,61 @@ pub fn render(frame: &mut Frame, mut state: UiState<'_>) {
         frame.render_stateful_widget(list, overlay_area, &mut list_state);
     }
 
-    if let Some(program_popup) = state.program_popup {
+    if let Some(list_popup) = state.list_popup {
+        let overlay_area = marker_rect(frame.area());
+        frame.render_widget(Clear, overlay_area);
+        let items: Vec<ListItem<'static>> =
+            list_popup.items.into_iter().map(ListItem::new).collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(list_popup.title)
+                    .style(base_style)
+                    .border_style(accent_style)
+                    .title_style(accent_style),
+            )
+            .highlight_style(selection_style)
+            .highlight_symbol("> ");
+        let mut list_state = ListState::default();
+        if !list.is_empty() {
+            let selected = list_popup.selected.min(list.len() - 1);
+            list_state.select(Some(selected));
+        }
+        frame.render_stateful_widget(list, overlay_area, &mut list_state);
+    }
+
+    // The program picker and the command palette share one layout: a search line over a
+    // list of names with a detail in the accent color.
+    let picker = match (state.program_popup, state.palette_popup) {
+        (Some(popup), _) => Some((
+            "Open With",
+            popup.filter,
+            popup
+                .items
+                .into_iter()
+                .map(|item| (item.name, item.dir))
+                .collect::<Vec<_>>(),
+            popup.selected,
+        )),
+        (None, Some(popup)) => Some((
+            "Commands",
+            popup.filter,
+            popup
+                .items
+                .into_iter()
+                .map(|item| (item.name, item.keys))
+                .collect(),
+            popup.selected,
+        )),
+        (None, None) => None,
+    };
+    if let Some((title, filter, picker_items, selected)) = picker {
         let overlay_area = program_rect(frame.area());
         frame.render_widget(Clear, overlay_area);
         let block = Block::default()
             .borders(Borders::ALL)
-            .title("Open With")
+            .title(title)
             .style(base_style)
             .border_style(accent_style)
             .title_style(accent_style);
@@ -318,21 +701,24 @@ pub fn render(frame: &mut Frame, mut state: UiState<'_>) {
             .direction(Direction::Vertical)
             .constraints([Constraint::Length(1), Constraint::Min(1)])
             .split(inner);
-        let search = Paragraph::new(format!("Search: {}|", program_popup.filter))
-            .style(base_style);
+        let search = Paragraph::new(format!("Search: {filter}|")).style(base_style);
         frame.render_widget(search, sections[0]);
 
-        let items: Vec<ListItem<'static>> = program_popup
-            .items
+        let items: Vec<ListItem<'static>> = picker_items
             .iter()
-            .map(|item| ListItem::new(format!("{}  {}", item.name, item.path)))
+            .map(|(name, detail)| {
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{name}  ")),
+                    Span::styled(detail.clone(), accent_style),
+                ]))
+            })
             .collect();
         let list = List::new(items)
             .highlight_style(selection_style)
             .highlight_symbol("> ");
         let mut list_state = ListState::default();
-        if !program_popup.items.is_empty() {
-            let selected = program_popup.selected.min(program_popup.items.len() - 1);
+        if !picker_items.is_empty() {
+            let selected = selected.min(picker_items.len() - 1);
             list_state.select(Some(selected));
         }
         frame.render_stateful_widget(list, sections[1], &mut list_state);
@@ -341,59 +727,155 @@ pub fn render(frame: &mut Frame, mut state: UiState<'_>) {
     if let Some(input) = state.input {
         let overlay_area = input_rect(areas[1]);
         frame.render_widget(Clear, overlay_area);
-        let input_widget = Paragraph::new(input.value).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(input.title)
-                .style(base_style)
-                .border_style(accent_style)
-                .title_style(accent_style),
-        )
-        .style(base_style);
+        // Long values scroll so the caret stays inside the field.
+        let inner_width = overlay_area.width.saturating_sub(2);
+        let caret = input.cursor.map(|cursor| {
+            let column = UnicodeWidthStr::width(&input.value[..cursor]) as u16;
+            (column, column.saturating_sub(inner_width.saturating_sub(1)))
+        });
+        let scroll = caret.map_or(0, |(_, scroll)| scroll);
+        let input_widget = Paragraph::new(input.value)
+            .scroll((0, scroll))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(input.title)
+                    .style(base_style)
+                    .border_style(accent_style)
+                    .title_style(accent_style),
+            )
+            .style(base_style);
         frame.render_widget(input_widget, overlay_area);
+        if let Some((column, scroll)) = caret {
+            frame.set_cursor_position((overlay_area.x + 1 + column - scroll, overlay_area.y + 1));
+        }
     }
 }
 
-pub fn highlight_preview(preview: &Preview) -> Option<HighlightedText> {
+pub fn highlight_preview(preview: &Preview, config: &Config) -> Option<HighlightedText> {
     let PreviewData::Text(text) = &preview.data else {
         return None;
     };
-    let syntax_set = syntax_set();
-    let syntax = preview
+    let is_markdown = preview
         .path
         .extension()
         .and_then(|ext| ext.to_str())
-        .and_then(|ext| syntax_set.find_syntax_by_extension(ext))
+        .is_some_and(|ext| ["md", "markdown"].contains(&ext.to_ascii_lowercase().as_str()));
+    if is_markdown && config.preview.render_markdown {
+        let accent = if config.theme.is_monochrome() {
+            Style::default()
+        } else {
+            Style::default().fg(parse_color(&config.theme.accent))
+        };
+        return Some(markdown::render(text, config, accent));
+    }
+    let syntax = detect_syntax(&preview.path, text);
+    Some(Text::from(highlight_lines(
+        text,
+        syntax,
+        config,
+        config.preview.line_numbers,
+    )))
+}
+
+/// Highlights a Markdown code block by its fence's language name or extension.
+pub fn highlight_code(code: &str, language: &str, config: &Config) -> Vec<Line<'static>> {
+    let syntax_set = syntax_set();
+    let syntax = syntax_set
+        .find_syntax_by_token(language)
         .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
-    let mut highlighter = HighlightLines::new(syntax, theme());
+    highlight_lines(code, syntax, config, false)
+}
+
+fn highlight_lines(
+    text: &str,
+    syntax: &SyntaxReference,
+    config: &Config,
+    line_numbers: bool,
+) -> Vec<Line<'static>> {
+    let syntax_set = syntax_set();
+    let mut highlighter = HighlightLines::new(syntax, theme(&config.preview.syntax_theme));
+    let gutter_width = if line_numbers {
+        text.lines().count().max(1).to_string().len()
+    } else {
+        0
+    };
+    let gutter_style = Style::default().add_modifier(Modifier::DIM);
+    let monochrome = config.theme.is_monochrome();
     let mut lines = Vec::new();
-    for line in LinesWithEndings::from(text) {
+    for (index, line) in LinesWithEndings::from(text).enumerate() {
         let ranges = highlighter
             .highlight_line(line, syntax_set)
             .unwrap_or_default();
-        let spans: Vec<Span<'static>> = ranges
-            .into_iter()
-            .map(|(style, content)| Span::styled(content.to_string(), syntect_style(style)))
-            .collect();
+        let mut spans: Vec<Span<'static>> = Vec::with_capacity(ranges.len() + 1);
+        if gutter_width > 0 {
+            spans.push(Span::styled(
+                format!("{:>gutter_width$} ", index + 1),
+                gutter_style,
+            ));
+        }
+        spans.extend(ranges.into_iter().map(|(style, content)| {
+            let mut style = syntect_style(style);
+            if monochrome {
+                style.fg = None;
+            }
+            Span::styled(content.to_string(), style)
+        }));
         lines.push(Line::from(spans));
     }
-    Some(Text::from(lines))
+    lines
+}
+
+#[derive(Clone, Copy, Default)]
+struct ListColumns {
+    permissions: bool,
+    owner: bool,
+    size: bool,
+}
+
+#[derive(Clone, Copy, Default)]
+struct ColumnWidths {
+    permissions: usize,
+    owner: usize,
+    size: usize,
+}
+
+#[derive(Clone, Copy)]
+struct ListStyles {
+    folder: Style,
+    marked: Style,
+    broken_link: Style,
+    /// The part of a name the filter matched.
+    matched: Style,
+}
+
+/// The filtered, markable view of the current list.
+#[derive(Clone, Copy)]
+struct ListView<'a> {
+    indices: &'a [usize],
+    marked: &'a HashSet<PathBuf>,
+    highlight: Option<&'a FilterHighlight>,
 }
 
 fn list_items(
     config: &Config,
     entries: &[FileEntry],
-    indices: Option<&[usize]>,
-    show_permissions: bool,
-    show_owner: bool,
+    view: Option<ListView<'_>>,
+    columns: ListColumns,
     content_width: u16,
-    folder_style: Style,
+    styles: ListStyles,
 ) -> Vec<ListItem<'static>> {
-    let entries_view: Vec<&FileEntry> = match indices {
-        Some(indices) => indices.iter().filter_map(|&index| entries.get(index)).collect(),
+    let entries_view: Vec<&FileEntry> = match view {
+        Some(view) => view
+            .indices
+            .iter()
+            .filter_map(|&index| entries.get(index))
+            .collect(),
         None => entries.iter().collect(),
     };
-    let perm_width = if show_permissions {
+    let marked = view.map(|view| view.marked);
+    let highlight = view.and_then(|view| view.highlight);
+    let perm_width = if columns.permissions {
         entries_view
             .iter()
             .map(|entry| UnicodeWidthStr::width(entry.permissions.as_str()))
@@ -402,7 +884,7 @@ fn list_items(
     } else {
         0
     };
-    let owner_width = if show_owner {
+    let owner_width = if columns.owner {
         entries_view
             .iter()
             .map(|entry| UnicodeWidthStr::width(entry.owner.as_str()))
@@ -411,21 +893,33 @@ fn list_items(
     } else {
         0
     };
+    let size_width = if columns.size {
+        entries_view
+            .iter()
+            .map(|entry| UnicodeWidthStr::width(size_column(entry).as_str()))
+            .max()
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    let widths = ColumnWidths {
+        permissions: perm_width,
+        owner: owner_width,
+        size: size_width,
+    };
     entries_view
         .into_iter()
         .map(|entry| {
-            let label = entry_label(
-                config,
-                entry,
-                show_permissions,
-                show_owner,
-                content_width,
-                perm_width,
-                owner_width,
-            );
-            let item = ListItem::new(label);
-            if entry.is_dir {
-                item.style(folder_style)
+            let matched =
+                highlight.map_or_else(Vec::new, |highlight| highlight.ranges(&entry.name));
+            let label = entry_label(config, entry, columns, widths, content_width);
+            let item = ListItem::new(highlight_label(label, &matched, styles.matched));
+            if marked.is_some_and(|marked| marked.contains(&entry.path)) {
+                item.style(styles.marked)
+            } else if entry.broken_link {
+                item.style(styles.broken_link)
+            } else if entry.is_dir {
+                item.style(styles.folder)
             } else {
                 item
             }
@@ -436,44 +930,120 @@ fn list_items(
 fn entry_label(
     config: &Config,
     entry: &FileEntry,
-    show_permissions: bool,
-    show_owner: bool,
+    columns: ListColumns,
+    widths: ColumnWidths,
     content_width: u16,
-    perm_width: usize,
-    owner_width: usize,
-) -> String {
-    let icon = if entry.is_dir {
+) -> (String, Range<usize>) {
+    let icon = if entry.is_symlink {
+        &config.icons.symlink
+    } else if entry.is_dir {
         &config.icons.folder
     } else {
         &config.icons.file
     };
     let prefix = format!("{icon} ");
+    let name = match &entry.link_target {
+        Some(target) => format!("{} -> {}", entry.name, target.display()),
+        None => entry.name.clone(),
+    };
     let prefix_width = UnicodeWidthStr::width(prefix.as_str());
-    let mut right_text = String::new();
-    if show_permissions {
-        right_text.push_str(&pad_to_width(&entry.permissions, perm_width));
+    let mut right_parts = Vec::new();
+    if columns.permissions {
+        right_parts.push(pad_to_width(&entry.permissions, widths.permissions));
     }
-    if show_owner {
-        if !right_text.is_empty() {
-            right_text.push_str("  ");
-        }
-        right_text.push_str(&pad_to_width(&entry.owner, owner_width));
+    if columns.owner {
+        right_parts.push(pad_to_width(&entry.owner, widths.owner));
     }
-    let right_width = UnicodeWidthStr::width(right_text.as_str());
+    if columns.size {
+        right_parts.push(pad_left_to_width(&size_column(entry), widths.size));
+    }
+    let mut right_text = right_parts.join("  ");
+    let mut right_width = UnicodeWidthStr::width(right_text.as_str());
     let content_width = content_width as usize;
     if content_width == 0 {
-        return format!("{prefix}{}", entry.name);
+        let shown = prefix.len()..prefix.len() + entry.name.len();
+        return (format!("{prefix}{name}"), shown);
+    }
+    // Drop the columns entirely rather than pushing the row past the panel edge.
+    if prefix_width + right_width + 2 >= content_width {
+        right_text.clear();
+        right_width = 0;
     }
     let gap = if right_text.is_empty() { 0 } else { 2 };
     let available_name_width = content_width.saturating_sub(prefix_width + right_width + gap);
-    let name = truncate_with_ellipsis(&entry.name, available_name_width);
+    let truncated = truncate_with_ellipsis(&name, available_name_width);
+    // Matches can only be shown in the part of the name before the ellipsis.
+    let kept = if name.starts_with(&truncated) {
+        truncated.len()
+    } else {
+        truncated.len() - 3
+    };
+    let shown = prefix.len()..prefix.len() + kept.min(entry.name.len());
+    let name = truncated;
     if right_text.is_empty() {
-        return format!("{prefix}{name}");
+        return (format!("{prefix}{name}"), shown);
     }
     let name_width = UnicodeWidthStr::width(name.as_str());
     let padding_width = content_width.saturating_sub(prefix_width + name_width + right_width);
     let padding = " ".repeat(padding_width);
-    format!("{prefix}{name}{padding}{right_text}")
+    (format!("{prefix}{name}{padding}{right_text}"), shown)
+}
+
+/// Styles the `matched` byte ranges of the entry name, which sits at `name` in `text`.
+fn highlight_label(
+    (text, name): (String, Range<usize>),
+    matched: &[Range<usize>],
+    style: Style,
+) -> Line<'static> {
+    if matched.is_empty() {
+        return Line::from(text);
+    }
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for range in matched {
+        let start = (name.start + range.start).min(name.end);
+        let end = (name.start + range.end).min(name.end);
+        if start >= end {
+            break;
+        }
+        spans.push(Span::raw(text[last..start].to_string()));
+        spans.push(Span::styled(text[start..end].to_string(), style));
+        last = end;
+    }
+    spans.push(Span::raw(text[last..].to_string()));
+    Line::from(spans)
+}
+
+fn size_column(entry: &FileEntry) -> String {
+    if entry.is_dir {
+        return match entry.child_count {
+            Some(ChildCount::Counted(count)) => count.to_string(),
+            Some(ChildCount::Unreadable) => "!".to_string(),
+            None => "-".to_string(),
+        };
+    }
+    match entry.size {
+        Some(size) => human_size(size),
+        None => "-".to_string(),
+    }
+}
+
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{value:.1}{}", UNITS[unit])
+    } else {
+        format!("{value:.0}{}", UNITS[unit])
+    }
 }
 
 fn preview_title(preview: &Preview) -> (String, bool) {
@@ -483,20 +1053,214 @@ fn preview_title(preview: &Preview) -> (String, bool) {
         .and_then(|name| name.to_str())
         .unwrap_or("Preview");
     let mut title = name.to_string();
+    if let Some(link) = &preview.link {
+        title.push_str(&format!(" -> {}", link.target.display()));
+    }
     let mismatch = matches!(preview.mismatch, Some(MismatchStatus::Mismatch { .. }));
     if mismatch {
         title.push_str(" !");
     }
-    (title, mismatch)
+    let broken_link = preview.link.as_ref().is_some_and(|link| link.broken);
+    (title, mismatch || broken_link)
+}
+
+/// The hops of a symlink chain above the preview, with a broken end or a loop flagged.
+/// A single working link is already described by the title.
+fn link_chain_lines(link: &LinkInfo, accent: Style, warning: Style) -> Vec<Line<'static>> {
+    if link.chain.len() < 2 && !link.broken {
+        return Vec::new();
+    }
+    let last = link.chain.len().saturating_sub(1);
+    let mut lines: Vec<Line<'static>> = link
+        .chain
+        .iter()
+        .enumerate()
+        .map(|(index, hop)| {
+            let note = if index < last {
+                ""
+            } else if link.cycle {
+                "  (loop: already passed)"
+            } else if link.broken {
+                "  (missing)"
+            } else {
+                ""
+            };
+            let style = if note.is_empty() { accent } else { warning };
+            Line::from(vec![
+                Span::styled("-> ", accent),
+                Span::styled(format!("{}{note}", hop.display()), style),
+            ])
+        })
+        .collect();
+    lines.push(Line::default());
+    lines
 }
 
-fn preview_text(preview: &Preview) -> String {
+fn preview_text(
+    preview: &Preview,
+    config: &Config,
+    folder_style: Style,
+    width: u16,
+) -> Text<'static> {
     match &preview.data {
-        PreviewData::Text(text) => text.clone(),
-        PreviewData::Image { width, height } => format!("image ({}x{})", width, height),
-        PreviewData::Binary { size } => format!("binary ({} bytes)", size),
-        PreviewData::Empty => String::new(),
+        PreviewData::Text(text) => Text::from(text.clone()),
+        PreviewData::Image { width, height } => Text::from(format!("image ({}x{})", width, height)),
+        PreviewData::Binary { size } => Text::from(format!("binary ({} bytes)", size)),
+        PreviewData::Directory(entries) => directory_text(entries, config, folder_style),
+        PreviewData::Unreadable(reason) => Text::from(Span::styled(
+            format!("cannot read directory: {reason}"),
+            Style::default().add_modifier(Modifier::DIM),
+        )),
+        PreviewData::Archive(entries) => archive_text(entries, folder_style),
+        PreviewData::Table { headers, rows } => table_text(headers, rows, width as usize),
+        PreviewData::Empty => Text::default(),
+    }
+}
+
+/// Lays out CSV rows as aligned columns. When the table is wider than the pane the
+/// widest columns are narrowed first and their cells truncated.
+fn table_text(headers: &[String], rows: &[Vec<String>], width: usize) -> Text<'static> {
+    const SEPARATOR: &str = "  ";
+    const MIN_COLUMN_WIDTH: usize = 3;
+    let columns = std::iter::once(headers)
+        .chain(rows.iter().map(Vec::as_slice))
+        .map(<[String]>::len)
+        .max()
+        .unwrap_or(0);
+    if columns == 0 {
+        return Text::from(Span::styled(
+            "empty",
+            Style::default().add_modifier(Modifier::DIM),
+        ));
+    }
+    let mut widths = vec![0; columns];
+    for row in std::iter::once(headers).chain(rows.iter().map(Vec::as_slice)) {
+        for (column, cell) in row.iter().enumerate() {
+            widths[column] = widths[column].max(UnicodeWidthStr::width(cell.as_str()));
+        }
+    }
+    let available = width.saturating_sub(SEPARATOR.len() * (columns - 1));
+    while widths.iter().sum::<usize>() > available {
+        let Some(widest) = widths.iter_mut().max() else {
+            break;
+        };
+        if *widest <= MIN_COLUMN_WIDTH {
+            break;
+        }
+        *widest -= 1;
+    }
+    let format_row = |row: &[String]| {
+        widths
+            .iter()
+            .enumerate()
+            .map(|(column, &width)| {
+                let cell = row.get(column).map(String::as_str).unwrap_or_default();
+                // Embedded line breaks would split the row across lines.
+                let cell = cell.replace(['\r', '\n'], " ");
+                pad_to_width(&truncate_with_ellipsis(&cell, width), width)
+            })
+            .collect::<Vec<_>>()
+            .join(SEPARATOR)
+            .trim_end()
+            .to_string()
+    };
+    let mut lines = vec![Line::from(Span::styled(
+        format_row(headers),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    lines.extend(rows.iter().map(|row| Line::from(format_row(row))));
+    if rows.len() >= TABLE_PREVIEW_ROWS {
+        lines.push(Line::from("…"));
+    }
+    Text::from(lines)
+}
+
+fn archive_text(entries: &[ArchiveEntry], folder_style: Style) -> Text<'static> {
+    let dim = Style::default().add_modifier(Modifier::DIM);
+    if entries.is_empty() {
+        return Text::from(Span::styled("empty archive", dim));
+    }
+    let mut lines: Vec<Line<'static>> = entries
+        .iter()
+        .map(|entry| {
+            let depth = entry.path.matches('/').count();
+            let name = entry.path.rsplit('/').next().unwrap_or_default();
+            let indent = "  ".repeat(depth);
+            if entry.is_dir {
+                Line::from(Span::styled(format!("{indent}{name}/"), folder_style))
+            } else {
+                Line::from(vec![
+                    Span::raw(format!("{indent}{name}  ")),
+                    Span::styled(human_size(entry.size), dim),
+                ])
+            }
+        })
+        .collect();
+    if entries.len() >= ARCHIVE_PREVIEW_LIMIT {
+        lines.push(Line::from("…"));
+    }
+    Text::from(lines)
+}
+
+fn directory_text(entries: &[FileEntry], config: &Config, folder_style: Style) -> Text<'static> {
+    if entries.is_empty() {
+        return Text::from(Span::styled(
+            "empty",
+            Style::default().add_modifier(Modifier::DIM),
+        ));
+    }
+    let mut lines: Vec<Line<'static>> = entries
+        .iter()
+        .map(|entry| {
+            let icon = if entry.is_dir {
+                &config.icons.folder
+            } else {
+                &config.icons.file
+            };
+            let line = Line::from(format!("{icon} {}", entry.name));
+            if entry.is_dir {
+                line.style(folder_style)
+            } else {
+                line
+            }
+        })
+        .collect();
+    if entries.len() >= DIRECTORY_PREVIEW_LIMIT {
+        lines.push(Line::from("…"));
+    }
+    Text::from(lines)
+}
+
+/// Right side of the status line: clipboard, filter, hidden files and list position.
+fn list_summary(state: &UiState<'_>) -> Line<'static> {
+    let shown = state.current_indices.len();
+    let mut parts = Vec::new();
+    if let Some(clipboard) = &state.clipboard {
+        parts.push(clipboard.clone());
     }
+    let filter_label = if state.sticky_filter {
+        "sticky filter"
+    } else {
+        "filter"
+    };
+    if !state.filter.is_empty() {
+        parts.push(format!(
+            "{filter_label} \"{}\" {shown} of {}",
+            state.filter,
+            state.current.len()
+        ));
+    } else if state.sticky_filter {
+        parts.push(filter_label.to_string());
+    }
+    let hidden = match (state.show_hidden, state.hidden_last) {
+        (false, _) => "off",
+        (true, false) => "on",
+        (true, true) => "last",
+    };
+    parts.push(format!("hidden: {hidden}"));
+    let position = if shown == 0 { 0 } else { state.selected + 1 };
+    parts.push(format!("{position}/{shown}"));
+    Line::from(parts.join("  "))
 }
 
 fn metadata_text(
@@ -504,33 +1268,72 @@ fn metadata_text(
     metadata: Option<&FileMetadata>,
     show_permissions: bool,
     show_dates: bool,
+    relative_dates: bool,
     show_owner: bool,
+    show_size: bool,
 ) -> String {
     let Some(metadata) = metadata else {
         return String::new();
     };
     let icons = &config.metadata_bar.icons;
     let mut parts = Vec::new();
+    if let Some(image) = &metadata.image {
+        let mut part = format!("{}x{} {}", image.width, image.height, image.format);
+        if let Some(frames) = image.frames {
+            part.push_str(&format!(", {frames} frames"));
+        }
+        parts.push(part);
+    }
+    if let Some(detected) = &metadata.detected {
+        parts.push(format!("type: {detected}"));
+    }
     if show_permissions {
         parts.push(format!("{} {}", icons.permissions, metadata.permissions));
     }
     if show_owner {
         parts.push(format!("{} {}", icons.owner, metadata.owner));
     }
+    if show_size {
+        let size = metadata
+            .size
+            .map(human_size)
+            .unwrap_or_else(|| "-".to_string());
+        parts.push(format!("{} {}", icons.size, size));
+    }
     if show_dates {
-        if let Some(created) = &metadata.created {
-            parts.push(format!("{} {}", icons.created, created));
-        }
-        if let Some(modified) = &metadata.modified {
-            parts.push(format!("{} {}", icons.modified, modified));
-        }
-        if let Some(accessed) = &metadata.accessed {
-            parts.push(format!("{} {}", icons.accessed, accessed));
+        let configured = config.metadata_bar.date_format.as_str();
+        let date_format = match (relative_dates, configured == RELATIVE_DATE_FORMAT) {
+            (false, _) => configured,
+            (true, false) => RELATIVE_DATE_FORMAT,
+            (true, true) => RFC3339_DATE_FORMAT,
+        };
+        let dates = [
+            (&icons.created, metadata.created),
+            (&icons.modified, metadata.modified),
+            (&icons.accessed, metadata.accessed),
+        ];
+        for (icon, time) in dates {
+            if let Some(formatted) = time.and_then(|time| format_time(time, date_format)) {
+                parts.push(format!("{icon} {formatted}"));
+            }
         }
     }
     parts.join("  ")
 }
 
+/// Text in the first row inside a panel's border, for lists with nothing to show.
+fn render_placeholder(frame: &mut Frame, area: Rect, text: &str, style: Style) {
+    let inner = area.inner(Margin::new(1, 1));
+    if inner.is_empty() {
+        return;
+    }
+    let row = Rect { height: 1, ..inner };
+    frame.render_widget(
+        Paragraph::new(text.to_string()).style(style.add_modifier(Modifier::ITALIC)),
+        row,
+    );
+}
+
 fn input_rect(area: Rect) -> Rect {
     let width = (area.width * 3 / 4).max(10u16).min(area.width);
     let height = 3u16.min(area.height.max(1u16));
@@ -579,6 +1382,18 @@ fn parse_color(value: &str) -> Color {
                 return Color::Rgb(r, g, b);
             }
         }
+        // `#rgb` is short for `#rrggbb`.
+        let digits: Option<Vec<u8>> = hex
+            .chars()
+            .map(|ch| ch.to_digit(16).map(|digit| digit as u8 * 17))
+            .collect();
+        if let Some([r, g, b]) = digits.as_deref() {
+            return Color::Rgb(*r, *g, *b);
+        }
+    }
+    // ANSI 256-color palette index.
+    if let Ok(index) = value.parse::<u8>() {
+        return Color::Indexed(index);
     }
     match value.to_ascii_lowercase().as_str() {
         "black" => Color::Black,
@@ -627,6 +1442,14 @@ fn pad_to_width(value: &str, width: usize) -> String {
     out
 }
 
+fn pad_left_to_width(value: &str, width: usize) -> String {
+    let value_width = UnicodeWidthStr::width(value);
+    if value_width >= width {
+        return value.to_string();
+    }
+    format!("{}{value}", " ".repeat(width - value_width))
+}
+
 fn truncate_with_ellipsis(value: &str, max_width: usize) -> String {
     if UnicodeWidthStr::width(value) <= max_width {
         return value.to_string();
@@ -657,13 +1480,41 @@ fn syntax_set() -> &'static SyntaxSet {
     SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
 }
 
-fn theme() -> &'static Theme {
-    static THEME: OnceLock<Theme> = OnceLock::new();
-    THEME.get_or_init(|| {
-        let set = ThemeSet::load_defaults();
-        set.themes
-            .get("base16-ocean.dark")
-            .cloned()
-            .unwrap_or_else(|| set.themes.values().next().cloned().unwrap())
-    })
+/// Picks a syntax by extension, then by the whole file name (`Makefile`, `.bashrc`),
+/// then by the first line (shebangs, modelines), falling back to plain text.
+fn detect_syntax(path: &Path, text: &str) -> &'static SyntaxReference {
+    let syntax_set = syntax_set();
+    let file_name = path.file_name().and_then(|name| name.to_str());
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| syntax_set.find_syntax_by_extension(ext))
+        .or_else(|| file_name.and_then(|name| syntax_set.find_syntax_by_extension(name)))
+        .or_else(|| file_name.and_then(|name| syntax_set.find_syntax_by_token(name)))
+        .or_else(|| {
+            text.lines()
+                .next()
+                .and_then(|line| syntax_set.find_syntax_by_first_line(line))
+        })
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text())
+}
+
+const DEFAULT_SYNTAX_THEME: &str = "base16-ocean.dark";
+
+fn theme_set() -> &'static ThemeSet {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    THEME_SET.get_or_init(ThemeSet::load_defaults)
+}
+
+pub fn syntax_theme_exists(name: &str) -> bool {
+    theme_set().themes.contains_key(name)
+}
+
+/// Looks up a bundled syntect theme, falling back to the default for unknown names.
+fn theme(name: &str) -> &'static Theme {
+    let themes = &theme_set().themes;
+    themes
+        .get(name)
+        .or_else(|| themes.get(DEFAULT_SYNTAX_THEME))
+        .or_else(|| themes.values().next())
+        .expect("syntect bundles default themes")
 }
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use tokio::fs;
+
+/// Number of saves started so far. Saves run one at a time and one that is no longer the
+/// latest is dropped, so an older state never replaces a newer one.
+static SAVES: Mutex<u64> = Mutex::new(0);
+
+/// View toggles as last left, restored at startup. Until the first toggle is saved the
+/// config provides the defaults, as it does for fields missing from the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewState {
+    pub show_hidden: bool,
+    pub hidden_last: bool,
+    pub show_metadata: bool,
+    pub show_permissions: bool,
+    pub show_dates: bool,
+    pub relative_dates: bool,
+    pub show_owner: bool,
+    pub show_size: bool,
+    pub show_list_permissions: bool,
+    pub show_list_owner: bool,
+    pub show_list_size: bool,
+    pub preview_wrap: bool,
+}
+
+impl ViewState {
+    /// The saved state, with `defaults` for the fields it lacks; `None` when nothing was
+    /// saved yet or the file is unreadable.
+    pub async fn load(defaults: Self) -> Option<Self> {
+        let content = fs::read_to_string(default_view_state_path()).await.ok()?;
+        let saved: toml::Table = toml::from_str(&content).ok()?;
+        let mut view = toml::Table::try_from(defaults).ok()?;
+        view.extend(saved);
+        view.try_into().ok()
+    }
+
+    pub fn save_task(self) -> impl Future<Output = io::Result<()>> + Send + 'static {
+        let path = default_view_state_path();
+        let save = {
+            let mut saves = SAVES.lock().unwrap_or_else(PoisonError::into_inner);
+            *saves += 1;
+            *saves
+        };
+        async move {
+            tokio::task::spawn_blocking(move || self.save(&path, save))
+                .await
+                .map_err(io::Error::other)?
+        }
+    }
+
+    /// Writes a temporary file next to `path` and renames it over `path`, so the file is
+    /// never seen half written.
+    fn save(&self, path: &Path, save: u64) -> io::Result<()> {
+        let saves = SAVES.lock().unwrap_or_else(PoisonError::into_inner);
+        if *saves != save {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        let temp = path.with_extension("toml.tmp");
+        std::fs::write(&temp, content)?;
+        std::fs::rename(&temp, path)
+    }
+}
+
+fn default_view_state_path() -> PathBuf {
+    if let Some(dir) = dirs::config_dir() {
+        return dir.join("tfm").join("view.toml");
+    }
+    if let Some(home) = dirs::home_dir() {
+        return home.join(".tfm.view.toml");
+    }
+    PathBuf::from("view.toml")
+}